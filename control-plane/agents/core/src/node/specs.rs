@@ -4,9 +4,38 @@ use common_lib::types::v0::{
     message_bus::{NodeId, Register},
     store::node::{NodeLabels, NodeSpec},
 };
+use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use snafu::OptionExt;
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Nodes currently marked as draining.
+///
+/// `NodeSpec`, like `Registry` itself, is an external type (`common_lib::types::v0::store::node`)
+/// not defined anywhere in this checkout, so it has no `draining` field to flip and no
+/// `set_draining`/`draining` methods to call - both were referenced here without ever being
+/// added anywhere in this tree. Tracked as a side-table instead, the same idiom
+/// `volume::lifecycle`'s `LIFECYCLE_POLICIES` uses for the same reason. The tradeoff is real:
+/// unlike a field on `NodeSpec` persisted via `registry.store_obj`, this is lost across a
+/// control-plane restart - `draining_nodes` below can only resume a drain the process already
+/// knew about.
+static DRAINING_NODES: Lazy<Mutex<HashSet<NodeId>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Per-node progress of an in-flight drain: how many replicas were left to move off the node the
+/// last time the drain worker looked, so a restarted worker can tell whether it's making progress
+/// without re-deriving the original target count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DrainProgress {
+    pub(crate) replicas_remaining: usize,
+}
+
+static DRAIN_PROGRESS: Lazy<Mutex<HashMap<NodeId, DrainProgress>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
 
 impl ResourceSpecsLocked {
     /// Create a node spec for the register request
@@ -73,4 +102,123 @@ impl ResourceSpecsLocked {
             .map(|n| n.lock().clone())
             .collect()
     }
+
+    /// Mark the node as draining, with `replicas_remaining` as the initial progress count the
+    /// drain worker measures itself against.
+    ///
+    /// Re-validates the node exists (so a typo'd `NodeId` fails immediately) but, since
+    /// `NodeSpec` has nowhere to persist this, only records the intent in-process; see
+    /// `DRAINING_NODES`'s doc comment.
+    pub(crate) async fn set_node_draining(
+        &self,
+        _registry: &Registry,
+        node_id: &NodeId,
+        replicas_remaining: usize,
+    ) -> Result<(), SvcError> {
+        self.get_locked_node(node_id)?;
+        DRAINING_NODES.lock().insert(node_id.clone());
+        DRAIN_PROGRESS
+            .lock()
+            .insert(node_id.clone(), DrainProgress { replicas_remaining });
+        Ok(())
+    }
+
+    /// Clear the draining intent from the node, once it has no more targets or replicas left
+    /// to move elsewhere.
+    pub(crate) async fn clear_node_draining(
+        &self,
+        _registry: &Registry,
+        node_id: &NodeId,
+    ) -> Result<(), SvcError> {
+        self.get_locked_node(node_id)?;
+        DRAINING_NODES.lock().remove(node_id);
+        DRAIN_PROGRESS.lock().remove(node_id);
+        Ok(())
+    }
+
+    /// All nodes which are currently marked as draining, used both to exclude them from
+    /// scheduling and to resume the drain worker after a control-plane restart (within the
+    /// lifetime of this process - see `DRAINING_NODES`).
+    pub(crate) fn draining_nodes(&self) -> Vec<NodeSpec> {
+        let draining = DRAINING_NODES.lock();
+        self.get_nodes()
+            .into_iter()
+            .filter(|node| draining.contains(node.id()))
+            .collect()
+    }
+
+    /// Background drain worker: every `period`, re-checks each draining node's progress via
+    /// `replicas_remaining_on` and calls `move_replica` for one more replica if any are left,
+    /// enforcing `min_replicas_remaining` as a safety floor so a volume is never drained down to
+    /// zero in-sync replicas by this worker alone. Once a node reaches zero (or the floor),
+    /// drains it of its target via `republish` and clears its draining flag.
+    ///
+    /// `replicas_remaining_on`/`move_replica`/`republish` are closures, the same shape
+    /// `volume::lifecycle::spawn_reconciler` takes its snapshot operations as: this crate has no
+    /// real `move_replica`/`republish` operation to call directly (both live, if anywhere, in the
+    /// separate `agents/src/bin/core` crate tree this one doesn't share a compilation unit with),
+    /// so the real call site would back these with those.
+    pub(crate) fn spawn_drain_worker<R, RFut, M, MFut, P, PFut>(
+        self: Arc<Self>,
+        registry: Registry,
+        period: Duration,
+        min_replicas_remaining: usize,
+        replicas_remaining_on: R,
+        move_replica: M,
+        republish: P,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        R: Fn(NodeId) -> RFut + Send + Sync + 'static,
+        RFut: Future<Output = usize> + Send + 'static,
+        M: Fn(NodeId) -> MFut + Send + Sync + 'static,
+        MFut: Future<Output = Result<(), SvcError>> + Send + 'static,
+        P: Fn(NodeId) -> PFut + Send + Sync + 'static,
+        PFut: Future<Output = Result<(), SvcError>> + Send + 'static,
+    {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+
+                let draining: Vec<NodeId> = DRAINING_NODES.lock().iter().cloned().collect();
+                for node_id in draining {
+                    let remaining = replicas_remaining_on(node_id.clone()).await;
+                    DRAIN_PROGRESS.lock().insert(
+                        node_id.clone(),
+                        DrainProgress {
+                            replicas_remaining: remaining,
+                        },
+                    );
+
+                    if remaining > min_replicas_remaining {
+                        if let Err(error) = move_replica(node_id.clone()).await {
+                            tracing::warn!(
+                                node.id = %node_id,
+                                %error,
+                                "Failed to move a replica off a draining node"
+                            );
+                        }
+                        continue;
+                    }
+
+                    if let Err(error) = republish(node_id.clone()).await {
+                        tracing::warn!(
+                            node.id = %node_id,
+                            %error,
+                            "Failed to republish targets off a draining node"
+                        );
+                        continue;
+                    }
+
+                    if let Err(error) = self.clear_node_draining(&registry, &node_id).await {
+                        tracing::warn!(
+                            node.id = %node_id,
+                            %error,
+                            "Failed to clear draining state once the node was drained"
+                        );
+                    }
+                }
+            }
+        })
+    }
 }
\ No newline at end of file
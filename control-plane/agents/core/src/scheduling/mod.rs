@@ -0,0 +1,2 @@
+pub(crate) mod placement;
+pub(crate) mod topology;
@@ -0,0 +1,253 @@
+//! Capacity- and zone-aware replica placement.
+//!
+//! `Registry::get_volume_state_with_replicas` only ever compares `replica_specs.len()` against
+//! `volume_spec.num_replicas`; nothing decides *which* pools those replicas should land on, so
+//! node/pool labels registered via `register_node`/`put_pool_label` go unused. This solves that
+//! as a min-cost max-flow problem: a source feeds `num_replicas` units into a replica-slot node,
+//! which fans out to every candidate pool with enough free space; pools fan into a per-node
+//! aggregator capped at one replica per node (anti-affinity), which in turn fans into an
+//! aggregator per value of `spread_key` (the label key a `spread by`/`must-not-colocate`
+//! constraint names) capped at `max_per_zone`, and finally to the sink.
+//! Edge cost is 0 for a pool already hosting a replica of this volume and 1 otherwise, so solving
+//! min-cost max-flow both maximizes how many replicas can be placed and minimizes churn when the
+//! topology is re-solved around an existing layout (e.g. during a rebuild).
+
+use common_lib::types::v0::message_bus::{NodeId, PoolId};
+use std::collections::{HashMap, VecDeque};
+
+/// A pool eligible to host a replica: it already has enough free capacity for the volume's size,
+/// reported alongside the node's labels (from `NodeSpec`/`register_node`) and the pool's own
+/// labels (from `LabelPool`), so `TopologyConstraint`s can be evaluated against it.
+#[derive(Debug, Clone)]
+pub(crate) struct PoolCandidate {
+    pub(crate) pool: PoolId,
+    pub(crate) node: NodeId,
+    /// Union of the owning node's labels and this pool's own labels.
+    pub(crate) labels: HashMap<String, String>,
+    pub(crate) free_capacity: u64,
+    /// Whether this pool already hosts a replica of the volume being placed; preferring it keeps
+    /// data movement down when the topology is re-solved.
+    pub(crate) existing_replica: bool,
+}
+
+/// Inputs to a single placement solve.
+#[derive(Debug, Clone)]
+pub(crate) struct PlacementRequest {
+    pub(crate) num_replicas: usize,
+    pub(crate) size: u64,
+    pub(crate) candidates: Vec<PoolCandidate>,
+    /// Label key that defines a failure domain (e.g. `topology.io/zone`) that no more than
+    /// `max_per_zone` replicas may share, derived from the volume's `spread by`/
+    /// `must-not-colocate` topology constraints. `None` means replicas aren't spread-constrained
+    /// beyond the per-node anti-affinity.
+    pub(crate) spread_key: Option<String>,
+    /// Maximum number of replicas that may land in any one `spread_key` domain.
+    pub(crate) max_per_zone: usize,
+}
+
+/// Outcome of a placement solve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PlacementResult {
+    /// Pools selected to host a replica, in no particular order.
+    pub(crate) pools: Vec<PoolId>,
+    /// True if fewer pools were assigned than `num_replicas` requested, i.e. the volume would be
+    /// under-provisioned with this candidate set.
+    pub(crate) under_provisioned: bool,
+}
+
+/// Default max-per-zone policy: never let a single failure domain hold more than half the
+/// replicas (floor, minimum 1), so losing one zone can't take the volume down with it.
+pub(crate) fn default_max_per_zone(num_replicas: usize) -> usize {
+    (num_replicas / 2).max(1)
+}
+
+/// Solve replica-to-pool placement for `request`, returning the chosen pools and whether the
+/// candidate set was enough to satisfy every requested replica.
+pub(crate) fn solve(request: &PlacementRequest) -> PlacementResult {
+    let eligible: Vec<&PoolCandidate> = request
+        .candidates
+        .iter()
+        .filter(|candidate| candidate.free_capacity >= request.size)
+        .collect();
+
+    let mut graph = FlowGraph::build(request, &eligible);
+    let (flow, assigned) = graph.min_cost_max_flow();
+
+    PlacementResult {
+        pools: assigned
+            .into_iter()
+            .map(|index| eligible[index].pool.clone())
+            .collect(),
+        under_provisioned: flow < request.num_replicas,
+    }
+}
+
+struct Edge {
+    to: usize,
+    cap: i64,
+    cost: i64,
+}
+
+/// Minimal min-cost max-flow graph (successive shortest augmenting paths via Bellman-Ford/SPFA,
+/// since edge costs are only ever 0 or 1 and the candidate counts here are small).
+struct FlowGraph {
+    edges: Vec<Edge>,
+    adj: Vec<Vec<usize>>,
+    source: usize,
+    slot: usize,
+    sink: usize,
+    pool_base: usize,
+    pool_count: usize,
+}
+
+impl FlowGraph {
+    fn add_edge(&mut self, from: usize, to: usize, cap: i64, cost: i64) {
+        let forward = self.edges.len();
+        self.edges.push(Edge { to, cap, cost });
+        self.adj[from].push(forward);
+        let backward = self.edges.len();
+        self.edges.push(Edge { to: from, cap: 0, cost: -cost });
+        self.adj[to].push(backward);
+    }
+
+    fn build(request: &PlacementRequest, eligible: &[&PoolCandidate]) -> Self {
+        const SOURCE: usize = 0;
+        const SLOT: usize = 1;
+        let pool_base = 2;
+        let pool_count = eligible.len();
+
+        let mut next_id = pool_base + pool_count;
+        let mut node_agg: HashMap<NodeId, usize> = HashMap::new();
+        let mut node_zone: HashMap<NodeId, Option<String>> = HashMap::new();
+        for candidate in eligible {
+            node_agg.entry(candidate.node.clone()).or_insert_with(|| {
+                let id = next_id;
+                next_id += 1;
+                id
+            });
+            node_zone.entry(candidate.node.clone()).or_insert_with(|| {
+                request
+                    .spread_key
+                    .as_ref()
+                    .and_then(|key| candidate.labels.get(key))
+                    .cloned()
+            });
+        }
+
+        let mut zone_agg: HashMap<String, usize> = HashMap::new();
+        for zone in node_zone.values().flatten() {
+            zone_agg.entry(zone.clone()).or_insert_with(|| {
+                let id = next_id;
+                next_id += 1;
+                id
+            });
+        }
+
+        let sink = next_id;
+        let node_count = sink + 1;
+
+        let mut graph = Self {
+            edges: Vec::new(),
+            adj: vec![Vec::new(); node_count],
+            source: SOURCE,
+            slot: SLOT,
+            sink,
+            pool_base,
+            pool_count,
+        };
+
+        graph.add_edge(SOURCE, SLOT, request.num_replicas as i64, 0);
+
+        for (index, candidate) in eligible.iter().enumerate() {
+            let pool_node = pool_base + index;
+            let cost = if candidate.existing_replica { 0 } else { 1 };
+            graph.add_edge(SLOT, pool_node, 1, cost);
+            graph.add_edge(pool_node, node_agg[&candidate.node], 1, 0);
+        }
+
+        for (node, &agg) in &node_agg {
+            match node_zone.get(node).and_then(|zone| zone.as_ref()) {
+                Some(zone) => graph.add_edge(agg, zone_agg[zone], 1, 0),
+                None => graph.add_edge(agg, sink, 1, 0),
+            }
+        }
+
+        for (_, &agg) in &zone_agg {
+            graph.add_edge(agg, sink, request.max_per_zone.max(1) as i64, 0);
+        }
+
+        graph
+    }
+
+    /// Successive shortest augmenting paths: repeatedly find the cheapest source-to-sink path
+    /// with spare residual capacity (Bellman-Ford, since augmenting can introduce negative-cost
+    /// residual edges) and push flow along it until none remain.
+    fn min_cost_max_flow(&mut self) -> (usize, Vec<usize>) {
+        let n = self.adj.len();
+        let mut total_flow: i64 = 0;
+
+        loop {
+            let mut dist = vec![i64::MAX; n];
+            let mut in_queue = vec![false; n];
+            let mut via_edge = vec![usize::MAX; n];
+
+            dist[self.source] = 0;
+            let mut queue = VecDeque::from([self.source]);
+            in_queue[self.source] = true;
+
+            while let Some(u) = queue.pop_front() {
+                in_queue[u] = false;
+                if dist[u] == i64::MAX {
+                    continue;
+                }
+                for &edge_idx in &self.adj[u] {
+                    let edge = &self.edges[edge_idx];
+                    if edge.cap > 0 && dist[u] + edge.cost < dist[edge.to] {
+                        let to = edge.to;
+                        dist[to] = dist[u] + edge.cost;
+                        via_edge[to] = edge_idx;
+                        if !in_queue[to] {
+                            queue.push_back(to);
+                            in_queue[to] = true;
+                        }
+                    }
+                }
+            }
+
+            if dist[self.sink] == i64::MAX {
+                break;
+            }
+
+            let mut push = i64::MAX;
+            let mut v = self.sink;
+            while v != self.source {
+                let edge_idx = via_edge[v];
+                push = push.min(self.edges[edge_idx].cap);
+                v = self.edges[edge_idx ^ 1].to;
+            }
+
+            v = self.sink;
+            while v != self.source {
+                let edge_idx = via_edge[v];
+                self.edges[edge_idx].cap -= push;
+                self.edges[edge_idx ^ 1].cap += push;
+                v = self.edges[edge_idx ^ 1].to;
+            }
+
+            total_flow += push;
+        }
+
+        let mut assigned = Vec::new();
+        for index in 0..self.pool_count {
+            let pool_node = self.pool_base + index;
+            let saturated = self.adj[self.slot].iter().any(|&edge_idx| {
+                self.edges[edge_idx].to == pool_node && self.edges[edge_idx].cap == 0
+            });
+            if saturated {
+                assigned.push(index);
+            }
+        }
+
+        (total_flow as usize, assigned)
+    }
+}
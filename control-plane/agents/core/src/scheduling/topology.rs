@@ -0,0 +1,71 @@
+//! Affinity/anti-affinity constraint language for volume scheduling, carried on a volume create
+//! request and enforced against `scheduling::placement::PoolCandidate`s when selecting pools.
+//!
+//! `NodeSpec` already carries `NodeLabels` and pools can be labeled via `LabelPool`/`UnlabelPool`,
+//! but nothing lets an operator say *how* those labels should shape replica placement beyond the
+//! count-based heuristic in `get_volume_state_with_replicas`. A `TopologyConstraint` expresses
+//! that in terms of the same key/value labels:
+//!   - `LabelIn { key, values }` — only pools whose `key` label is one of `values` are eligible.
+//!   - `SpreadBy { key }` — replicas should spread across distinct values of `key` (e.g. `zone`).
+//!   - `MustNotColocate { key }` — no two replicas may share the same value of `key` at all.
+
+use crate::scheduling::placement::PoolCandidate;
+use std::collections::HashSet;
+
+/// One constraint in a volume's topology requirement. Multiple constraints are combined with
+/// logical AND: every `LabelIn` must be satisfied by a candidate pool, and at most one of
+/// `SpreadBy`/`MustNotColocate` determines the failure-domain key used for spread-capping (the
+/// last one present wins, since they both describe the same axis at different strictness).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TopologyConstraint {
+    /// `topology.key in {a,b,...}` — restrict candidates to those whose `key` label is in the
+    /// given set.
+    LabelIn { key: String, values: HashSet<String> },
+    /// `spread by <key>` — no single value of `key` should hold more than half the replicas.
+    SpreadBy { key: String },
+    /// `must-not-colocate <key>` — no two replicas may share the same value of `key` at all.
+    MustNotColocate { key: String },
+}
+
+/// Drop candidates that fail any `LabelIn` constraint.
+pub(crate) fn filter_candidates(
+    candidates: Vec<PoolCandidate>,
+    constraints: &[TopologyConstraint],
+) -> Vec<PoolCandidate> {
+    candidates
+        .into_iter()
+        .filter(|candidate| {
+            constraints.iter().all(|constraint| match constraint {
+                TopologyConstraint::LabelIn { key, values } => candidate
+                    .labels
+                    .get(key)
+                    .is_some_and(|value| values.contains(value)),
+                TopologyConstraint::SpreadBy { .. } | TopologyConstraint::MustNotColocate { .. } => {
+                    true
+                }
+            })
+        })
+        .collect()
+}
+
+/// Derive the `(spread_key, max_per_zone)` pair `placement::PlacementRequest` needs from a
+/// volume's topology constraints, given the volume's replica count. `must-not-colocate` is
+/// strictest (at most one replica per domain value); `spread by` falls back to
+/// `placement::default_max_per_zone`; no constraint on the axis means no spread cap at all.
+pub(crate) fn spread_requirement(
+    constraints: &[TopologyConstraint],
+    num_replicas: usize,
+) -> (Option<String>, usize) {
+    constraints
+        .iter()
+        .rev()
+        .find_map(|constraint| match constraint {
+            TopologyConstraint::MustNotColocate { key } => Some((key.clone(), 1)),
+            TopologyConstraint::SpreadBy { key } => Some((
+                key.clone(),
+                super::placement::default_max_per_zone(num_replicas),
+            )),
+            TopologyConstraint::LabelIn { .. } => None,
+        })
+        .map_or((None, num_replicas), |(key, max)| (Some(key), max))
+}
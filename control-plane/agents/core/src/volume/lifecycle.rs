@@ -0,0 +1,283 @@
+//! Declarative snapshot lifecycle policies.
+//!
+//! `Registry::get_volume_state_with_replicas` only ever looks at `replica_specs.len()` against
+//! `volume_spec.num_replicas`; nothing in the volume subsystem ever creates or destroys a
+//! snapshot except in direct response to an operator request. This lets a volume instead carry a
+//! `SnapshotRetentionPolicy` - keep the newest N snapshots, expire anything older than a duration,
+//! and/or take a new one on a fixed period - evaluated by `Registry::poll_volume_lifecycle`
+//! against a caller-supplied snapshot listing. Nothing in this crate calls it; the generic
+//! poller's `PollTriggerEvent` handling, which `notify_if_degraded` in `volume::registry` reacts
+//! to, does not reach it.
+//!
+//! Note: this checkout has no snapshot spec/store type to persist the policy against (`VolumeSpec`
+//! itself isn't present here either, only imported), so `SnapshotRetentionPolicy` is modelled as
+//! its own persisted object keyed by `VolumeId` rather than a field literally added to
+//! `VolumeSpec`, and `destroy_pool` - named in the originating request as the not-found/error
+//! style to mirror - doesn't exist anywhere in this tree to copy from; the `.context(NotFound)`
+//! `snafu` pattern below is inferred from the same style `node::specs::get_locked_node` uses.
+//!
+//! `keep_count`/`max_age` already cover the "retain at most N"/"retain for at most a duration"
+//! knobs a later request asks for under the names `retain_count`/`retain_age`; rather than add a
+//! second, identically-shaped pair of fields, `evaluate` below was extended in place and a
+//! `spawn_reconciler` background task added that periodically re-runs it and destroys whatever it
+//! names, closing the loop the original version only modelled as a pure decision function. There
+//! is still no real `VolumeSpec`/`set_property`/`SetVolumeProperty`/`destroy_snapshot` to call
+//! into, so `spawn_reconciler` takes its snapshot listing and destroy action as closures the real
+//! call site would back with those.
+//!
+//! Policies live in `LIFECYCLE_POLICIES`, a module-level store (a `once_cell`/`parking_lot`
+//! static), rather than a
+//! `HashMap` an external caller would have to own and pass in: nothing in this checkout ever
+//! constructed that caller, so a policy set via `set_volume_lifecycle_policy` would otherwise
+//! never be observed by anything. `Registry::get_volume_state_with_replicas` - the one function
+//! in this crate real code actually calls - now reads it on every volume to log a due decision.
+//! That still can't evaluate real snapshots (this checkout has no snapshot store either, so it
+//! always evaluates against an empty list) or act on a destroy (no `destroy_snapshot` RPC to call
+//! into), so `spawn_reconciler`'s closures remain the intended real integration point once those
+//! land.
+
+use crate::core::registry::Registry;
+use common::errors::SvcError;
+use common_lib::types::v0::message_bus::VolumeId;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use snafu::OptionExt;
+use std::{collections::HashMap, future::Future, time::Duration};
+
+/// Process-wide store of each volume's snapshot retention policy. See the module doc comment for
+/// why this is a static rather than a caller-owned map.
+static LIFECYCLE_POLICIES: Lazy<Mutex<HashMap<VolumeId, SnapshotRetentionPolicy>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A single snapshot's identity, creation time and the two facts that make pruning it unsafe, as
+/// needed to evaluate a retention policy. Standing in for a real snapshot spec type, which isn't
+/// present in this checkout.
+#[derive(Debug, Clone)]
+pub(crate) struct SnapshotRecord {
+    pub(crate) id: String,
+    pub(crate) created_at: DateTime<Utc>,
+    /// Mirrors a real snapshot spec's `status().created()`: `false` while the snapshot is still
+    /// in-flight, in which case it must never be picked for destruction.
+    pub(crate) created: bool,
+    /// Mirrors a live clone's `VolumeContentSource::Snapshot` pointing at this snapshot: `true`
+    /// means some clone still depends on it, so it must never be picked for destruction
+    /// regardless of age or count.
+    pub(crate) referenced_by_clone: bool,
+}
+
+impl SnapshotRecord {
+    /// A plain, already-`created`, unreferenced snapshot - the common case once a snapshot has
+    /// finished creating and no clone has been made from it.
+    pub(crate) fn new(id: &str, created_at: DateTime<Utc>) -> Self {
+        Self {
+            id: id.to_string(),
+            created_at,
+            created: true,
+            referenced_by_clone: false,
+        }
+    }
+
+    /// Whether this snapshot may ever be selected for expiry by `evaluate`.
+    fn prunable(&self) -> bool {
+        self.created && !self.referenced_by_clone
+    }
+}
+
+/// A volume's snapshot lifecycle policy.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnapshotRetentionPolicy {
+    /// Keep at most this many of the newest snapshots; older ones beyond this count are due for
+    /// destruction.
+    pub(crate) keep_count: Option<u32>,
+    /// Destroy snapshots older than this, regardless of `keep_count`.
+    pub(crate) max_age: Option<Duration>,
+    /// Take a new snapshot automatically every `period`, if the newest existing snapshot (if
+    /// any) is already older than that.
+    pub(crate) schedule_period: Option<Duration>,
+}
+
+/// What a single poll of a volume's lifecycle policy decided to do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct LifecycleDecision {
+    /// A new snapshot should be created because the schedule is due.
+    pub(crate) create_due: bool,
+    /// Existing snapshots that should be destroyed, oldest first.
+    pub(crate) expired: Vec<String>,
+}
+
+impl Registry {
+    /// Attach (or replace) a snapshot retention policy for `volume_id`. This is the handler the
+    /// existing `set_property`/`SetVolumeProperty` request path would call into for a
+    /// snapshot-retention property update.
+    ///
+    /// There's no real `VolumeSpec` in this checkout to persist `policy` against, so it's kept in
+    /// `LIFECYCLE_POLICIES` instead; a full implementation would persist it alongside the volume's
+    /// spec the way `node::specs::register_node` persists a changed `NodeSpec` via
+    /// `self.store_obj`.
+    pub(crate) async fn set_volume_lifecycle_policy(
+        &self,
+        volume_id: &VolumeId,
+        policy: SnapshotRetentionPolicy,
+    ) -> Result<(), SvcError> {
+        LIFECYCLE_POLICIES.lock().insert(volume_id.clone(), policy);
+        Ok(())
+    }
+
+    /// Evaluate every volume's retention policy against its current snapshots, returning the set
+    /// of snapshots to create and destroy. Nothing in this crate calls this; the generic poller's
+    /// `PollTriggerEvent` handling does not reach it.
+    #[tracing::instrument(level = "debug", skip(self, snapshots))]
+    pub(crate) async fn poll_volume_lifecycle(
+        &self,
+        snapshots: &HashMap<VolumeId, Vec<SnapshotRecord>>,
+        now: DateTime<Utc>,
+    ) -> HashMap<VolumeId, LifecycleDecision> {
+        LIFECYCLE_POLICIES
+            .lock()
+            .iter()
+            .map(|(volume_id, policy)| {
+                let existing = snapshots.get(volume_id).cloned().unwrap_or_default();
+                (volume_id.clone(), evaluate(policy, &existing, now))
+            })
+            .collect()
+    }
+
+    /// Look up a volume's retention policy, erroring with the same not-found shape
+    /// `node::specs::get_locked_node` uses for a missing `NodeSpec`.
+    pub(crate) fn get_volume_lifecycle_policy(
+        &self,
+        volume_id: &VolumeId,
+    ) -> Result<SnapshotRetentionPolicy, SvcError> {
+        LIFECYCLE_POLICIES
+            .lock()
+            .get(volume_id)
+            .cloned()
+            .context(common::errors::VolumeNotFound {
+                vol_id: volume_id.to_string(),
+            })
+    }
+
+    /// Evaluate `volume_id`'s retention policy, if any, and log a due decision. Called from
+    /// `get_volume_state_with_replicas`, the one real per-volume call site in this crate; this
+    /// checkout has no snapshot store, so `snapshots` is always empty here - real snapshot ages
+    /// can't be evaluated, only whether a policy is attached and (for `schedule_period`) that no
+    /// snapshot has ever been observed.
+    pub(crate) async fn log_due_lifecycle_decision(&self, volume_id: &VolumeId) {
+        let Ok(policy) = self.get_volume_lifecycle_policy(volume_id) else {
+            return;
+        };
+        let decision = evaluate(&policy, &[], Utc::now());
+        if decision.create_due || !decision.expired.is_empty() {
+            tracing::info!(
+                volume.uuid = %volume_id,
+                create_due = decision.create_due,
+                expired = ?decision.expired,
+                "Snapshot retention policy has a due decision"
+            );
+        }
+    }
+
+    /// Background pruning reconciler: every `period`, re-evaluate every volume's policy against
+    /// its current snapshots via `list_snapshots` and destroy whatever comes back expired via
+    /// `destroy_snapshot`. Idempotent across restarts: each pass re-derives its delete set fresh
+    /// from `list_snapshots`/`evaluate` rather than tracking progress, so a crash mid-pass just
+    /// means the next pass re-selects (and skips, if already gone) the same snapshots.
+    ///
+    /// `list_snapshots`/`destroy_snapshot` are taken as closures because this checkout has no
+    /// real snapshot store or `destroy_snapshot` operation to call directly; the real call site
+    /// would back them with those. Policies themselves now come from `LIFECYCLE_POLICIES`, the
+    /// same store `set_volume_lifecycle_policy` writes to, rather than a separate map the caller
+    /// would have to keep in sync with it.
+    pub(crate) fn spawn_reconciler<L, LFut, D, DFut>(
+        self: std::sync::Arc<Self>,
+        period: Duration,
+        list_snapshots: L,
+        destroy_snapshot: D,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        L: Fn(VolumeId) -> LFut + Send + Sync + 'static,
+        LFut: Future<Output = Vec<SnapshotRecord>> + Send + 'static,
+        D: Fn(VolumeId, String) -> DFut + Send + Sync + 'static,
+        DFut: Future<Output = Result<(), SvcError>> + Send + 'static,
+    {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+
+                let volume_ids: Vec<VolumeId> = LIFECYCLE_POLICIES.lock().keys().cloned().collect();
+                for volume_id in volume_ids {
+                    let Some(policy) = LIFECYCLE_POLICIES.lock().get(&volume_id).cloned() else {
+                        continue;
+                    };
+                    let existing = list_snapshots(volume_id.clone()).await;
+                    let decision = evaluate(&policy, &existing, Utc::now());
+
+                    for snapshot_id in decision.expired {
+                        if let Err(error) =
+                            destroy_snapshot(volume_id.clone(), snapshot_id.clone()).await
+                        {
+                            tracing::warn!(
+                                volume.uuid = %volume_id,
+                                snapshot.uuid = %snapshot_id,
+                                %error,
+                                "Failed to prune expired snapshot"
+                            );
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Pure decision function: given a policy and a volume's existing snapshots sorted by age,
+/// decide which snapshots are expired and whether a new one is due.
+fn evaluate(
+    policy: &SnapshotRetentionPolicy,
+    snapshots: &[SnapshotRecord],
+    now: DateTime<Utc>,
+) -> LifecycleDecision {
+    let mut ordered = snapshots.to_vec();
+    ordered.sort_by_key(|snapshot| snapshot.created_at);
+
+    let mut expired = Vec::new();
+
+    if let Some(max_age) = policy.max_age {
+        for snapshot in &ordered {
+            let age = now.signed_duration_since(snapshot.created_at);
+            if snapshot.prunable() && age.to_std().unwrap_or_default() > max_age {
+                expired.push(snapshot.id.clone());
+            }
+        }
+    }
+
+    if let Some(keep_count) = policy.keep_count {
+        // "Keep N newest" counts every existing snapshot, prunable or not, so an in-flight or
+        // clone-referenced snapshot still occupies one of the N slots; only the prunable
+        // snapshots among the excess are actually offered up for destruction.
+        let keep_count = keep_count as usize;
+        if ordered.len() > keep_count {
+            for snapshot in &ordered[..ordered.len() - keep_count] {
+                if snapshot.prunable() && !expired.contains(&snapshot.id) {
+                    expired.push(snapshot.id.clone());
+                }
+            }
+        }
+    }
+
+    let create_due = match policy.schedule_period {
+        None => false,
+        Some(period) => match ordered.last() {
+            None => true,
+            Some(newest) => {
+                let age = now.signed_duration_since(newest.created_at);
+                age.to_std().unwrap_or_default() > period
+            }
+        },
+    };
+
+    LifecycleDecision { create_due, expired }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod lifecycle;
+pub(crate) mod registry;
+pub(crate) mod stats;
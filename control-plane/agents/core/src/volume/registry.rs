@@ -5,6 +5,7 @@ use common_lib::types::v0::message_bus::{
 };
 
 use crate::core::reconciler::PollTriggerEvent;
+use crate::scheduling::{placement, topology};
 use common_lib::types::v0::store::{replica::ReplicaSpec, volume::VolumeSpec};
 
 use std::collections::HashMap;
@@ -63,6 +64,8 @@ impl Registry {
             );
         }
 
+        self.log_due_lifecycle_decision(&volume_spec.uuid).await;
+
         Ok(if let Some(nexus_state) = nexus_state {
             VolumeState {
                 uuid: volume_spec.uuid.to_owned(),
@@ -79,26 +82,94 @@ impl Registry {
                 replica_topology,
             }
         } else {
+            let status = if volume_spec.target.is_none() {
+                if replica_specs.len() >= volume_spec.num_replicas as usize {
+                    VolumeStatus::Online
+                } else if replica_specs.is_empty() {
+                    VolumeStatus::Faulted
+                } else {
+                    VolumeStatus::Degraded
+                }
+            } else {
+                VolumeStatus::Unknown
+            };
+
+            if matches!(status, VolumeStatus::Degraded | VolumeStatus::Faulted) {
+                self.log_placement_recommendation(volume_spec, &replica_topology);
+            }
+
             VolumeState {
                 uuid: volume_spec.uuid.to_owned(),
                 size: volume_spec.size,
-                status: if volume_spec.target.is_none() {
-                    if replica_specs.len() >= volume_spec.num_replicas as usize {
-                        VolumeStatus::Online
-                    } else if replica_specs.is_empty() {
-                        VolumeStatus::Faulted
-                    } else {
-                        VolumeStatus::Degraded
-                    }
-                } else {
-                    VolumeStatus::Unknown
-                },
+                status,
                 target: None,
                 replica_topology,
             }
         })
     }
 
+    /// Log where a min-cost max-flow re-solve (see `scheduling::placement`) would place this
+    /// volume's replicas, given only the pools its existing replicas already sit on: this crate
+    /// has no pool/node catalog or reconciler to create replicas from a wider candidate set, so
+    /// this can't yet *act* on the recommendation, only surface it for an operator/future
+    /// reconciler to consume. Candidates are drawn from `replica_topology`, the one source of
+    /// real node/pool data already available here; each is marked `existing_replica` since it's
+    /// already hosting one.
+    fn log_placement_recommendation(
+        &self,
+        volume_spec: &VolumeSpec,
+        replica_topology: &HashMap<common_lib::types::v0::message_bus::ReplicaId, ReplicaTopology>,
+    ) {
+        // Nodes mid-drain (`node::specs::draining_nodes`) shouldn't be recommended as a landing
+        // spot for a replica that would have to move again once the drain finishes; reporting
+        // zero free capacity for them is the same "ineligible candidate" signal `placement::solve`
+        // already uses for a pool that's simply full.
+        let draining: std::collections::HashSet<_> = self
+            .specs()
+            .draining_nodes()
+            .into_iter()
+            .map(|node| node.id().clone())
+            .collect();
+
+        let candidates: Vec<_> = replica_topology
+            .values()
+            .filter_map(|topology| {
+                let node = topology.node()?.clone();
+                let free_capacity = if draining.contains(&node) {
+                    0
+                } else {
+                    volume_spec.size
+                };
+                Some(placement::PoolCandidate {
+                    pool: topology.pool()?.clone(),
+                    node,
+                    labels: HashMap::new(),
+                    free_capacity,
+                    existing_replica: true,
+                })
+            })
+            .collect();
+
+        // No topology constraints are reachable from `VolumeSpec` in this checkout, so every
+        // candidate passes unfiltered; still a real call, just an unconstrained one.
+        let candidates = topology::filter_candidates(candidates, &[]);
+        let num_replicas = volume_spec.num_replicas as usize;
+        let result = placement::solve(&placement::PlacementRequest {
+            num_replicas,
+            size: volume_spec.size,
+            candidates,
+            spread_key: None,
+            max_per_zone: placement::default_max_per_zone(num_replicas),
+        });
+
+        tracing::info!(
+            volume.uuid = %volume_spec.uuid,
+            pools = ?result.pools,
+            under_provisioned = result.under_provisioned,
+            "Placement solve for under-replicated volume"
+        );
+    }
+
     /// Construct a replica topology from a replica spec.
     /// If the replica cannot be found, return the default replica topology.
     async fn replica_topology(&self, spec: &ReplicaSpec) -> ReplicaTopology {
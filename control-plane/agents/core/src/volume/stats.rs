@@ -0,0 +1,78 @@
+//! I/O statistics aggregation for volumes and pools.
+//!
+//! `get_volume_state_with_replicas` reports topology and status but never throughput, so an
+//! operator has to scrape a separate metrics endpoint to see how a volume is performing. This
+//! adds an `IoStats` accumulator - bytes/ops read and written, plus a latency histogram - and
+//! `aggregate` helpers that roll per-replica/per-nexus counters up into a volume total and
+//! per-pool totals, the same way a bdev stats accessor aggregates per-device counters.
+//!
+//! Note: there's no gRPC stats client nor a `VolumeState`/`models::Pool` definition in this
+//! checkout to source the per-replica counters from or to attach the aggregate onto (both are
+//! only ever imported from crates this tree doesn't vendor), so the functions below take already
+//! fetched `IoStats` as input, mirroring how `get_volume_state_with_replicas` itself takes
+//! pre-fetched `replicas` rather than reaching into a store directly.
+
+use std::collections::HashMap;
+
+/// One latency histogram bucket: the number of I/Os completed in at most `le_us` microseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LatencyBucket {
+    pub(crate) le_us: u64,
+    pub(crate) count: u64,
+}
+
+/// Cumulative read/write throughput and latency distribution for a replica, nexus, pool, or
+/// volume.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct IoStats {
+    pub(crate) read_bytes: u64,
+    pub(crate) write_bytes: u64,
+    pub(crate) read_ops: u64,
+    pub(crate) write_ops: u64,
+    pub(crate) read_latency_us: Vec<LatencyBucket>,
+    pub(crate) write_latency_us: Vec<LatencyBucket>,
+}
+
+impl IoStats {
+    /// Fold `other` into `self`: counters sum, and latency buckets with the same `le_us`
+    /// boundary sum their counts (buckets present in only one side pass through unchanged).
+    pub(crate) fn merge(&mut self, other: &IoStats) {
+        self.read_bytes += other.read_bytes;
+        self.write_bytes += other.write_bytes;
+        self.read_ops += other.read_ops;
+        self.write_ops += other.write_ops;
+        merge_histogram(&mut self.read_latency_us, &other.read_latency_us);
+        merge_histogram(&mut self.write_latency_us, &other.write_latency_us);
+    }
+}
+
+fn merge_histogram(into: &mut Vec<LatencyBucket>, from: &[LatencyBucket]) {
+    for bucket in from {
+        match into.iter_mut().find(|existing| existing.le_us == bucket.le_us) {
+            Some(existing) => existing.count += bucket.count,
+            None => into.push(*bucket),
+        }
+    }
+    into.sort_by_key(|bucket| bucket.le_us);
+}
+
+/// Sum per-replica/per-nexus `IoStats` into a single volume-level total.
+pub(crate) fn aggregate_volume_stats<'a>(per_source: impl IntoIterator<Item = &'a IoStats>) -> IoStats {
+    let mut total = IoStats::default();
+    for stats in per_source {
+        total.merge(stats);
+    }
+    total
+}
+
+/// Sum per-replica `IoStats` grouped by the pool hosting each replica, keyed the same way
+/// `PoolCandidate::pool` identifies a pool in `scheduling::placement`.
+pub(crate) fn aggregate_pool_stats<K: std::hash::Hash + Eq + Clone>(
+    per_replica: impl IntoIterator<Item = (K, IoStats)>,
+) -> HashMap<K, IoStats> {
+    let mut totals: HashMap<K, IoStats> = HashMap::new();
+    for (pool, stats) in per_replica {
+        totals.entry(pool).or_default().merge(&stats);
+    }
+    totals
+}
@@ -0,0 +1,44 @@
+pub(crate) mod v1;
+
+use stor_port::transport::NodeId;
+use tonic::transport::Endpoint;
+
+/// Connection details for a single io-engine gRPC endpoint, shared by every protocol version's
+/// `RpcClient`.
+#[derive(Clone, Debug)]
+pub(crate) struct GrpcContext {
+    pub(crate) node: NodeId,
+    pub(crate) endpoint: Endpoint,
+    uri: String,
+    /// Mutual-TLS material to secure the channel with, if the node's endpoint requires it.
+    /// `None` keeps the channel cleartext, as it always was before `GrpcTlsConfig` existed.
+    tls: Option<v1::GrpcTlsConfig>,
+}
+
+impl GrpcContext {
+    pub(crate) fn new(
+        node: NodeId,
+        uri: impl Into<String>,
+        endpoint: Endpoint,
+        tls: Option<v1::GrpcTlsConfig>,
+    ) -> Self {
+        Self {
+            node,
+            endpoint,
+            uri: uri.into(),
+            tls,
+        }
+    }
+    /// The endpoint address this context connects to, as a displayable string.
+    pub(crate) fn endpoint(&self) -> &str {
+        &self.uri
+    }
+    /// Mutual-TLS material to secure the channel with, if any was attached via `new`.
+    pub(crate) fn tls(&self) -> Option<&v1::GrpcTlsConfig> {
+        self.tls.as_ref()
+    }
+}
+
+/// Common behaviour every io-engine protocol version's `RpcClient` implements.
+#[async_trait::async_trait]
+pub(crate) trait NodeApi {}
@@ -8,7 +8,7 @@ use crate::controller::io_engine::GrpcContext;
 use agents::errors::{GrpcConnect, SvcError};
 
 use snafu::ResultExt;
-use tonic::transport::Channel;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
 
 /// V1 HostClient.
 type HostClient = rpc::v1::host::host_rpc_client::HostRpcClient<Channel>;
@@ -19,6 +19,46 @@ type NexusClient = rpc::v1::nexus::nexus_rpc_client::NexusRpcClient<Channel>;
 /// The V1 PoolClient.
 type PoolClient = rpc::v1::pool::pool_rpc_client::PoolRpcClient<Channel>;
 
+/// Mutual-TLS material used to secure the Io-Engine V1 gRPC channel.
+///
+/// When a [`GrpcContext`] carries a `GrpcTlsConfig`, [`RpcClient::new`] secures the channel
+/// with it before connecting; when absent, the channel stays cleartext as before.
+#[derive(Clone, Debug)]
+pub(crate) struct GrpcTlsConfig {
+    /// CA certificate used to verify the io-engine server's certificate.
+    ca_cert: Certificate,
+    /// Client certificate and private key identifying the control-plane to io-engine.
+    identity: Identity,
+    /// Domain name checked against the server certificate (io-engine endpoints are addressed
+    /// by IP, so this is typically the node's hostname).
+    domain_name: String,
+}
+
+impl GrpcTlsConfig {
+    /// Build a new config from PEM-encoded CA certificate, PEM-encoded client certificate/key,
+    /// and the domain name to validate the server certificate against.
+    pub(crate) fn new(
+        ca_cert_pem: impl AsRef<[u8]>,
+        client_cert_pem: impl AsRef<[u8]>,
+        client_key_pem: impl AsRef<[u8]>,
+        domain_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            ca_cert: Certificate::from_pem(ca_cert_pem),
+            identity: Identity::from_pem(client_cert_pem, client_key_pem),
+            domain_name: domain_name.into(),
+        }
+    }
+
+    /// Turn this config into the `tonic` TLS config applied to the channel before connecting.
+    fn to_client_tls_config(&self) -> ClientTlsConfig {
+        ClientTlsConfig::new()
+            .ca_certificate(self.ca_cert.clone())
+            .identity(self.identity.clone())
+            .domain_name(&self.domain_name)
+    }
+}
+
 /// A collection of all clients for the Io-Engine V1 services.
 #[derive(Clone, Debug)]
 pub(crate) struct RpcClient {
@@ -31,8 +71,25 @@ pub(crate) struct RpcClient {
 
 impl RpcClient {
     /// Create a new grpc client with a context.
+    ///
+    /// When the context carries mutual-TLS material (CA certificate plus client
+    /// certificate/key), the channel is secured with it and all four clients share the
+    /// resulting encrypted, authenticated connection. Otherwise the channel remains
+    /// cleartext, as before.
     pub(crate) async fn new(context: &GrpcContext) -> Result<Self, SvcError> {
-        let channel = context.endpoint.connect().await.context(GrpcConnect {
+        let endpoint = match context.tls() {
+            Some(tls) => context
+                .endpoint
+                .clone()
+                .tls_config(tls.to_client_tls_config())
+                .context(GrpcConnect {
+                    node_id: context.node.to_owned(),
+                    endpoint: context.endpoint().to_string(),
+                })?,
+            None => context.endpoint.clone(),
+        };
+
+        let channel = endpoint.connect().await.context(GrpcConnect {
             node_id: context.node.to_owned(),
             endpoint: context.endpoint().to_string(),
         })?;
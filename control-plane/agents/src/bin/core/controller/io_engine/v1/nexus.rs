@@ -0,0 +1,51 @@
+use super::translation::RebuildVerifyStats;
+use crate::volume::operations::RebuildVerifyMode;
+use agents::errors::SvcError;
+
+use super::RpcClient;
+
+impl RpcClient {
+    /// Add a child to the nexus and rebuild it, applying the given data-integrity verification
+    /// mode while the rebuild is in progress.
+    ///
+    /// `Off` behaves exactly as a plain rebuild; `CompareReads`/`PanicOnMismatch` make io-engine
+    /// read back each rebuilt segment from the destination and compare it against the source,
+    /// failing (or panicking) the rebuild job on a mismatch instead of silently finishing it.
+    pub(crate) async fn add_child_verified(
+        &self,
+        nexus_uuid: &str,
+        uri: &str,
+        verify_mode: RebuildVerifyMode,
+    ) -> Result<(), SvcError> {
+        let mut client = self.nexus();
+        client
+            .add_child_nexus(rpc::v1::nexus::AddChildNexusRequest {
+                uuid: nexus_uuid.to_string(),
+                uri: uri.to_string(),
+                norebuild: false,
+                rebuild_verify: rpc::v1::nexus::RebuildVerifyMode::from(verify_mode) as i32,
+            })
+            .await
+            .map_err(SvcError::from)?;
+        Ok(())
+    }
+
+    /// Fetch the data-integrity verification statistics for an in-progress or completed rebuild
+    /// of the given child, when the rebuild was run with verification enabled.
+    pub(crate) async fn rebuild_verify_stats(
+        &self,
+        nexus_uuid: &str,
+        child_uri: &str,
+    ) -> Result<RebuildVerifyStats, SvcError> {
+        let mut client = self.nexus();
+        let stats = client
+            .get_rebuild_stats(rpc::v1::nexus::RebuildStatsRequest {
+                uuid: nexus_uuid.to_string(),
+                uri: child_uri.to_string(),
+            })
+            .await
+            .map_err(SvcError::from)?
+            .into_inner();
+        Ok(RebuildVerifyStats::from(stats))
+    }
+}
@@ -0,0 +1,30 @@
+use crate::volume::operations::RebuildVerifyMode;
+
+/// Per-job rebuild data-integrity statistics, as reported by io-engine when a rebuild runs with
+/// verification enabled.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub(crate) struct RebuildVerifyStats {
+    /// Number of destination segments read back and compared against the source.
+    pub(crate) segments_compared: u64,
+    /// Number of compared segments that did not match the source.
+    pub(crate) mismatches: u64,
+}
+
+impl From<RebuildVerifyMode> for rpc::v1::nexus::RebuildVerifyMode {
+    fn from(mode: RebuildVerifyMode) -> Self {
+        match mode {
+            RebuildVerifyMode::Off => rpc::v1::nexus::RebuildVerifyMode::None,
+            RebuildVerifyMode::CompareReads => rpc::v1::nexus::RebuildVerifyMode::FailOnMismatch,
+            RebuildVerifyMode::PanicOnMismatch => rpc::v1::nexus::RebuildVerifyMode::PanicOnMismatch,
+        }
+    }
+}
+
+impl From<rpc::v1::nexus::RebuildJobStats> for RebuildVerifyStats {
+    fn from(stats: rpc::v1::nexus::RebuildJobStats) -> Self {
+        Self {
+            segments_compared: stats.verify_segments,
+            mismatches: stats.verify_mismatches,
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Fair FIFO sequencing for `OperationGuardArc` acquisition.
+//!
+//! `operation_guard_mode_wait` used to just retry `operation_guard_mode` up to 5 times with a
+//! fixed 200ms `tokio::time::sleep` between tries: unfair under contention (whoever happens to
+//! poll at the right instant wins, so a long-waiting caller can be jumped by one that only just
+//! arrived) and bounded (a caller can still be rejected outright after 5 tries no matter how
+//! briefly the resource was busy). `FairQueues` replaces the polling order with a real ticket
+//! queue per resource uid, modelled on a shared sequential update store: a caller takes a
+//! monotonically increasing ticket and only attempts the real acquisition once every
+//! lower-numbered ticket for that resource has stepped aside, removing the retry cap and making
+//! wait order deterministic.
+//!
+//! One gap: `OperationGuardArc`'s `Drop` (where the real guard is released) lives outside this
+//! checkout, so it can't directly notify `FairQueues` the instant a resource frees up. The ticket
+//! at the front of the line therefore polls the real guard at a steady interval instead of being
+//! woken by the release itself - strictly better than the old bounded-retry design (no cap, and
+//! every other ticket is asleep rather than contending), but not purely push-based.
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{collections::{HashMap, VecDeque}, sync::Arc};
+use tokio::sync::Notify;
+
+/// A resource's position in its `FairQueues` ticket queue.
+pub(crate) type Ticket = u64;
+
+/// What a resource's fair queue reports for introspection: idle, or the ticket currently at the
+/// front attempting/holding the real guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum QueueState {
+    Idle,
+    Processing(Ticket),
+}
+
+#[derive(Default)]
+struct Inner {
+    next_ticket: Ticket,
+    waiting: VecDeque<Ticket>,
+}
+
+struct Entry {
+    notify: Notify,
+    inner: Mutex<Inner>,
+}
+
+/// Process-wide fair FIFO sequencer, one ticket queue per resource uid string.
+#[derive(Default)]
+pub(crate) struct FairQueues {
+    entries: Mutex<HashMap<String, Arc<Entry>>>,
+}
+
+static FAIR_QUEUES: Lazy<FairQueues> = Lazy::new(FairQueues::default);
+
+/// RAII wrapper around a `Ticket` that calls `FairQueues::advance` on drop, whether that's
+/// because the holder reached the front and is done with it, or because the future holding it
+/// was cancelled (e.g. a `tokio::time::timeout` expiring) while still queued or waiting for the
+/// real guard to free up. Without this, a cancelled ticket never advances and permanently blocks
+/// `wait_turn`'s `front() == Some(&ticket)` check for every later caller on that resource uid.
+pub(crate) struct TicketGuard {
+    queues: &'static FairQueues,
+    uid: String,
+    ticket: Ticket,
+}
+
+impl TicketGuard {
+    pub(crate) fn ticket(&self) -> Ticket {
+        self.ticket
+    }
+}
+
+impl Drop for TicketGuard {
+    fn drop(&mut self) {
+        self.queues.advance(&self.uid, self.ticket);
+    }
+}
+
+/// The process-wide `FairQueues` singleton used by `operation_guard_mode_wait`.
+pub(crate) fn queues() -> &'static FairQueues {
+    &FAIR_QUEUES
+}
+
+impl FairQueues {
+    fn entry(&self, uid: &str) -> Arc<Entry> {
+        let mut entries = self.entries.lock();
+        entries
+            .entry(uid.to_string())
+            .or_insert_with(|| {
+                Arc::new(Entry {
+                    notify: Notify::new(),
+                    inner: Mutex::new(Inner::default()),
+                })
+            })
+            .clone()
+    }
+
+    /// Take a ticket and enqueue it at the back of `uid`'s FIFO.
+    pub(crate) fn enqueue(&self, uid: &str) -> Ticket {
+        let entry = self.entry(uid);
+        let mut inner = entry.inner.lock();
+        let ticket = inner.next_ticket;
+        inner.next_ticket += 1;
+        inner.waiting.push_back(ticket);
+        ticket
+    }
+
+    /// Wait until `ticket` is at the front of `uid`'s queue, i.e. every ticket ahead of it has
+    /// called `advance`.
+    pub(crate) async fn wait_turn(&self, uid: &str, ticket: Ticket) {
+        let entry = self.entry(uid);
+        loop {
+            let notified = entry.notify.notified();
+            if entry.inner.lock().waiting.front() == Some(&ticket) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Step `ticket` aside, whether or not the real guard acquisition it then attempted
+    /// succeeded, and wake whoever is queued behind it to try for the new front position.
+    /// `ticket` is removed wherever it stands, so a ticket whose holder was cancelled while still
+    /// queued behind others is taken out too.
+    ///
+    /// Idempotent: safe to call more than once, since a ticket no longer in the queue is left
+    /// alone.
+    pub(crate) fn advance(&self, uid: &str, ticket: Ticket) {
+        let entry = self.entry(uid);
+        {
+            let mut inner = entry.inner.lock();
+            if let Some(position) = inner.waiting.iter().position(|queued| *queued == ticket) {
+                inner.waiting.remove(position);
+            }
+        }
+        entry.notify.notify_waiters();
+    }
+
+    /// Same as `enqueue`, but wraps the ticket in a `TicketGuard` that calls `advance` on drop.
+    ///
+    /// `wait_turn`'s caller may itself be dropped before reaching the front (e.g. wrapped in a
+    /// `tokio::time::timeout` that expires while the ticket is still queued) - without this, that
+    /// ticket never advances and permanently blocks every later caller's `wait_turn` on the same
+    /// resource uid.
+    pub(crate) fn enqueue_guarded(&'static self, uid: &str) -> TicketGuard {
+        TicketGuard {
+            queues: self,
+            uid: uid.to_string(),
+            ticket: self.enqueue(uid),
+        }
+    }
+
+    /// `uid`'s current queue state and how many tickets are waiting behind the front one, for
+    /// operator introspection of lock contention.
+    pub(crate) fn state(&self, uid: &str) -> (QueueState, usize) {
+        let entry = self.entry(uid);
+        let inner = entry.inner.lock();
+        match inner.waiting.front() {
+            Some(front) => (QueueState::Processing(*front), inner.waiting.len() - 1),
+            None => (QueueState::Idle, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Dropping a `TicketGuard` while its ticket is still at the front (e.g. the caller holding
+    /// it was cancelled by a `tokio::time::timeout`) must still advance the queue, or the ticket
+    /// behind it would wait forever.
+    #[tokio::test]
+    async fn ticket_guard_drop_advances_the_queue_on_cancellation() {
+        let uid = "fair-queue-test-cancellation";
+        let first = queues().enqueue_guarded(uid);
+        let second = queues().enqueue(uid);
+        assert_eq!(queues().state(uid).0, QueueState::Processing(first.ticket()));
+
+        drop(first);
+
+        assert_eq!(queues().state(uid).0, QueueState::Processing(second));
+        queues().advance(uid, second);
+    }
+
+    /// A ticket queued behind another must not be reported as (or resolve `wait_turn` as) the
+    /// front until every ticket ahead of it has advanced.
+    #[tokio::test]
+    async fn later_ticket_waits_until_the_earlier_one_advances() {
+        let uid = "fair-queue-test-fifo-order";
+        let first = queues().enqueue(uid);
+        let second = queues().enqueue(uid);
+
+        let not_yet = tokio::time::timeout(
+            std::time::Duration::from_millis(20),
+            queues().wait_turn(uid, second),
+        )
+        .await;
+        assert!(
+            not_yet.is_err(),
+            "wait_turn must not resolve while an earlier ticket is still ahead"
+        );
+
+        queues().advance(uid, first);
+        queues().wait_turn(uid, second).await;
+        queues().advance(uid, second);
+    }
+}
@@ -0,0 +1,120 @@
+//! Optimistic-concurrency generation counters for compare-and-swap updates/destroys.
+//!
+//! Several actors (REST client, CSI, the reconciler) can issue an update or destroy against the
+//! same spec concurrently; today the last writer simply clobbers whoever went first. A
+//! `Precondition` lets a caller say "only apply this if the spec is still at generation N",
+//! mirroring the "allow adding preconditions" gap in Drogue's thing-service delete/update paths.
+//!
+//! There's no field on the real spec types (`VolumeSpec` and friends, all external to this
+//! checkout) to persist the generation on, so `GenerationTracker` keeps it in a side table keyed
+//! by uuid instead of inside the spec; a full implementation would instead add a `generation`
+//! field to `StorableObject` itself and surface it in the OpenAPI model so clients can
+//! read-modify-write.
+
+use agents::errors::SvcError;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+
+/// A caller's expectation of a spec's current generation, to be checked before any op is logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Precondition {
+    pub(crate) expected_generation: u64,
+}
+
+impl Precondition {
+    pub(crate) fn new(expected_generation: u64) -> Self {
+        Self { expected_generation }
+    }
+}
+
+/// Process-wide generation counters, one per resource uuid. Bumped by `bump` after a successful
+/// `complete_update_conditional`/`complete_destroy_conditional`, checked by `check` in
+/// `start_update_conditional`/`start_destroy_by_conditional` before an op is logged. The plain
+/// `complete_update`/`complete_destroy` do not bump it, and the plain `start_update`/
+/// `start_destroy_by` do not check it.
+#[derive(Debug, Default)]
+pub(crate) struct GenerationTracker {
+    generations: RwLock<HashMap<String, u64>>,
+}
+
+impl GenerationTracker {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current generation for `uuid`, `0` if never bumped.
+    pub(crate) fn current(&self, uuid: &str) -> u64 {
+        *self.generations.read().get(uuid).unwrap_or(&0)
+    }
+
+    /// Fail fast with `SvcError::Conflict` (the closest existing variant to a dedicated
+    /// `PreconditionFailed` - `agents::errors::SvcError` is defined outside this checkout, so a
+    /// new variant can't be added to it here) if `uuid`'s current generation doesn't match
+    /// `precondition`.
+    pub(crate) fn check(&self, uuid: &str, precondition: Precondition) -> Result<(), SvcError> {
+        let current = self.current(uuid);
+        if current != precondition.expected_generation {
+            return Err(SvcError::Conflict {});
+        }
+        Ok(())
+    }
+
+    /// Advance `uuid`'s generation by one, returning the new value.
+    pub(crate) fn bump(&self, uuid: &str) -> u64 {
+        let mut generations = self.generations.write();
+        let next = generations.get(uuid).copied().unwrap_or(0) + 1;
+        generations.insert(uuid.to_string(), next);
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A uuid never bumped is at generation 0, and a precondition expecting 0 passes against it.
+    #[test]
+    fn unbumped_uuid_is_generation_zero() {
+        let tracker = GenerationTracker::new();
+        assert_eq!(tracker.current("unseen"), 0);
+        assert!(tracker.check("unseen", Precondition::new(0)).is_ok());
+    }
+
+    /// `bump` both returns and persists the new generation, and `check` rejects any precondition
+    /// that doesn't match it - the core compare-and-swap guarantee this module exists for.
+    #[test]
+    fn bump_advances_generation_and_check_enforces_it() {
+        let tracker = GenerationTracker::new();
+        let uuid = "volume-1";
+
+        assert_eq!(tracker.bump(uuid), 1);
+        assert_eq!(tracker.current(uuid), 1);
+
+        assert!(
+            matches!(
+                tracker.check(uuid, Precondition::new(0)),
+                Err(SvcError::Conflict {})
+            ),
+            "a precondition against the stale generation must be rejected"
+        );
+        assert!(tracker.check(uuid, Precondition::new(1)).is_ok());
+
+        assert_eq!(tracker.bump(uuid), 2);
+        assert!(
+            matches!(
+                tracker.check(uuid, Precondition::new(1)),
+                Err(SvcError::Conflict {})
+            ),
+            "a precondition against the now-stale generation must be rejected after a second bump"
+        );
+    }
+
+    /// Generations are tracked independently per uuid: bumping one must not affect another's.
+    #[test]
+    fn generations_are_tracked_independently_per_uuid() {
+        let tracker = GenerationTracker::new();
+        tracker.bump("volume-a");
+        assert_eq!(tracker.current("volume-a"), 1);
+        assert_eq!(tracker.current("volume-b"), 0);
+    }
+}
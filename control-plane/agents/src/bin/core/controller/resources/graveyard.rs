@@ -0,0 +1,129 @@
+//! Tombstone tracking for resources that entered `Deleting`/`Deleted` or lost all owners.
+//!
+//! `fail_creating_to_deleting` flips a spec to `SpecStatus::Deleting` and clears its op, and
+//! `start_destroy_by`/`start_destroy_by_opt` call `disown_all` before attempting the data-plane
+//! delete - but today neither records anything durable, so if the process crashes before
+//! `complete_destroy` runs, the orphaned pool/replica/nexus just sits there until some unrelated
+//! reconciler pass happens to notice it. `Graveyard` gives every such resource a tombstone the
+//! moment it's marked for deletion, and only removes it once `complete_destroy` confirms the
+//! underlying object is actually gone - so a crash mid-delete leaves a durable breadcrumb instead
+//! of a silent leak.
+//!
+//! Like `fair_queue::FairQueues`, this is a process-wide singleton rather than a field threaded
+//! through every call site, since burying/exhuming happens from deep inside
+//! `GuardedOperationsHelper` default methods whose signatures are shared with ~15 external call
+//! sites and can't grow a `&Graveyard` parameter.
+//!
+//! Note: the request asks for the graveyard to be "a dedicated persisted collection in
+//! `ResourceSpecs`". Tombstones are tracked here in memory only for now - persisting them needs an
+//! `ObjectKey` impl for a `(ResourceKind, uuid)` pair, and `ObjectKey` is defined outside this
+//! checkout (see `QuarantinedEntry`'s `operations_helper.rs` doc comment for the same gap). On
+//! `init`, `ResourceSpecsLocked` reconciles this in-memory graveyard against freshly loaded specs,
+//! so at least within a single process's uptime a tombstone survives a retried delete.
+
+use agents::errors::SvcError;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use stor_port::transport_api::ResourceKind;
+
+/// Why a resource was buried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TombstoneReason {
+    /// A create failed and the spec was flipped to `Deleting` so the GC can clean it up.
+    FailedCreate,
+    /// A destroy was started; `disown_all` was called before attempting it on the data-plane.
+    Destroying,
+    /// The resource lost all of its owners (`disown_all` called outside of a destroy).
+    Disowned,
+}
+
+/// A durable-in-intent record of a resource that is mid-delete or orphaned, kept until the
+/// underlying object is confirmed gone.
+#[derive(Debug, Clone)]
+pub(crate) struct Tombstone {
+    pub(crate) kind: ResourceKind,
+    pub(crate) uuid: String,
+    pub(crate) reason: TombstoneReason,
+    pub(crate) tombstoned_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Process-wide graveyard of tombstones, keyed by resource uuid. See the module docs.
+#[derive(Debug, Default)]
+pub(crate) struct Graveyard {
+    tombstones: RwLock<HashMap<String, Tombstone>>,
+}
+
+static GRAVEYARD: Lazy<Graveyard> = Lazy::new(Graveyard::default);
+
+/// The process-wide `Graveyard` singleton used by `GuardedOperationsHelper`.
+pub(crate) fn graveyard() -> &'static Graveyard {
+    &GRAVEYARD
+}
+
+impl Graveyard {
+    /// Record a tombstone for `uuid`, overwriting any existing one (e.g. `Disowned` upgraded to
+    /// `Destroying` once the destroy actually starts).
+    pub(crate) fn bury(&self, kind: ResourceKind, uuid: impl Into<String>, reason: TombstoneReason) {
+        let uuid = uuid.into();
+        self.tombstones.write().insert(
+            uuid.clone(),
+            Tombstone {
+                kind,
+                uuid,
+                reason,
+                tombstoned_at: chrono::Utc::now(),
+            },
+        );
+    }
+
+    /// Remove `uuid`'s tombstone once its underlying object is confirmed gone.
+    pub(crate) fn exhume(&self, uuid: &str) {
+        self.tombstones.write().remove(uuid);
+    }
+
+    /// All tombstones currently tracked, for the background GC walker and operator introspection.
+    pub(crate) fn tombstones(&self) -> Vec<Tombstone> {
+        self.tombstones.read().values().cloned().collect()
+    }
+
+    /// Drop every tombstone whose uuid `live_uuids` rejects, keeping the rest. `init` does not
+    /// call this: it only re-buries specs still `Deleting`, and leaves tombstones for uuids that
+    /// did not load to `run_gc`.
+    pub(crate) fn retain_live(&self, live_uuids: impl Fn(&str) -> bool) {
+        self.tombstones.write().retain(|uuid, _| live_uuids(uuid));
+    }
+}
+
+/// Drives retries of the data-plane destroy for every tombstoned resource. Implemented per
+/// `ResourceKind` (volume/pool/replica/nexus destroy logic lives in `volume/operations.rs` and
+/// friends, outside this module), since the actual retry call is resource-specific; this trait
+/// just gives the background walker in `run_gc` a uniform shape to drive them through.
+#[async_trait::async_trait]
+pub(crate) trait GraveyardReaper {
+    /// Retry the data-plane destroy for `tombstone`, returning `Ok(true)` once the underlying
+    /// object is confirmed gone (so its tombstone can be exhumed), `Ok(false)` if it's still
+    /// being cleaned up, or `Err` if the retry itself failed.
+    async fn retry_destroy(&self, tombstone: &Tombstone) -> Result<bool, SvcError>;
+}
+
+/// Walk every tombstone once, retrying its destroy via `reaper` and exhuming it on confirmed
+/// success. Intended to be driven periodically (e.g. from the same poller loop that runs the
+/// dirty-spec reconciler) rather than looping internally, so its cadence is controlled the same
+/// way as every other `task_poller` job.
+pub(crate) async fn run_gc(reaper: &dyn GraveyardReaper) {
+    for tombstone in graveyard().tombstones() {
+        match reaper.retry_destroy(&tombstone).await {
+            Ok(true) => graveyard().exhume(&tombstone.uuid),
+            Ok(false) => {}
+            Err(error) => {
+                tracing::warn!(
+                    uuid = %tombstone.uuid,
+                    kind = ?tombstone.kind,
+                    %error,
+                    "graveyard GC retry failed, will retry on the next pass"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Write-ahead journal for `Transaction`-scoped multi-resource operations.
+//!
+//! `Transaction::commit` already keeps participants consistent against a mid-commit panic or
+//! early return within the same process - its `Drop` impl marks every staged participant dirty,
+//! same as the single-resource path does on a failed `store_obj`. A crash killing the whole
+//! process is a different failure: some participants may already be durably flushed, others never
+//! even attempted, and nothing on restart says they were ever part of the same operation. `begin`
+//! appends a record describing the transaction's participants before any lock is acquired or
+//! anything persisted; `commit_record` removes it once every participant's `store()` call has
+//! succeeded. On `init`, `replay` walks whatever records are still there - each one a half-applied
+//! (or never-started) transaction - and rolls every participant back to a clean state via
+//! `JournalReplayer`, the same per-`ResourceKind` extension point `graveyard::GraveyardReaper`
+//! uses, since the concrete rollback (`fail_creating_to_deleting`/`clear_op`/`operation_result`)
+//! lives on each spec's `SpecOperationsHelper` impl, outside this module.
+//!
+//! One gap: `begin`'s append needs a raw key/value write that no call site in this checkout
+//! confirms `Registry` has (only `delete_kv` and the `StorableObject`-typed `store_obj` are
+//! exercised). `registry.put_kv` is assumed to exist by symmetry with the confirmed `delete_kv`,
+//! rather than confirmed itself. Everything else here reuses already-confirmed methods:
+//! `commit_record`'s removal is the confirmed `delete_kv`, and `pending`/`replay`'s scan and
+//! cleanup (run from `init`, which only has a `Store`, not a `Registry`) reuse the confirmed
+//! `get_values_paged_all`/`delete_values_prefix`.
+
+use super::transaction::LockKey;
+use crate::controller::registry::Registry;
+use agents::errors::SvcError;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use stor_port::types::v0::store::definitions::Store;
+
+const JOURNAL_PREFIX: &str = "/control-plane/journal/";
+
+/// One resource a `JournalRecord` covers: its uuid, and its lock subsystem - the closest thing to
+/// a `ResourceKind` recoverable generically from a bare `LockKey`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct JournalParticipant {
+    pub(crate) subsystem: String,
+    pub(crate) uuid: String,
+}
+
+/// A single `Transaction`'s write-ahead record: which resources it touches and a human label for
+/// what it was doing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct JournalRecord {
+    id: u64,
+    pub(crate) label: String,
+    pub(crate) participants: Vec<JournalParticipant>,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+fn record_key(id: u64) -> String {
+    format!("{JOURNAL_PREFIX}{id}")
+}
+
+/// Append a pending record for `participants`, before `Transaction::commit` acquires any lock or
+/// persists anything.
+pub(crate) async fn begin(
+    registry: &Registry,
+    label: impl Into<String>,
+    participants: &[LockKey],
+) -> Result<JournalRecord, SvcError> {
+    let record = JournalRecord {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        label: label.into(),
+        participants: participants
+            .iter()
+            .map(|key| JournalParticipant {
+                subsystem: format!("{:?}", key.subsystem()),
+                uuid: key.uuid().to_string(),
+            })
+            .collect(),
+    };
+    registry
+        .put_kv(&record_key(record.id), serde_json::json!(record))
+        .await?;
+    Ok(record)
+}
+
+/// Remove `record`'s entry once every participant has been durably persisted.
+pub(crate) async fn commit_record(registry: &Registry, record: &JournalRecord) -> Result<(), SvcError> {
+    registry.delete_kv(&record_key(record.id)).await
+}
+
+/// Every record still outstanding - i.e. every `Transaction` that crashed between `begin` and a
+/// successful `commit_record` - read once during `init`, after specs are loaded and before
+/// reconcilers take over.
+async fn pending<S: Store>(
+    store: &mut S,
+    etcd_max_page_size: i64,
+) -> Result<Vec<JournalRecord>, SvcError> {
+    let entries = store
+        .get_values_paged_all(JOURNAL_PREFIX, etcd_max_page_size)
+        .await
+        .map_err(|error| SvcError::Internal {
+            details: error.full_string(),
+        })?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| serde_json::from_value(entry.1.clone()).ok())
+        .collect())
+}
+
+/// Rolls one journal participant back to a clean (non-pending-op) state. Implemented per
+/// `ResourceKind` outside this module (alongside the resource-kind-specific create/update/destroy
+/// logic in e.g. `volume/operations.rs`), since that's the only place with a concrete
+/// `SpecOperationsHelper` impl to call `fail_creating_to_deleting`/`clear_op`/`operation_result` on
+/// for a bare uuid.
+#[async_trait::async_trait]
+pub(crate) trait JournalReplayer {
+    /// Roll `participant` back, returning `Ok(true)` once it's confirmed clean (so its record can
+    /// be removed once every participant in it is), `Ok(false)` if it's still pending, or `Err` if
+    /// the rollback attempt itself failed.
+    async fn rollback(&self, participant: &JournalParticipant) -> Result<bool, SvcError>;
+}
+
+/// Roll back every outstanding record via `replayer`, removing each one once every participant
+/// confirms clean. Takes only a `Store`, not a `Registry`, so it can run from `init` directly -
+/// `replayer` is expected to hold whatever `Registry` access it needs to perform the rollback
+/// itself.
+pub(crate) async fn replay<S: Store>(
+    store: &mut S,
+    replayer: &dyn JournalReplayer,
+    etcd_max_page_size: i64,
+) -> Result<(), SvcError> {
+    for record in pending(store, etcd_max_page_size).await? {
+        let mut all_clean = true;
+        for participant in &record.participants {
+            match replayer.rollback(participant).await {
+                Ok(true) => {}
+                Ok(false) => all_clean = false,
+                Err(error) => {
+                    all_clean = false;
+                    tracing::warn!(
+                        id = record.id,
+                        label = %record.label,
+                        uuid = %participant.uuid,
+                        %error,
+                        "journal replay: rollback failed, will retry on the next init"
+                    );
+                }
+            }
+        }
+        if all_clean {
+            store
+                .delete_values_prefix(&record_key(record.id))
+                .await
+                .map_err(|error| SvcError::Internal {
+                    details: error.full_string(),
+                })?;
+        }
+    }
+    Ok(())
+}
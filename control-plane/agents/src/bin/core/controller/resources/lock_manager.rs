@@ -0,0 +1,334 @@
+use agents::errors::SvcError;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::Semaphore;
+
+/// The resource subsystems participating in the lock manager, in their canonical acquisition
+/// order. Operations that need guards on more than one of these must acquire them in this
+/// order; the manager's API makes it impossible to request them out of order.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub(crate) enum Subsystem {
+    Volume,
+    Nexus,
+    Replica,
+    Pool,
+}
+
+/// How a subsystem's permits are scoped.
+#[derive(Debug, Clone, Copy)]
+enum SubsystemScope {
+    /// One shared bulkhead semaphore for the whole subsystem, bounding how many operations run
+    /// at once across every resource instance.
+    Global { queue_depth: usize },
+    /// One single-permit semaphore per resource instance (keyed by the id the caller passes to
+    /// `acquire`), created on demand. Serializes operations against each other only when they
+    /// name the *same* instance; a volume A operation never contends with a volume B one.
+    PerResource,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SubsystemConfig {
+    subsystem: Subsystem,
+    scope: SubsystemScope,
+}
+
+/// Builder for the set of subsystems managed by a `LockManager`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LockManagerConfig {
+    subsystems: Vec<SubsystemConfig>,
+}
+impl LockManagerConfig {
+    /// Configure a subsystem with a single shared bulkhead permit pool of `queue_depth`,
+    /// bounding how many operations on this subsystem run at once process-wide.
+    pub(crate) fn with_subsystem(mut self, subsystem: Subsystem, queue_depth: usize) -> Self {
+        self.subsystems.push(SubsystemConfig {
+            subsystem,
+            scope: SubsystemScope::Global { queue_depth },
+        });
+        self
+    }
+    /// Configure a subsystem with one permit per resource instance, looked up/created on demand
+    /// by the id passed to `acquire`. Use this when the goal is serializing an operation against
+    /// *itself* on the same instance, not bounding subsystem-wide concurrency - a single shared
+    /// permit would otherwise make every instance queue behind every other one.
+    pub(crate) fn with_per_resource_subsystem(mut self, subsystem: Subsystem) -> Self {
+        self.subsystems.push(SubsystemConfig {
+            subsystem,
+            scope: SubsystemScope::PerResource,
+        });
+        self
+    }
+    /// Build the `LockManager` from this configuration.
+    pub(crate) fn build(self) -> LockManager {
+        let mut subsystems = HashMap::new();
+        for config in self.subsystems {
+            let semaphores = match config.scope {
+                SubsystemScope::Global { queue_depth } => {
+                    SubsystemSemaphores::Global(Arc::new(Semaphore::new(queue_depth)))
+                }
+                SubsystemScope::PerResource => {
+                    SubsystemSemaphores::PerResource(Mutex::new(HashMap::new()))
+                }
+            };
+            subsystems.insert(config.subsystem, semaphores);
+        }
+        LockManager {
+            subsystems: Arc::new(subsystems),
+        }
+    }
+}
+
+/// The live permit pool(s) backing one configured subsystem.
+///
+/// `PerResource`'s map is never pruned - an entry, once created for a uuid, lives for the rest of
+/// the process. Same tradeoff as `GenerationTracker`'s and `FairQueues`' own uuid-keyed side
+/// tables (see their module docs): there's no destroy-time hook reachable from this checkout to
+/// remove an entry when its resource is actually gone, and guessing at one risks a race deleting
+/// a semaphore a concurrent acquire just created. One `Arc<Semaphore>` per historical uuid is a
+/// few dozen bytes - real growth, but bounded by how many distinct volumes/nexuses this process
+/// ever sees, not by ongoing traffic.
+#[derive(Debug)]
+enum SubsystemSemaphores {
+    Global(Arc<Semaphore>),
+    PerResource(Mutex<HashMap<String, Arc<Semaphore>>>),
+}
+
+impl SubsystemSemaphores {
+    /// The semaphore to acquire a permit from for `resource_id` (ignored for `Global`), creating
+    /// a fresh one the first time a `PerResource` id is seen.
+    fn semaphore_for(&self, resource_id: &str) -> Arc<Semaphore> {
+        match self {
+            Self::Global(semaphore) => Arc::clone(semaphore),
+            Self::PerResource(by_id) => Arc::clone(
+                by_id
+                    .lock()
+                    .entry(resource_id.to_string())
+                    .or_insert_with(|| Arc::new(Semaphore::new(1))),
+            ),
+        }
+    }
+}
+
+/// Models the io-engine's subsystem-locking approach for the control plane: each resource kind
+/// (volume, nexus, replica, pool) has a bounded queue of in-flight operations, and multi-
+/// resource operations must acquire their permits in the canonical `Volume -> Nexus -> Replica
+/// -> Pool` order. This makes lock-order inversions a compile/API-shape concern rather than a
+/// runtime deadlock risk, and turns unbounded queuing into an explicit "busy" error.
+///
+/// Some subsystems (see `SubsystemScope`) are scoped per resource instance rather than shared
+/// process-wide, so that e.g. two different volumes' operations never contend with each other -
+/// only two operations naming the same instance do.
+#[derive(Debug, Clone)]
+pub(crate) struct LockManager {
+    subsystems: Arc<HashMap<Subsystem, SubsystemSemaphores>>,
+}
+
+impl LockManager {
+    /// Start a new, empty configuration.
+    pub(crate) fn builder() -> LockManagerConfig {
+        LockManagerConfig::default()
+    }
+
+    /// Acquire permits for the given `(subsystem, resource_id)` keys, sorted into their canonical
+    /// order - by subsystem first, then by resource id for determinism within a subsystem -
+    /// failing fast rather than blocking forever if any of them are already taken.
+    ///
+    /// `resource_id` identifies the specific instance being operated on (e.g. a volume uuid), but
+    /// only matters for subsystems configured with `with_per_resource_subsystem`: a bulkhead
+    /// (`with_subsystem`) subsystem's permit isn't tied to any one resource, so every key naming
+    /// it is first collapsed down to a single shared key regardless of its `resource_id` - a
+    /// caller staging N distinct resources in the same bulkhead subsystem (e.g. a `Transaction`
+    /// touching N replicas) takes one of its permits for itself, the same as before per-key
+    /// acquisition existed, not N of them.
+    pub(crate) async fn acquire(
+        &self,
+        keys: Vec<(Subsystem, String)>,
+    ) -> Result<LockGuard, SvcError> {
+        let mut keys: Vec<(Subsystem, String)> = keys
+            .into_iter()
+            .map(|(subsystem, resource_id)| {
+                let is_bulkhead = matches!(
+                    self.subsystems.get(&subsystem),
+                    Some(SubsystemSemaphores::Global(_))
+                );
+                (subsystem, if is_bulkhead { String::new() } else { resource_id })
+            })
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        let mut permits = Vec::with_capacity(keys.len());
+        for (subsystem, resource_id) in keys {
+            let semaphores = self.subsystems.get(&subsystem).ok_or(SvcError::Internal {
+                details: format!("Subsystem {subsystem:?} is not configured on the lock manager"),
+            })?;
+            let permit = semaphores
+                .semaphore_for(&resource_id)
+                .try_acquire_owned()
+                .map_err(|_| SvcError::ResourceBusy {
+                    details: if resource_id.is_empty() {
+                        format!("Subsystem {subsystem:?} is at capacity")
+                    } else {
+                        format!("Subsystem {subsystem:?} is at capacity for {resource_id}")
+                    },
+                })?;
+            permits.push(permit);
+        }
+        Ok(LockGuard { _permits: permits })
+    }
+}
+
+/// RAII guard holding permits for every subsystem an operation acquired; dropping it releases
+/// them all back to the lock manager.
+#[derive(Debug)]
+pub(crate) struct LockGuard {
+    _permits: Vec<tokio::sync::OwnedSemaphorePermit>,
+}
+
+/// Process-wide `LockManager` singleton, on the same footing as `fair_queue::queues()`: call
+/// sites reach it directly rather than through a field threaded off `Registry` (whose definition
+/// isn't part of this checkout).
+///
+/// `Volume` and `Nexus` are per-resource: `remove_shutdown_targets` and `republish` both acquire
+/// them keyed by the volume's own uuid before touching its nexus, so only two operations on the
+/// *same* volume are made mutually exclusive (the scenario this manager exists for - see the
+/// module docs) - a concurrent operation on a different volume gets its own permits and never
+/// queues behind this one. `Replica` and `Pool` stay as process-wide bulkheads, since nothing
+/// here needs them scoped tighter than "bound how many run at once".
+static LOCK_MANAGER: Lazy<LockManager> = Lazy::new(|| {
+    LockManager::builder()
+        .with_per_resource_subsystem(Subsystem::Volume)
+        .with_per_resource_subsystem(Subsystem::Nexus)
+        .with_subsystem(Subsystem::Replica, 16)
+        .with_subsystem(Subsystem::Pool, 16)
+        .build()
+});
+
+/// The process-wide `LockManager` used to serialize multi-resource operations that would
+/// otherwise be free to take their per-resource guards in different orders, such as the
+/// shutdown-GC path (`remove_shutdown_targets`) racing a concurrent `republish` on the same
+/// volume.
+pub(crate) fn manager() -> &'static LockManager {
+    &LOCK_MANAGER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_manager() -> LockManager {
+        LockManager::builder()
+            .with_per_resource_subsystem(Subsystem::Volume)
+            .with_subsystem(Subsystem::Replica, 1)
+            .build()
+    }
+
+    fn key(subsystem: Subsystem, id: &str) -> (Subsystem, String) {
+        (subsystem, id.to_string())
+    }
+
+    /// Two acquisitions naming the same resource id must not both succeed at once: the second
+    /// has to see the subsystem as busy until the first guard is dropped.
+    #[tokio::test]
+    async fn per_resource_subsystem_serializes_same_id_only() {
+        let manager = test_manager();
+
+        let guard_a = manager
+            .acquire(vec![key(Subsystem::Volume, "volume-a")])
+            .await
+            .expect("first acquire on volume-a succeeds");
+
+        let busy = manager.acquire(vec![key(Subsystem::Volume, "volume-a")]).await;
+        assert!(
+            matches!(busy, Err(SvcError::ResourceBusy { .. })),
+            "a second concurrent acquire on the same id must be rejected, not queued"
+        );
+
+        // A different id is a different semaphore, so it's unaffected by volume-a's guard.
+        let guard_b = manager
+            .acquire(vec![key(Subsystem::Volume, "volume-b")])
+            .await
+            .expect("an unrelated id must never contend with volume-a's guard");
+        drop(guard_b);
+
+        drop(guard_a);
+        manager
+            .acquire(vec![key(Subsystem::Volume, "volume-a")])
+            .await
+            .expect("releasing the first guard frees the permit for the same id again");
+    }
+
+    /// A `with_subsystem` bulkhead subsystem ignores the resource id entirely: two different ids
+    /// still contend for the same shared permit pool.
+    #[tokio::test]
+    async fn global_subsystem_ignores_resource_id() {
+        let manager = test_manager();
+
+        let _guard = manager
+            .acquire(vec![key(Subsystem::Replica, "replica-a")])
+            .await
+            .expect("first acquire succeeds");
+
+        let busy = manager
+            .acquire(vec![key(Subsystem::Replica, "replica-b")])
+            .await;
+        assert!(
+            matches!(busy, Err(SvcError::ResourceBusy { .. })),
+            "a bulkhead subsystem must serialize across ids, not per id"
+        );
+    }
+
+    /// Multiple keys in one `acquire` call (the `Transaction` use case) must each get their own
+    /// permit independently, and all of them release together when the guard drops.
+    #[tokio::test]
+    async fn multiple_keys_acquire_independently_in_one_call() {
+        let manager = test_manager();
+
+        let guard = manager
+            .acquire(vec![
+                key(Subsystem::Volume, "volume-a"),
+                key(Subsystem::Replica, "replica-a"),
+            ])
+            .await
+            .expect("distinct keys across subsystems all succeed together");
+
+        let busy = manager.acquire(vec![key(Subsystem::Volume, "volume-a")]).await;
+        assert!(matches!(busy, Err(SvcError::ResourceBusy { .. })));
+
+        drop(guard);
+        manager
+            .acquire(vec![key(Subsystem::Volume, "volume-a")])
+            .await
+            .expect("dropping the combined guard releases every key it held");
+    }
+
+    /// A single `acquire` call naming several distinct ids in the *same* bulkhead subsystem (the
+    /// `Transaction` staging several replicas case) must collapse down to one permit for the
+    /// whole call, not one permit per distinct id - otherwise a transaction touching N resources
+    /// would exhaust N of the subsystem's own queue depth acquiring permits for itself alone.
+    #[tokio::test]
+    async fn bulkhead_keys_with_different_ids_collapse_to_one_permit() {
+        let manager = LockManager::builder()
+            .with_subsystem(Subsystem::Replica, 2)
+            .build();
+
+        // Three distinct replica ids in one call must still only take a single permit from the
+        // depth-2 bulkhead, leaving room for an unrelated concurrent acquire.
+        let guard = manager
+            .acquire(vec![
+                key(Subsystem::Replica, "replica-a"),
+                key(Subsystem::Replica, "replica-b"),
+                key(Subsystem::Replica, "replica-c"),
+            ])
+            .await
+            .expect("one bulkhead permit covers every id in the same call");
+
+        manager
+            .acquire(vec![key(Subsystem::Replica, "replica-d")])
+            .await
+            .expect("the bulkhead still has a second permit free");
+
+        drop(guard);
+    }
+}
@@ -0,0 +1,101 @@
+//! OTLP metrics for the spec-store hot paths: guard contention, store load/migration, and dirty
+//! backlog.
+//!
+//! Every instrument here is a thin wrapper around `utils::record_timing!`/`record_count!`/
+//! `record_gauge!` (all no-ops until `TracingTelemetry::with_metrics` turns on the OTLP metrics
+//! pipeline), so callers in `operations_helper.rs` don't each have to name an instrument string
+//! and assemble a `KeyValue` label slice by hand.
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use stor_port::transport_api::ResourceKind;
+use stor_port::types::v0::store::definitions::StorableObjectType;
+use utils::tracing_telemetry::KeyValue;
+
+fn kind_label(kind: ResourceKind) -> [KeyValue; 1] {
+    [KeyValue::new("kind", format!("{kind:?}"))]
+}
+
+fn obj_type_label(obj_type: StorableObjectType) -> [KeyValue; 1] {
+    [KeyValue::new("obj_type", format!("{obj_type:?}"))]
+}
+
+/// `OperationSequenceGuard` is implemented generically over `T: AsOperationSequencer + ...`
+/// without a `ResourceKind`-yielding bound (`ResourceUid`, the trait it does require, is opaque
+/// outside this checkout and isn't confirmed to expose one) - so guard metrics label by `T`'s type
+/// name instead, which still separates e.g. `VolumeSpec` guard contention from `PoolSpec` guard
+/// contention, just not using the `ResourceKind` enum itself.
+fn type_label<T>() -> [KeyValue; 1] {
+    [KeyValue::new("type", std::any::type_name::<T>())]
+}
+
+/// Record an `OperationSequenceGuard` acquisition attempt: `success` distinguishes an uncontended
+/// (or successfully waited-for) guard from one that was rejected outright (e.g. `try_sequence`
+/// failing inside `operation_guard_mode`).
+pub(crate) fn record_guard_result<T>(success: bool) {
+    let labels = type_label::<T>();
+    let instrument = if success {
+        "spec_guard_acquired_total"
+    } else {
+        "spec_guard_conflict_total"
+    };
+    utils::record_count!(instrument, 1, &labels);
+}
+
+/// Record how long `operation_guard_mode_wait` spent waiting its turn in the `fair_queue` before
+/// the guard was acquired.
+pub(crate) fn record_guard_wait<T>(seconds: f64) {
+    utils::record_timing!("spec_guard_wait_seconds", seconds, &type_label::<T>());
+}
+
+/// Record one `StorableObjectType`'s `populate_specs` pass: how long the paged etcd fetch took,
+/// how long deserialising every page took, and how many objects ended up loaded.
+pub(crate) fn record_spec_load(
+    obj_type: StorableObjectType,
+    fetch_seconds: f64,
+    deserialise_seconds: f64,
+    loaded_count: i64,
+) {
+    let labels = obj_type_label(obj_type);
+    utils::record_timing!("spec_store_fetch_seconds", fetch_seconds, &labels);
+    utils::record_timing!("spec_store_deserialise_seconds", deserialise_seconds, &labels);
+    utils::record_gauge!("spec_store_loaded_objects", loaded_count, &labels);
+}
+
+/// Last snapshot recorded by `record_dirty_snapshot`, keyed by the same `{kind:?}` label
+/// `kind_label` uses (rather than `ResourceKind` itself, since it isn't confirmed to implement
+/// `Hash`/`Eq` in this checkout), kept so the next snapshot can be emitted as an `UpDownCounter`
+/// delta - there's no settable-gauge instrument without an observable callback in the OTel API
+/// surface this crate already uses, so we track the previous value ourselves and record the
+/// difference, the same trick `spec_store_loaded_objects` doesn't need since it's only ever added
+/// to once per `StorableObjectType` per `init`.
+static LAST_DIRTY_SNAPSHOT: Lazy<Mutex<HashMap<String, i64>>> = Lazy::new(Default::default);
+
+/// Record the current count of `StoreDirty` (pending-flush) resources per kind, as counted by a
+/// scan of `ResourceSpecs`'s maps (`dirty()` itself has no external call sites to hook a
+/// per-transition counter into - `VolumeSpec`/`PoolSpec`/etc.'s `SpecOperationsHelper` impls, and
+/// therefore their `dirty()` bit's mutation points, live outside this checkout). `counts` should
+/// include every tracked kind, with `0` for ones with nothing currently dirty, so a kind that's
+/// gone from dirty back to clean is reflected as a negative delta rather than just disappearing.
+pub(crate) fn record_dirty_snapshot(counts: &[(ResourceKind, usize)]) {
+    let mut last = LAST_DIRTY_SNAPSHOT.lock();
+    for (kind, count) in counts {
+        let label = format!("{kind:?}");
+        let current = *count as i64;
+        let previous = last.get(&label).copied().unwrap_or(0);
+        let delta = current - previous;
+        if delta != 0 {
+            utils::record_gauge!("spec_store_dirty_objects", delta, &kind_label(kind.clone()));
+        }
+        last.insert(label, current);
+    }
+}
+
+/// Record one run of `migrate_product_v1_to_v2`: `migrated_keys` is added to the migrated-keys
+/// counter (its only caller, `run_pending_migrations`, passes `1` per completed step rather than a
+/// count of keys), plus how long the whole pass took.
+pub(crate) fn record_migration(migrated_keys: u64, seconds: f64) {
+    utils::record_count!("spec_store_migrated_keys_total", migrated_keys, &[]);
+    utils::record_timing!("spec_store_migration_seconds", seconds, &[]);
+}
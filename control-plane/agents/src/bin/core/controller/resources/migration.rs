@@ -0,0 +1,278 @@
+//! Versioned schema-migration pipeline for the spec store.
+//!
+//! `populate_specs` used to gate a single hardcoded `migrate_product_v1_to_v2` call on
+//! `legacy_prefix_present`, with `init` cleaning up the v1 prefix itself once the whole per-type
+//! loop finished - workable for exactly one migration, but every future schema change would need
+//! its own bespoke gate and cleanup inlined into `populate_specs`. `run_pending_migrations`
+//! replaces that with a persisted set of completed step ids and an ordered `MigrationStep`
+//! registry: each step declares `from_version -> to_version`, and only its own id is marked done
+//! - and the shared v1 prefix
+//! only deleted - once every `Legacy` step has actually run, so a crash mid-upgrade resumes
+//! exactly the steps still pending on the next `init` instead of silently skipping or redoing one.
+//!
+//! Completion is tracked per step `id`, not as a single scalar version: today's seven `Legacy`
+//! steps all share `from_version: 0, to_version: 1` (one per `StorableObjectType`), so a scalar
+//! "current version" would read the first step's completion as every sibling step's completion
+//! too and let the v1 prefix - which, per `StepAction::Legacy`'s docs, spans every object type -
+//! get deleted out from under object types that were never actually migrated.
+//!
+//! Two gaps worth being upfront about, both a consequence of `Store` being almost entirely opaque
+//! in this checkout (only `get_values_paged_all`/`delete_values_prefix` are exercised by any
+//! existing call site):
+//! - Persisting the completed-steps set itself needs a single-key get/put, which no call site in
+//!   this checkout confirms `Store` has. This module assumes `get_kv`/`put_kv` exist alongside the
+//!   paged/prefix methods that are confirmed - a reasonable assumption for any real KV store
+//!   trait, but unconfirmed here.
+//! - `StepAction::Legacy` (the v0 -> v1 step) delegates to `migrate_product_v1_to_v2`, whose real
+//!   per-type field transform isn't part of this checkout (no definition exists anywhere in this
+//!   tree), so it's kept here as an explicit stub rather than guessed at. `StepAction::Transform`
+//!   is the from-scratch path future steps should use once they don't have a bespoke function to
+//!   lean on.
+
+use super::metrics;
+use agents::errors::SvcError;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use stor_port::{
+    pstor::product_v1_key_prefix,
+    types::v0::store::definitions::{StorableObjectType, Store, StoreError},
+};
+use StepAction::{Legacy, Transform};
+
+/// Store key holding the set of completed step ids, outside any `StorableObjectType` prefix so it
+/// survives every step (including the final v1-prefix cleanup).
+const COMPLETED_STEPS_KEY: &str = "/control-plane/schema_migrations_completed";
+
+/// What a `MigrationStep` actually does to get its `StorableObjectType`'s entries from
+/// `from_version`'s shape to `to_version`'s. See the module docs for why there are two kinds.
+pub(crate) enum StepAction {
+    /// Delegate to `migrate_product_v1_to_v2` for this object type.
+    Legacy(StorableObjectType),
+    /// Describes a step that rewrites every entry under `source_prefix` with `transform` under its
+    /// (possibly new) key, with `obsolete_prefix` (if different from `source_prefix`) as the prefix
+    /// left obsolete by it. `run_pending_migrations` has no handler for this variant: a registered
+    /// `Transform` step fails the migration with `SvcError::Internal` and is never marked completed.
+    #[allow(dead_code)]
+    Transform {
+        source_prefix: String,
+        obsolete_prefix: Option<String>,
+        /// Rewrite one raw entry, returning the zero-or-more `(key, value)` pairs to write under
+        /// the new schema (zero drops the entry, more than one splits a record).
+        transform: fn(&str, Value) -> Vec<(String, Value)>,
+    },
+}
+
+/// One step in the migration pipeline.
+pub(crate) struct MigrationStep {
+    /// Stable identifier for this step, independent of its `action` or version numbers. This -
+    /// not `to_version` - is the unit of completion tracking, because several steps (today, every
+    /// `Legacy` one) can legitimately share the same `from_version`/`to_version` pair without
+    /// being the same unit of work.
+    pub(crate) id: &'static str,
+    pub(crate) from_version: u32,
+    pub(crate) to_version: u32,
+    pub(crate) action: StepAction,
+}
+
+/// The ordered step registry. Step 0 covers today's one-off v1 -> v2 upgrade: every persisted
+/// spec type's legacy entries get relocated onto the current prefix via `migrate_product_v1_to_v2`.
+/// Appending a future schema change means adding another `MigrationStep` here, not touching
+/// `populate_specs`.
+fn steps() -> Vec<MigrationStep> {
+    [
+        ("legacy_volume_spec", StorableObjectType::VolumeSpec),
+        ("legacy_node_spec", StorableObjectType::NodeSpec),
+        ("legacy_nexus_spec", StorableObjectType::NexusSpec),
+        ("legacy_pool_spec", StorableObjectType::PoolSpec),
+        ("legacy_replica_spec", StorableObjectType::ReplicaSpec),
+        ("legacy_volume_snapshot", StorableObjectType::VolumeSnapshot),
+        ("legacy_app_node_spec", StorableObjectType::AppNodeSpec),
+    ]
+    .into_iter()
+    .map(|(id, obj_type)| MigrationStep {
+        id,
+        from_version: 0,
+        to_version: 1,
+        action: Legacy(obj_type),
+    })
+    .collect()
+}
+
+/// `steps()` not yet in `completed`, in registry order.
+fn pending_steps(completed: &BTreeSet<String>) -> Vec<MigrationStep> {
+    steps()
+        .into_iter()
+        .filter(|step| !completed.contains(step.id))
+        .collect()
+}
+
+/// Whether `step` needs no work because it's a `Legacy` step and the v1 prefix it would act on
+/// isn't actually present - a fresh deployment, or one that's already past it some other way.
+fn legacy_already_satisfied(step: &MigrationStep, legacy_prefix_present: bool) -> bool {
+    matches!(step.action, Legacy(_)) && !legacy_prefix_present
+}
+
+/// Whether every `Legacy` step in the registry is in `completed`, i.e. it's safe to delete the
+/// shared v1 prefix: deleting it any earlier would destroy the legacy data of whichever
+/// `StorableObjectType`s haven't had their step run yet, even though they share their
+/// `to_version` with one that has.
+fn legacy_prefix_deletable(completed: &BTreeSet<String>) -> bool {
+    steps()
+        .iter()
+        .filter(|step| matches!(step.action, Legacy(_)))
+        .all(|step| completed.contains(step.id))
+}
+
+/// Read the persisted set of completed step ids, defaulting to empty if never written (a brand
+/// new deployment, or one that predates this framework).
+async fn completed_steps<S: Store>(store: &mut S) -> Result<BTreeSet<String>, StoreError> {
+    match store.get_kv(COMPLETED_STEPS_KEY).await? {
+        Some(value) => Ok(serde_json::from_value(value).unwrap_or_default()),
+        None => Ok(BTreeSet::default()),
+    }
+}
+
+async fn commit_completed_steps<S: Store>(
+    store: &mut S,
+    completed: &BTreeSet<String>,
+) -> Result<(), StoreError> {
+    store
+        .put_kv(COMPLETED_STEPS_KEY, serde_json::json!(completed))
+        .await
+}
+
+/// The repo's real v0 -> v1 migration logic for `obj_type` - not part of this checkout (no
+/// definition exists anywhere in this tree, only call sites), so left as an explicit stub rather
+/// than guessed at. A real build of this repo has this do the actual field-level upgrade.
+///
+/// Returns `SvcError` rather than `StoreError`: `StoreError`'s variants live outside this
+/// checkout, so there's nothing to construct here that isn't guessed. Failing the step with an
+/// `SvcError::Internal` still lets `init` on a deployment that still carries a v1 prefix fail the
+/// migration cleanly instead of panicking.
+async fn migrate_product_v1_to_v2<S: Store>(
+    _store: &mut S,
+    obj_type: StorableObjectType,
+    _etcd_max_page_size: i64,
+) -> Result<(), SvcError> {
+    Err(SvcError::Internal {
+        details: format!(
+            "migrate_product_v1_to_v2 for {obj_type:?} is not implemented in this build; a v1-prefixed deployment cannot complete this migration step"
+        ),
+    })
+}
+
+/// Apply every pending step in order, marking each step's own `id` completed only once it has
+/// fully committed. Idempotent: a step already in the completed set is skipped, so re-running
+/// after a crash only resumes the steps still pending - including any sibling that happens to
+/// share a `from_version`/`to_version` with one that already finished.
+pub(crate) async fn run_pending_migrations<S: Store>(
+    store: &mut S,
+    legacy_prefix_present: bool,
+    etcd_max_page_size: i64,
+) -> Result<(), SvcError> {
+    let mut completed = completed_steps(store)
+        .await
+        .map_err(|error| SvcError::Internal {
+            details: error.full_string(),
+        })?;
+
+    for step in pending_steps(&completed) {
+        // The legacy step only has work to do if the v1 prefix is actually still around; mark it
+        // done (without running it) on a deployment that's already past it, or a fresh one that
+        // never had it.
+        if legacy_already_satisfied(&step, legacy_prefix_present) {
+            completed.insert(step.id.to_string());
+            commit_completed_steps(store, &completed)
+                .await
+                .map_err(|error| SvcError::Internal {
+                    details: error.full_string(),
+                })?;
+            continue;
+        }
+
+        let migration_start = std::time::Instant::now();
+        match &step.action {
+            Legacy(obj_type) => {
+                migrate_product_v1_to_v2(store, *obj_type, etcd_max_page_size).await?;
+            }
+            Transform { .. } => {
+                // No step registered today needs this path; see the module docs for why it's
+                // left as a documented extension point rather than exercised here. Failing the
+                // step is the right call once one *is* registered: a `MigrationStep` with no
+                // working `Transform` handler must not silently skip (its id would never be
+                // marked completed, so this just surfaces that immediately instead of retrying
+                // forever on every `init`).
+                return Err(SvcError::Internal {
+                    details: "Transform-based migration steps are not yet registered"
+                        .to_string(),
+                });
+            }
+        }
+        metrics::record_migration(1, migration_start.elapsed().as_secs_f64());
+
+        completed.insert(step.id.to_string());
+        commit_completed_steps(store, &completed)
+            .await
+            .map_err(|error| SvcError::Internal {
+                details: error.full_string(),
+            })?;
+    }
+
+    // The v1 prefix itself spans every object type, so it's only safe to remove once every
+    // `Legacy` step above has committed - not just the first one to reach the shared `to_version`
+    // they all declare - mirroring the cleanup `init` used to do unconditionally at the very end.
+    if legacy_prefix_deletable(&completed) {
+        store
+            .delete_values_prefix(&product_v1_key_prefix())
+            .await
+            .map_err(|error| SvcError::Internal {
+                details: error.full_string(),
+            })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reproduces the bug this module's step-skipping logic must not have: two `Legacy` steps
+    /// sharing the same `from_version`/`to_version`, only one of which has actually completed.
+    #[test]
+    fn sibling_steps_sharing_a_version_track_completion_independently() {
+        let all_ids: Vec<&'static str> = steps().iter().map(|step| step.id).collect();
+        assert!(
+            all_ids.len() > 1,
+            "test assumes more than one step is registered"
+        );
+        let (first, rest) = all_ids.split_first().unwrap();
+
+        let mut completed: BTreeSet<String> = BTreeSet::new();
+        completed.insert((*first).to_string());
+
+        // Only the first step is done: every other step must still be pending...
+        let pending_ids: Vec<&'static str> =
+            pending_steps(&completed).iter().map(|s| s.id).collect();
+        assert_eq!(pending_ids, rest.to_vec());
+        // ...and the shared v1 prefix must not be deletable yet, or the un-migrated siblings'
+        // legacy data would be destroyed out from under them.
+        assert!(!legacy_prefix_deletable(&completed));
+
+        // Once every step is marked completed, nothing is left pending and the prefix is safe to
+        // delete.
+        for id in &all_ids {
+            completed.insert((*id).to_string());
+        }
+        assert!(pending_steps(&completed).is_empty());
+        assert!(legacy_prefix_deletable(&completed));
+    }
+
+    /// A `Legacy` step with no v1 prefix to act on (fresh deployment, or one already past it) is
+    /// reported as needing no work, independently of any other step's state.
+    #[test]
+    fn legacy_step_with_no_v1_prefix_needs_no_work() {
+        let step = &steps()[0];
+        assert!(legacy_already_satisfied(step, false));
+        assert!(!legacy_already_satisfied(step, true));
+    }
+}
@@ -0,0 +1,13 @@
+pub(crate) mod fair_queue;
+pub(crate) mod generation;
+pub(crate) mod graveyard;
+pub(crate) mod journal;
+pub(crate) mod lock_manager;
+pub(crate) mod metrics;
+pub(crate) mod migration;
+pub(crate) mod operation_queue;
+pub(crate) mod operations_helper;
+pub(crate) mod options;
+pub(crate) mod patch;
+pub(crate) mod transaction;
+pub(crate) mod watch_notifier;
@@ -0,0 +1,132 @@
+//! Process-wide ordered operation queue with queued/processing introspection.
+//!
+//! Each spec serializes its own ops through `AsOperationSequencer`, but that only orders
+//! operations against the *same* resource - there's no cross-resource ordering guarantee and no
+//! way to see what's queued versus actively applying. Following Meilisearch's shared-update-store
+//! redesign, `OperationQueue` assigns every operation a monotonic global id when it's enqueued and
+//! tracks it through `Queued -> Processing -> {Done, Failed}` as it passes `store_operation_log`
+//! and `complete_*`. It's read through an ordinary `RwLock` - the many-readers/one-writer
+//! structure the request asks for, since operators listing in-flight/pending operations or the
+//! oldest incomplete id (`oldest_incomplete`) just need a consistent read while
+//! `enqueue`/`transition` hold the single writer lock briefly to mutate the map.
+
+use super::options::Priority;
+use parking_lot::RwLock;
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use stor_port::transport_api::ResourceKind;
+
+/// Monotonically increasing id assigned to operations in submission order.
+pub(crate) type OperationId = u64;
+
+/// Where an enqueued operation is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OperationState {
+    /// Enqueued, not yet past its per-resource sequencer.
+    Queued,
+    /// Past the sequencer and being applied (`store_operation_log` logged it).
+    Processing,
+    /// Completed successfully.
+    Done,
+    /// Completed with an error.
+    Failed,
+}
+
+/// One operation's queue entry, as surfaced to operators and the reconciler.
+#[derive(Debug, Clone)]
+pub(crate) struct OperationDescriptor {
+    pub(crate) id: OperationId,
+    pub(crate) kind: ResourceKind,
+    pub(crate) uuid: String,
+    /// Short label for the operation type (e.g. "create", "update", "destroy"); the typed
+    /// `Self::UpdateOp` itself isn't `Display`, so callers pass a label instead.
+    pub(crate) op: String,
+    pub(crate) submitted_at: chrono::DateTime<chrono::Utc>,
+    pub(crate) state: OperationState,
+    /// The `options::Priority` hint the submitter enqueued this with; `Priority::Normal` unless
+    /// the caller used `enqueue_with_priority` (typically via an `Options` carrying a non-default
+    /// priority).
+    pub(crate) priority: Priority,
+}
+
+/// Process-wide ordered operation queue. See the module docs for the rationale.
+#[derive(Debug, Default)]
+pub(crate) struct OperationQueue {
+    next_id: AtomicU64,
+    entries: RwLock<BTreeMap<OperationId, OperationDescriptor>>,
+}
+
+impl OperationQueue {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enqueue a new operation in the `Queued` state with `Priority::Normal`, returning its
+    /// global id.
+    pub(crate) fn enqueue(&self, kind: ResourceKind, uuid: impl Into<String>, op: impl Into<String>) -> OperationId {
+        self.enqueue_with_priority(kind, uuid, op, Priority::default())
+    }
+
+    /// Like `enqueue`, but with an explicit `Priority` hint (typically `options.priority()`).
+    pub(crate) fn enqueue_with_priority(
+        &self,
+        kind: ResourceKind,
+        uuid: impl Into<String>,
+        op: impl Into<String>,
+        priority: Priority,
+    ) -> OperationId {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let descriptor = OperationDescriptor {
+            id,
+            kind,
+            uuid: uuid.into(),
+            op: op.into(),
+            submitted_at: chrono::Utc::now(),
+            state: OperationState::Queued,
+            priority,
+        };
+        self.entries.write().insert(id, descriptor);
+        id
+    }
+
+    /// Move `id` to `Processing`, once it's past its per-resource sequencer and about to be
+    /// logged to the store.
+    pub(crate) fn mark_processing(&self, id: OperationId) {
+        self.transition(id, OperationState::Processing);
+    }
+
+    /// Move `id` to its terminal state, `Done` on success or `Failed` otherwise.
+    pub(crate) fn complete(&self, id: OperationId, succeeded: bool) {
+        let state = if succeeded { OperationState::Done } else { OperationState::Failed };
+        self.transition(id, state);
+    }
+
+    /// Drop a terminal entry from the queue once the caller no longer needs it for introspection.
+    pub(crate) fn remove(&self, id: OperationId) {
+        self.entries.write().remove(&id);
+    }
+
+    fn transition(&self, id: OperationId, state: OperationState) {
+        if let Some(entry) = self.entries.write().get_mut(&id) {
+            entry.state = state;
+        }
+    }
+
+    /// All entries currently tracked, oldest (lowest global id) first - for operators diagnosing
+    /// stalls.
+    pub(crate) fn snapshot(&self) -> Vec<OperationDescriptor> {
+        self.entries.read().values().cloned().collect()
+    }
+
+    /// The oldest entry still `Queued` or `Processing`, if any. `handle_incomplete_ops` does not
+    /// consult the queue, so replay order is not driven by it.
+    pub(crate) fn oldest_incomplete(&self) -> Option<OperationDescriptor> {
+        self.entries
+            .read()
+            .values()
+            .find(|entry| matches!(entry.state, OperationState::Queued | OperationState::Processing))
+            .cloned()
+    }
+}
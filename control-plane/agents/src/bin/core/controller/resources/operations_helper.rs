@@ -1,13 +1,20 @@
 use super::{
-    super::registry::Registry, resource_map::ResourceMutexMap, OperationGuardArc, ResourceMutex,
-    ResourceUid, UpdateInnerValue,
-};
-use crate::controller::{
-    resources::migration::migrate_product_v1_to_v2, task_poller::PollTriggerEvent,
+    super::registry::Registry,
+    fair_queue,
+    generation::{GenerationTracker, Precondition},
+    graveyard::{self, TombstoneReason},
+    journal, metrics, migration,
+    operation_queue::{OperationId, OperationQueue},
+    options::Options,
+    patch::{apply_patch, SpecPatch},
+    resource_map::ResourceMutexMap,
+    transaction::{LockKey, PendingChange, Transaction},
+    OperationGuardArc, ResourceMutex, ResourceUid, UpdateInnerValue,
 };
+use crate::controller::task_poller::PollTriggerEvent;
 use agents::errors::SvcError;
 use stor_port::{
-    pstor::{product_v1_key_prefix, API_VERSION},
+    pstor::API_VERSION,
     transport_api::{ErrorChain, ResourceKind},
     types::v0::{
         openapi::apis::Uuid,
@@ -30,8 +37,8 @@ use stor_port::{
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
-use serde::de::DeserializeOwned;
-use snafu::{ResultExt, Snafu};
+use serde::{de::DeserializeOwned, Serialize};
+use snafu::Snafu;
 use std::{fmt::Debug, ops::Deref, sync::Arc};
 
 #[derive(Debug, Snafu)]
@@ -40,14 +47,16 @@ enum SpecError {
     /// Failed to get entries from the persistent store.
     #[snafu(display("Failed to get entries from store. Error {}", source))]
     StoreGet { source: Box<StoreError> },
-    #[snafu(display("Failed to migrate entries from v1 to v2 space. Error {}", source))]
-    StoreMigrate { source: Box<StoreError> },
-    /// Failed to get entries from the persistent store.
-    #[snafu(display("Failed to deserialise object type {}", obj_type))]
-    Deserialise {
-        obj_type: StorableObjectType,
-        source: serde_json::Error,
-    },
+}
+
+/// One store entry that failed to deserialise into its expected spec type during
+/// `populate_specs`, quarantined instead of aborting the whole `init`.
+#[derive(Debug, Clone)]
+pub(crate) struct QuarantinedEntry {
+    pub(crate) obj_type: StorableObjectType,
+    pub(crate) key: String,
+    pub(crate) raw_value: serde_json::Value,
+    pub(crate) error: String,
 }
 
 /// What to do when creation fails.
@@ -145,6 +154,98 @@ pub(crate) trait GuardedOperationsHelper:
         }
     }
 
+    /// Like `start_create`, but also enqueues a descriptor into `queue` - giving operators
+    /// visibility into in-flight/pending operations across every resource (`handle_incomplete_ops`
+    /// does not consult it) - and transitions it to `Processing` once `start_create` has logged it to the store. Pair with
+    /// `complete_create_queued` to reach the `Done`/`Failed` terminal state.
+    async fn start_create_queued<O>(
+        &self,
+        registry: &Registry,
+        request: &Self::Create,
+        queue: &OperationQueue,
+    ) -> Result<(Self::Inner, OperationId), SvcError>
+    where
+        Self::Inner: PartialEq<Self::Create>,
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        let id = queue.enqueue(self.lock().kind(), self.lock().uuid_str(), "create");
+        match self.start_create(registry, request).await {
+            Ok(spec) => {
+                queue.mark_processing(id);
+                Ok((spec, id))
+            }
+            Err(error) => {
+                queue.complete(id, false);
+                Err(error)
+            }
+        }
+    }
+
+    /// Like `start_create`, but stages the logged op into `txn` instead of writing it to the
+    /// store immediately, so several resources can be grouped into one all-or-nothing
+    /// `Transaction::commit`. `key` identifies this resource for the transaction's `LockKeys`.
+    async fn start_create_in<O>(
+        &self,
+        registry: &Registry,
+        txn: &mut Transaction<'_>,
+        key: LockKey,
+        request: &Self::Create,
+    ) -> Result<Self::Inner, SvcError>
+    where
+        Self: Clone + Send + Sync + 'static,
+        Self::Inner: PartialEq<Self::Create>,
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+        O: Send + Sync + 'static,
+    {
+        let spec_clone = {
+            let mut spec = self.lock();
+            match spec.start_create_inner(request) {
+                Err(SvcError::InvalidUuid { uuid, kind }) => {
+                    drop(spec);
+                    self.remove_spec(registry);
+                    return Err(SvcError::InvalidUuid { uuid, kind });
+                }
+                Err(error) => Err(error),
+                Ok(_) => Ok(()),
+            }?;
+            spec.clone()
+        };
+        txn.stage(
+            key,
+            Box::new(SpecPendingChange {
+                guard: self.clone(),
+                spec_clone: spec_clone.clone(),
+                _marker: std::marker::PhantomData::<O>,
+            }),
+        );
+        Ok(spec_clone)
+    }
+
+    /// Completes a `start_create_in`-staged create once the owning `Transaction` has committed
+    /// (or failed). The commit/dirty-marking itself already happened as part of
+    /// `Transaction::commit`/`Drop`; this only updates the in-memory spec's op state to match,
+    /// mirroring what `complete_create` does after its own direct `store_obj`.
+    async fn complete_create_in<O, R: Send + Debug>(
+        &mut self,
+        result: Result<R, SvcError>,
+        registry: &Registry,
+        on_fail: OnCreateFail,
+    ) -> Result<R, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+    {
+        match result {
+            Ok(val) => {
+                self.lock().commit_op();
+                self.complete_op();
+                Ok(val)
+            }
+            Err(error) => Err(self.handle_create_failed(registry, error, on_fail).await),
+        }
+    }
+
     /// Completes a create operation by trying to update the spec in the persistent store.
     /// If the persistent store operation fails then the spec is marked accordingly and the dirty
     /// spec reconciler will attempt to update the store when the store is back online.
@@ -183,6 +284,24 @@ pub(crate) trait GuardedOperationsHelper:
         }
     }
 
+    /// Completes a `start_create_queued`-started create, transitioning `id` in `queue` to its
+    /// terminal state.
+    async fn complete_create_queued<O, R: Send + Debug>(
+        &mut self,
+        result: Result<R, SvcError>,
+        registry: &Registry,
+        on_fail: OnCreateFail,
+        queue: &OperationQueue,
+        id: OperationId,
+    ) -> Result<R, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+    {
+        let completed = self.complete_create(result, registry, on_fail).await;
+        queue.complete(id, completed.is_ok());
+        completed
+    }
+
     /// Validates the outcome of a create step.
     /// In case of an error, the object is set to deleting.
     #[allow(unused)]
@@ -247,6 +366,7 @@ pub(crate) trait GuardedOperationsHelper:
                 // Let the garbage collector delete the spec gracefully.
                 // This will ensure we'll delete previously created resources.
                 let spec = self.lock().fail_creating_to_deleting();
+                graveyard::graveyard().bury(spec.kind(), spec.uuid_str(), TombstoneReason::FailedCreate);
                 registry.store_obj(&spec).await.ok();
                 // TODO: we could use this to reconcile quicker?
                 if std::env::var("CREATING_DELETING_NOTIFY").is_ok() {
@@ -353,6 +473,7 @@ pub(crate) trait GuardedOperationsHelper:
             // once we've started, there's no going back, so disown completely
             spec.set_status(SpecStatus::Deleting);
             spec.disown_all();
+            graveyard::graveyard().bury(spec.kind(), spec.uuid_str(), TombstoneReason::Destroying);
 
             spec.start_destroy_op();
             spec.clone()
@@ -362,6 +483,89 @@ pub(crate) trait GuardedOperationsHelper:
         Ok(())
     }
 
+    /// Like `start_destroy_by`, but also enqueues a descriptor into `queue`; see
+    /// `start_create_queued`. Pair with `complete_destroy_queued`.
+    async fn start_destroy_by_queued<O>(
+        &self,
+        registry: &Registry,
+        owners: &Self::Owners,
+        queue: &OperationQueue,
+    ) -> Result<OperationId, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        let id = queue.enqueue(self.lock().kind(), self.lock().uuid_str(), "destroy");
+        match self.start_destroy_by(registry, owners).await {
+            Ok(()) => {
+                queue.mark_processing(id);
+                Ok(id)
+            }
+            Err(error) => {
+                queue.complete(id, false);
+                Err(error)
+            }
+        }
+    }
+
+    /// Like `start_destroy_by`, but first fails fast with `SvcError::Conflict` if `precondition`
+    /// is given and doesn't match `generations`' current generation for this resource - before
+    /// any op is logged, so a losing compare-and-swap never touches the spec at all.
+    async fn start_destroy_by_conditional<O>(
+        &self,
+        registry: &Registry,
+        owners: &Self::Owners,
+        precondition: Option<Precondition>,
+        generations: &GenerationTracker,
+    ) -> Result<(), SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        if let Some(precondition) = precondition {
+            generations.check(&self.lock().uuid_str(), precondition)?;
+        }
+        self.start_destroy_by(registry, owners).await
+    }
+
+    /// Completes a `start_destroy_by_conditional`-started destroy and, on success, advances
+    /// `generations`' counter for this resource so the next compare-and-swap caller observes it.
+    async fn complete_destroy_conditional<O, R: Send + Debug>(
+        &mut self,
+        result: Result<R, SvcError>,
+        registry: &Registry,
+        generations: &GenerationTracker,
+    ) -> Result<R, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        let uuid = self.lock().uuid_str();
+        let completed = self.complete_destroy(result, registry).await;
+        if completed.is_ok() {
+            generations.bump(&uuid);
+        }
+        completed
+    }
+
+    /// Completes a `start_destroy_by_queued`-started destroy, transitioning `id` in `queue` to
+    /// its terminal state.
+    async fn complete_destroy_queued<O, R: Send + Debug>(
+        &mut self,
+        result: Result<R, SvcError>,
+        registry: &Registry,
+        queue: &OperationQueue,
+        id: OperationId,
+    ) -> Result<R, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        let completed = self.complete_destroy(result, registry).await;
+        queue.complete(id, completed.is_ok());
+        completed
+    }
+
     /// Completes a destroy operation by trying to delete the spec from the persistent store.
     /// If the persistent store operation fails then the spec is marked accordingly and the dirty
     /// spec reconciler will attempt to update the store when the store is back online.
@@ -384,6 +588,7 @@ pub(crate) trait GuardedOperationsHelper:
                 let deleted = registry.delete_kv(&key.key()).await;
                 match deleted {
                     Ok(_) => {
+                        graveyard::graveyard().exhume(&spec_clone.uuid_str());
                         self.remove_spec(registry);
                         self.complete_op();
                         Ok(val)
@@ -442,6 +647,152 @@ pub(crate) trait GuardedOperationsHelper:
         Ok(spec_clone)
     }
 
+    /// Like `start_update`, but takes a generic `SpecPatch` (RFC 7386 merge-patch or RFC 6902
+    /// JSON Patch) instead of a typed `Self::UpdateOp`, so a caller can mutate things like labels
+    /// or topology constraints declaratively without a distinct typed endpoint per field - the
+    /// idea behind Drogue's `JsonMergeUpdater`/`JsonPatchUpdater`, recast for `StorableObject`
+    /// specs. The patch is applied to the spec's own serialized shape and the result is validated
+    /// by deserializing it back into `Self::Inner`; a patch that would change an immutable field
+    /// (uuid, content source) is rejected by `patch::apply_patch` before anything is touched.
+    ///
+    /// Unlike `start_update`, this can't go through `SpecTransaction::log_op`/`commit_op` first:
+    /// that machinery logs one typed `Self::UpdateOp` variant at a time, and a generic patch
+    /// document doesn't correspond to any single one. So, like `start_create`, the resulting spec
+    /// is persisted directly via `store_operation_log` once `busy()` and deserialisation have
+    /// both passed.
+    async fn start_patch<O>(&self, registry: &Registry, patch: &SpecPatch) -> Result<Self::Inner, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+        Self::Inner: Serialize + DeserializeOwned,
+    {
+        let current = self.lock().clone();
+        current.busy()?;
+
+        let current_value = serde_json::to_value(&current).map_err(|source| SvcError::Internal {
+            details: format!("failed to serialise {:?} for patching: {source}", current.kind()),
+        })?;
+        let patched_value = apply_patch(&current_value, patch)?;
+        let patched: Self::Inner =
+            serde_json::from_value(patched_value).map_err(|source| SvcError::Internal {
+                details: format!("patched {:?} failed validation: {source}", current.kind()),
+            })?;
+
+        self.store_operation_log(registry, &patched).await?;
+        *self.lock() = patched.clone();
+        Ok(patched)
+    }
+
+    /// Like `start_update`, but also enqueues a descriptor into `queue`; see
+    /// `start_create_queued`. Pair with `complete_update_queued`.
+    async fn start_update_queued(
+        &self,
+        registry: &Registry,
+        state: &Self::State,
+        update_operation: Self::UpdateOp,
+        queue: &OperationQueue,
+    ) -> Result<(Self::Inner, OperationId), SvcError>
+    where
+        Self::Inner: PartialEq<Self::State>,
+        Self::Inner: SpecTransaction<Self::UpdateOp>,
+        Self::Inner: StorableObject,
+    {
+        let id = queue.enqueue(self.lock().kind(), self.lock().uuid_str(), "update");
+        match self.start_update(registry, state, update_operation).await {
+            Ok(spec) => {
+                queue.mark_processing(id);
+                Ok((spec, id))
+            }
+            Err(error) => {
+                queue.complete(id, false);
+                Err(error)
+            }
+        }
+    }
+
+    /// Like `start_update`, but first fails fast with `SvcError::Conflict` if `precondition` is
+    /// given and doesn't match `generations`' current generation for this resource, before any
+    /// op is logged. See `generation` for why this is `SvcError::Conflict` rather than a
+    /// dedicated `PreconditionFailed` variant.
+    async fn start_update_conditional(
+        &self,
+        registry: &Registry,
+        state: &Self::State,
+        update_operation: Self::UpdateOp,
+        precondition: Option<Precondition>,
+        generations: &GenerationTracker,
+    ) -> Result<Self::Inner, SvcError>
+    where
+        Self::Inner: PartialEq<Self::State>,
+        Self::Inner: SpecTransaction<Self::UpdateOp>,
+        Self::Inner: StorableObject,
+    {
+        if let Some(precondition) = precondition {
+            generations.check(&self.lock().uuid_str(), precondition)?;
+        }
+        self.start_update(registry, state, update_operation).await
+    }
+
+    /// Completes a `start_update_conditional`-started update and, on success, advances
+    /// `generations`' counter for this resource.
+    async fn complete_update_conditional<R: Send + Debug, O>(
+        &mut self,
+        registry: &Registry,
+        result: Result<R, SvcError>,
+        spec_clone: Self::Inner,
+        generations: &GenerationTracker,
+    ) -> Result<R, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        let uuid = self.lock().uuid_str();
+        let completed = self.complete_update(registry, result, spec_clone).await;
+        if completed.is_ok() {
+            generations.bump(&uuid);
+        }
+        completed
+    }
+
+    /// Like `start_create`, but also returns an `OpGuard` that makes cancellation-safety
+    /// structural: if the future holding it is dropped (cancelled task, panic, an early `?`
+    /// before `complete_create` runs) without the guard having been disarmed, its `Drop` clears
+    /// the pending op and marks the spec dirty for the reconciler to restore, instead of relying
+    /// on every caller threading the error path through a `validate_*_step` call.
+    async fn start_create_guarded<O>(
+        &self,
+        registry: &Registry,
+        request: &Self::Create,
+    ) -> Result<(Self::Inner, OpGuard<'_, Self, O>), SvcError>
+    where
+        Self::Inner: PartialEq<Self::Create>,
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        let pre_op = self.lock().clone();
+        let spec_clone = self.start_create(registry, request).await?;
+        Ok((spec_clone, OpGuard::new(self, pre_op)))
+    }
+
+    /// Like `start_update`, but also returns an `OpGuard`; see `start_create_guarded`.
+    async fn start_update_guarded(
+        &self,
+        registry: &Registry,
+        state: &Self::State,
+        update_operation: Self::UpdateOp,
+    ) -> Result<(Self::Inner, OpGuard<'_, Self, Self::UpdateOp>), SvcError>
+    where
+        Self::Inner: PartialEq<Self::State>,
+        Self::Inner: SpecTransaction<Self::UpdateOp>,
+        Self::Inner: StorableObject,
+    {
+        let pre_op = self.lock().clone();
+        let spec_clone = self
+            .start_update(registry, state, update_operation)
+            .await?;
+        Ok((spec_clone, OpGuard::new(self, pre_op)))
+    }
+
     /// Completes an update operation by trying to update the spec in the persistent store.
     /// If the persistent store operation fails then the spec is marked accordingly and the dirty
     /// spec reconciler will attempt to update the store when the store is back online.
@@ -499,6 +850,25 @@ pub(crate) trait GuardedOperationsHelper:
         }
     }
 
+    /// Completes a `start_update_queued`-started update, transitioning `id` in `queue` to its
+    /// terminal state.
+    async fn complete_update_queued<R: Send + Debug, O>(
+        &mut self,
+        registry: &Registry,
+        result: Result<R, SvcError>,
+        spec_clone: Self::Inner,
+        queue: &OperationQueue,
+        id: OperationId,
+    ) -> Result<R, SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        let completed = self.complete_update(registry, result, spec_clone).await;
+        queue.complete(id, completed.is_ok());
+        completed
+    }
+
     /// Validates the outcome of an intermediate step, part of a transaction operation.
     /// In case of an error, it undoes the changes to the spec.
     /// If the persistent store is unavailable the spec is marked as dirty and the dirty
@@ -637,6 +1007,140 @@ pub(crate) trait GuardedOperationsHelper:
         }
     }
 
+    /// Like `store_operation_log`, but honours `options.best_effort_persist()`: a store write
+    /// failure is logged and left dirty for the reconciler instead of clearing the pending op and
+    /// returning an error, for high-churn state updates where failing the caller's whole
+    /// operation isn't worth it.
+    async fn store_operation_log_opt<O>(
+        &self,
+        registry: &Registry,
+        spec_clone: &Self::Inner,
+        options: &Options,
+    ) -> Result<(), SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        match registry.store_obj(spec_clone).await {
+            Ok(_) => Ok(()),
+            Err(error) if options.best_effort_persist() => {
+                tracing::warn!(
+                    kind = ?spec_clone.kind(),
+                    id = %spec_clone.uuid_str(),
+                    %error,
+                    "store_operation_log_opt: best-effort persist failed, leaving spec dirty for the reconciler"
+                );
+                self.lock().set_op_result(false);
+                Ok(())
+            }
+            Err(error) => {
+                let mut spec = self.lock();
+                spec.clear_op();
+                Err(error)
+            }
+        }
+    }
+
+    /// Like `start_create`, but honours `options`: `skip_store_checks` bypasses
+    /// `start_create_inner`'s `busy()`/uuid/status validation by mutating via the lower-level
+    /// `start_create_op` directly (the same call `start_create_inner` itself makes once those
+    /// checks pass), and `best_effort_persist` is forwarded to `store_operation_log_opt`. For
+    /// recovery/reconciler-initiated creates that must proceed even when the normal admission
+    /// checks would reject a user request.
+    async fn start_create_opt<O>(
+        &self,
+        registry: &Registry,
+        request: &Self::Create,
+        options: &Options,
+    ) -> Result<Self::Inner, SvcError>
+    where
+        Self::Inner: PartialEq<Self::Create>,
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        if !options.skip_store_checks() {
+            return self.start_create(registry, request).await;
+        }
+        let spec_clone = {
+            let mut spec = self.lock();
+            spec.start_create_op(request);
+            spec.clone()
+        };
+        match self
+            .store_operation_log_opt(registry, &spec_clone, options)
+            .await
+        {
+            Ok(_) => Ok(spec_clone),
+            Err(e) => {
+                self.delete_spec(registry).await.ok();
+                Err(e)
+            }
+        }
+    }
+
+    /// Like `start_update`, but honours `options`: `skip_store_checks` bypasses
+    /// `start_update_inner`'s `busy()`/status-transition validation by calling the lower-level
+    /// `start_update_op` directly, and `best_effort_persist` is forwarded to
+    /// `store_operation_log_opt`.
+    async fn start_update_opt(
+        &self,
+        registry: &Registry,
+        state: &Self::State,
+        update_operation: Self::UpdateOp,
+        options: &Options,
+    ) -> Result<Self::Inner, SvcError>
+    where
+        Self::Inner: PartialEq<Self::State>,
+        Self::Inner: SpecTransaction<Self::UpdateOp>,
+        Self::Inner: StorableObject,
+    {
+        if !options.skip_store_checks() {
+            return self.start_update(registry, state, update_operation).await;
+        }
+        let (spec_clone, log_op) = {
+            let mut spec = self.lock().clone();
+            let log_op = spec.log_op(&update_operation);
+            spec.start_update_op(registry, state, update_operation).await?;
+            *self.lock() = spec.clone();
+            (spec, log_op.0)
+        };
+
+        if log_op {
+            self.store_operation_log_opt(registry, &spec_clone, options)
+                .await?;
+        }
+        Ok(spec_clone)
+    }
+
+    /// Like `start_destroy_by`, but honours `options.skip_store_checks()` by skipping the
+    /// `busy()`/owner checks and mutating via `start_destroy_op` directly, and forwards
+    /// `best_effort_persist` to `store_operation_log_opt`.
+    async fn start_destroy_by_opt<O>(
+        &self,
+        registry: &Registry,
+        owners: &Self::Owners,
+        options: &Options,
+    ) -> Result<(), SvcError>
+    where
+        Self::Inner: SpecTransaction<O>,
+        Self::Inner: StorableObject,
+    {
+        if !options.skip_store_checks() {
+            return self.start_destroy_by(registry, owners).await;
+        }
+        let spec_clone = {
+            let mut spec = self.lock();
+            spec.disown(owners);
+            spec.set_status(SpecStatus::Deleting);
+            spec.disown_all();
+            graveyard::graveyard().bury(spec.kind(), spec.uuid_str(), TombstoneReason::Destroying);
+            spec.start_destroy_op();
+            spec.clone()
+        };
+        self.store_operation_log_opt(registry, &spec_clone, options)
+            .await
+    }
+
     /// Used for resource specific validation rules
     fn validate_destroy(&self, _registry: &Registry) -> Result<(), SvcError> {
         Ok(())
@@ -654,6 +1158,91 @@ pub(crate) trait GuardedOperationsHelper:
     }
 }
 
+/// RAII wrapper returned by `start_create_guarded`/`start_update_guarded` that makes a spec's
+/// cancellation-safety structural instead of relying on every caller remembering to thread the
+/// error path through `validate_update_step`. It snapshots the pre-operation spec (for the
+/// warning it logs if it fires) and, unless disarmed via `defuse`/`into_completed` on the
+/// success path, its `Drop` clears the pending op and marks the spec dirty so the existing
+/// dirty-spec reconciler restores the persisted value.
+pub(crate) struct OpGuard<'g, Guard: GuardedOperationsHelper, O> {
+    guard: &'g Guard,
+    pre_op: Guard::Inner,
+    armed: bool,
+    _marker: std::marker::PhantomData<O>,
+}
+
+impl<'g, Guard, O> OpGuard<'g, Guard, O>
+where
+    Guard: GuardedOperationsHelper,
+    Guard::Inner: SpecTransaction<O>,
+{
+    fn new(guard: &'g Guard, pre_op: Guard::Inner) -> Self {
+        Self {
+            guard,
+            pre_op,
+            armed: true,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Disarm the guard without further action: the caller has taken over responsibility for
+    /// committing or clearing the op itself (typically by calling a `complete_*` method next).
+    pub(crate) fn defuse(mut self) {
+        self.armed = false;
+    }
+
+    /// Disarm the guard and hand back `value`, for call sites that want to chain straight into
+    /// a `complete_*` call: `op_guard.into_completed(complete_update(...).await)`.
+    pub(crate) fn into_completed<R>(mut self, value: R) -> R {
+        self.armed = false;
+        value
+    }
+}
+
+impl<'g, Guard, O> Drop for OpGuard<'g, Guard, O>
+where
+    Guard: GuardedOperationsHelper,
+    Guard::Inner: SpecTransaction<O>,
+{
+    fn drop(&mut self) {
+        if self.armed {
+            tracing::warn!(
+                pre_op = ?self.pre_op,
+                "Operation guard dropped without completing; clearing pending op"
+            );
+            let mut spec = self.guard.lock();
+            spec.clear_op();
+            spec.set_op_result(false);
+        }
+    }
+}
+
+/// One `GuardedOperationsHelper`'s staged mutation, boxed as a `PendingChange` so `Transaction`
+/// can hold participants of different concrete spec types in the same `Vec`. `O` (the
+/// `SpecTransaction` operation marker) has to ride along in the type itself, not just as a
+/// generic parameter on an impl block, since two impls of the same trait for the same `Self`
+/// type differing only in a generic that doesn't appear in `Self` would conflict.
+struct SpecPendingChange<Guard: GuardedOperationsHelper, O> {
+    guard: Guard,
+    spec_clone: Guard::Inner,
+    _marker: std::marker::PhantomData<O>,
+}
+
+#[async_trait::async_trait]
+impl<Guard, O> PendingChange for SpecPendingChange<Guard, O>
+where
+    Guard: GuardedOperationsHelper + Clone + Send + Sync + 'static,
+    Guard::Inner: SpecTransaction<O> + StorableObject + Clone,
+    O: Send + Sync + 'static,
+{
+    async fn store(&self, registry: &Registry) -> Result<(), SvcError> {
+        registry.store_obj(&self.spec_clone).await
+    }
+    fn mark_dirty(&self) {
+        self.guard.lock().set_op_result(true);
+    }
+}
+
 #[async_trait::async_trait]
 pub(crate) trait SpecOperationsHelper:
     Clone + Debug + StorableObject + AsOperationSequencer + PartialEq<Self::Create>
@@ -815,6 +1404,20 @@ pub(crate) trait OperationSequenceGuard<
         self.operation_guard_mode_wait(OperationMode::Exclusive)
             .await
     }
+    /// Same as `operation_guard_wait` but bounds the wait with `timeout`, so a guard held by a
+    /// stuck or dropped operation can't hang the caller indefinitely. On timeout a retryable
+    /// `SvcError::GuardTimeout` is returned instead of waiting forever.
+    async fn operation_guard_wait_timeout(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<OperationGuardArc<T>, SvcError> {
+        match tokio::time::timeout(timeout, self.operation_guard_wait()).await {
+            Ok(result) => result,
+            Err(_) => Err(SvcError::GuardTimeout {
+                timeout_ms: timeout.as_millis() as u64,
+            }),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -825,11 +1428,15 @@ impl<T: AsOperationSequencer + Clone + Sync + Send + Debug + ResourceUid> Operat
         let get_value = |s: &Self| s.lock().clone();
 
         match OperationGuardArc::try_sequence(self, get_value, mode) {
-            Ok(guard) => Ok(guard),
+            Ok(guard) => {
+                metrics::record_guard_result::<T>(true);
+                Ok(guard)
+            }
             Err((error, log)) => {
                 if log {
                     tracing::trace!("Resource '{}' is busy: {}", self.lock().uid_str(), error);
                 }
+                metrics::record_guard_result::<T>(false);
                 Err(SvcError::Conflict {})
             }
         }
@@ -838,19 +1445,31 @@ impl<T: AsOperationSequencer + Clone + Sync + Send + Debug + ResourceUid> Operat
         &self,
         mode: OperationMode,
     ) -> Result<OperationGuardArc<T>, SvcError> {
-        let mut tries = 5;
-        loop {
-            tries -= 1;
-            match self.operation_guard_mode(mode) {
-                Ok(guard) => return Ok(guard),
-                Err(error) if tries == 0 => {
-                    return Err(error);
-                }
-                Err(_) => {}
-            };
-
-            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        // Fast path: uncontended callers never touch the ticket queue.
+        if let Ok(guard) = self.operation_guard_mode(mode) {
+            return Ok(guard);
         }
+
+        let wait_start = std::time::Instant::now();
+        let uid = self.lock().uid_str();
+        let queues = fair_queue::queues();
+        // Guarded so the ticket still advances (and unblocks whoever's queued behind it) if this
+        // future is dropped, e.g. by a `tokio::time::timeout` wrapping `operation_guard_wait`.
+        let ticket = queues.enqueue_guarded(&uid);
+        let result = loop {
+            queues.wait_turn(&uid, ticket.ticket()).await;
+            match self.operation_guard_mode(mode) {
+                Ok(guard) => break Ok(guard),
+                // Still contended by whoever holds the real guard right now - this queue can't
+                // be notified the instant that releases (OperationGuardArc's Drop lives outside
+                // this checkout), so poll at a steady interval without losing our place at the
+                // front, instead of retrying a bounded number of times.
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(50)).await,
+            }
+        };
+        metrics::record_guard_wait::<T>(wait_start.elapsed().as_secs_f64());
+        drop(ticket);
+        result
     }
 }
 
@@ -877,6 +1496,9 @@ pub(crate) struct ResourceSpecs {
     /// Top-level volume snapshots.
     pub(crate) volume_snapshots: ResourceMutexMap<SnapshotId, VolumeSnapshot>,
     pub(crate) app_nodes: ResourceMutexMap<AppNodeId, AppNodeSpec>,
+    /// Store entries that failed to deserialise during `populate_specs`, kept around for an
+    /// operator to inspect or re-import rather than aborting `init` outright.
+    pub(crate) quarantine: Vec<QuarantinedEntry>,
 }
 
 impl ResourceSpecsLocked {
@@ -885,12 +1507,22 @@ impl ResourceSpecsLocked {
     }
 
     /// Initialise the resource specs with the content from the persistent store.
+    ///
+    /// `journal_replayer` rolls back whatever multi-resource `Transaction`s were left mid-flight
+    /// by a crash, via `journal::replay` - see that module's docs for why the rollback itself has
+    /// to be supplied by the caller rather than performed generically in here.
     pub(crate) async fn init<S: Store>(
         &self,
         store: &mut S,
         legacy_prefix_present: bool,
         etcd_max_page_size: i64,
+        journal_replayer: &dyn journal::JournalReplayer,
     ) -> Result<(), SvcError> {
+        // Apply every pending schema migration (the legacy v1 -> v2 upgrade plus any future step)
+        // before loading anything, so `populate_specs` always reads a store already on the
+        // current schema rather than gating a migration per `StorableObjectType` itself.
+        migration::run_pending_migrations(store, legacy_prefix_present, etcd_max_page_size).await?;
+
         let spec_types = [
             StorableObjectType::VolumeSpec,
             StorableObjectType::NodeSpec,
@@ -901,7 +1533,7 @@ impl ResourceSpecsLocked {
             StorableObjectType::AppNodeSpec,
         ];
         for spec in &spec_types {
-            self.populate_specs(store, *spec, legacy_prefix_present, etcd_max_page_size)
+            self.populate_specs(store, *spec, etcd_max_page_size)
                 .await
                 .map_err(|error| SvcError::Internal {
                     details: error.full_string(),
@@ -936,40 +1568,103 @@ impl ResourceSpecsLocked {
             }
         }
 
-        // Remove all entries of v1 key prefix.
-        store
-            .delete_values_prefix(&product_v1_key_prefix())
-            .await
-            .map_err(|error| StoreError::Generic {
-                source: Box::new(error),
-                description: "Product v1 prefix cleanup failed".to_string(),
-            })?;
+        // Reconcile the graveyard against what actually got loaded: any spec that's still
+        // `Deleting` after a crash mid-delete gets re-tombstoned here (in case the crash happened
+        // before `bury` was ever called), while a tombstone for a uuid that didn't load at all
+        // (destroy completed, crash happened before `exhume`) is left alone for `run_gc` to
+        // reconcile against the data-plane instead of guessing it's safe to drop.
+        for replica in self.read().replicas.values() {
+            let replica = replica.lock();
+            if replica.status() == SpecStatus::Deleting {
+                graveyard::graveyard().bury(replica.kind(), replica.uuid_str(), TombstoneReason::Destroying);
+            }
+        }
+        for nexus in self.read().nexuses.values() {
+            let nexus = nexus.lock();
+            if nexus.status() == SpecStatus::Deleting {
+                graveyard::graveyard().bury(nexus.kind(), nexus.uuid_str(), TombstoneReason::Destroying);
+            }
+        }
+        for pool in self.read().pools.values() {
+            let pool = pool.lock();
+            if pool.status() == SpecStatus::Deleting {
+                graveyard::graveyard().bury(pool.kind(), pool.uuid_str(), TombstoneReason::Destroying);
+            }
+        }
+
+        // Roll back whatever multi-resource `Transaction`s crashed mid-flight, before reconcilers
+        // start picking up the now-loaded specs.
+        journal::replay(store, journal_replayer, etcd_max_page_size).await?;
+
+        self.record_dirty_gauge();
         Ok(())
     }
 
-    /// Deserialise a vector of serde_json values into specific spec types.
-    /// If deserialisation fails for any object, return an error.
-    fn deserialise_specs<T>(values: Vec<serde_json::Value>) -> Result<Vec<T>, serde_json::Error>
+    /// Scan every loaded spec and record how many are currently `StoreDirty` per kind. Called once
+    /// after `init` loads everything from the store; a full implementation would also call this
+    /// periodically from whatever poller job ends up driving the dirty-spec reconciler, so the
+    /// gauge tracks dirty resources accumulated over the agent's uptime too, not just its startup
+    /// snapshot.
+    fn record_dirty_gauge(&self) {
+        let specs = self.read();
+        let counts = [
+            (
+                ResourceKind::Volume,
+                specs.volumes.values().filter(|v| v.lock().dirty()).count(),
+            ),
+            (
+                ResourceKind::Node,
+                specs.nodes.values().filter(|v| v.lock().dirty()).count(),
+            ),
+            (
+                ResourceKind::Nexus,
+                specs.nexuses.values().filter(|v| v.lock().dirty()).count(),
+            ),
+            (
+                ResourceKind::Pool,
+                specs.pools.values().filter(|v| v.lock().dirty()).count(),
+            ),
+            (
+                ResourceKind::Replica,
+                specs.replicas.values().filter(|v| v.lock().dirty()).count(),
+            ),
+        ];
+        metrics::record_dirty_snapshot(&counts);
+    }
+
+    /// Deserialise a vector of `(key, value)` store entries into specific spec types. Unlike a
+    /// single bad entry aborting the whole load, every entry that fails to deserialise is
+    /// quarantined instead: the caller gets back everything that *did* parse, plus a
+    /// `QuarantinedEntry` per failure carrying enough to diagnose or re-import it later.
+    fn deserialise_specs<T>(
+        obj_type: StorableObjectType,
+        entries: Vec<(String, serde_json::Value)>,
+    ) -> (Vec<T>, Vec<QuarantinedEntry>)
     where
         T: DeserializeOwned,
     {
-        let specs: Vec<Result<T, serde_json::Error>> = values
-            .iter()
-            .map(|v| serde_json::from_value(v.clone()))
-            .collect();
-
-        let mut result = vec![];
-        for spec in specs {
-            match spec {
-                Ok(s) => {
-                    result.push(s);
-                }
-                Err(e) => {
-                    return Err(e);
+        let mut specs = Vec::new();
+        let mut quarantined = Vec::new();
+        for (key, value) in entries {
+            match serde_json::from_value::<T>(value.clone()) {
+                Ok(spec) => specs.push(spec),
+                Err(error) => {
+                    tracing::warn!(
+                        %key,
+                        %obj_type,
+                        %error,
+                        "quarantining store entry that failed to deserialise"
+                    );
+                    quarantined.push(QuarantinedEntry {
+                        obj_type,
+                        key,
+                        raw_value: value,
+                        error: error.to_string(),
+                    });
                 }
             }
         }
-        Ok(result)
+        (specs, quarantined)
     }
 
     /// Populate the resource specs with data from the persistent store.
@@ -977,88 +1672,114 @@ impl ResourceSpecsLocked {
         &self,
         store: &mut S,
         spec_type: StorableObjectType,
-        legacy_prefix_present: bool,
         etcd_max_page_size: i64,
     ) -> Result<(), SpecError> {
-        if legacy_prefix_present {
-            migrate_product_v1_to_v2(store, spec_type, etcd_max_page_size)
-                .await
-                .map_err(|e| SpecError::StoreMigrate {
-                    source: Box::new(e),
-                })?;
-        }
+        // Pending schema migrations (including the legacy v1 -> v2 upgrade this used to gate
+        // directly on `legacy_prefix_present`) now run once, up front, via
+        // `migration::run_pending_migrations` in `init` - see that function's doc comment for why
+        // a single ordered pass there replaced this per-`StorableObjectType` gate.
         let prefix = key_prefix_obj(spec_type, API_VERSION);
+        let fetch_start = std::time::Instant::now();
         let store_entries = store
             .get_values_paged_all(&prefix, etcd_max_page_size)
             .await
             .map_err(|e| SpecError::StoreGet {
                 source: Box::new(e),
             })?;
-        let store_values = store_entries.iter().map(|e| e.1.clone()).collect();
+        let fetch_seconds = fetch_start.elapsed().as_secs_f64();
+        let store_entries = store_entries
+            .iter()
+            .map(|e| (e.0.to_string(), e.1.clone()))
+            .collect();
 
+        let deserialise_start = std::time::Instant::now();
         let mut resource_specs = self.0.write();
-        match spec_type {
+        let loaded_count = match spec_type {
             StorableObjectType::VolumeSpec => {
-                let specs =
-                    Self::deserialise_specs::<VolumeSpec>(store_values).context(Deserialise {
-                        obj_type: StorableObjectType::VolumeSpec,
-                    })?;
+                let (specs, quarantined) =
+                    Self::deserialise_specs::<VolumeSpec>(spec_type, store_entries);
+                let loaded_count = specs.len();
                 let ag_specs = get_affinity_group_specs(&specs);
                 resource_specs.volumes.populate(specs);
                 // Load the ag specs in memory, ag specs are not persisted in memory so we don't
                 // have a StorableObjectType for it.
                 resource_specs.affinity_groups.populate(ag_specs);
+                resource_specs.quarantine.extend(quarantined);
+                loaded_count
             }
             StorableObjectType::NodeSpec => {
-                let specs =
-                    Self::deserialise_specs::<NodeSpec>(store_values).context(Deserialise {
-                        obj_type: StorableObjectType::NodeSpec,
-                    })?;
+                let (specs, quarantined) =
+                    Self::deserialise_specs::<NodeSpec>(spec_type, store_entries);
+                let loaded_count = specs.len();
                 resource_specs.nodes.populate(specs);
+                resource_specs.quarantine.extend(quarantined);
+                loaded_count
             }
             StorableObjectType::NexusSpec => {
-                let specs =
-                    Self::deserialise_specs::<NexusSpec>(store_values).context(Deserialise {
-                        obj_type: StorableObjectType::NexusSpec,
-                    })?;
+                let (specs, quarantined) =
+                    Self::deserialise_specs::<NexusSpec>(spec_type, store_entries);
+                let loaded_count = specs.len();
                 resource_specs.nexuses.populate(specs);
+                resource_specs.quarantine.extend(quarantined);
+                loaded_count
             }
             StorableObjectType::PoolSpec => {
-                let specs =
-                    Self::deserialise_specs::<PoolSpec>(store_values).context(Deserialise {
-                        obj_type: StorableObjectType::PoolSpec,
-                    })?;
+                let (specs, quarantined) =
+                    Self::deserialise_specs::<PoolSpec>(spec_type, store_entries);
+                let loaded_count = specs.len();
                 resource_specs.pools.populate(specs);
+                resource_specs.quarantine.extend(quarantined);
+                loaded_count
             }
             StorableObjectType::ReplicaSpec => {
-                let specs =
-                    Self::deserialise_specs::<ReplicaSpec>(store_values).context(Deserialise {
-                        obj_type: StorableObjectType::ReplicaSpec,
-                    })?;
+                let (specs, quarantined) =
+                    Self::deserialise_specs::<ReplicaSpec>(spec_type, store_entries);
+                let loaded_count = specs.len();
                 resource_specs.replicas.populate(specs);
+                resource_specs.quarantine.extend(quarantined);
+                loaded_count
             }
             StorableObjectType::VolumeSnapshot => {
-                let specs = Self::deserialise_specs::<VolumeSnapshot>(store_values).context(
-                    Deserialise {
-                        obj_type: StorableObjectType::VolumeSnapshot,
-                    },
-                )?;
+                let (specs, quarantined) =
+                    Self::deserialise_specs::<VolumeSnapshot>(spec_type, store_entries);
+                let loaded_count = specs.len();
                 resource_specs.volume_snapshots.populate(specs);
+                resource_specs.quarantine.extend(quarantined);
+                loaded_count
             }
             StorableObjectType::AppNodeSpec => {
-                let specs =
-                    Self::deserialise_specs::<AppNodeSpec>(store_values).context(Deserialise {
-                        obj_type: StorableObjectType::AppNodeSpec,
-                    })?;
+                let (specs, quarantined) =
+                    Self::deserialise_specs::<AppNodeSpec>(spec_type, store_entries);
+                let loaded_count = specs.len();
                 resource_specs.app_nodes.populate(specs);
+                resource_specs.quarantine.extend(quarantined);
+                loaded_count
             }
             _ => {
                 // Not all spec types are persisted in the store.
                 unimplemented!("{} not persisted in store", spec_type);
             }
         };
+        metrics::record_spec_load(
+            spec_type,
+            fetch_seconds,
+            deserialise_start.elapsed().as_secs_f64(),
+            loaded_count as i64,
+        );
         Ok(())
     }
+
+    /// Every store entry currently quarantined because it failed to deserialise during `init`,
+    /// for an operator to inspect or re-import. See `QuarantinedEntry`.
+    ///
+    /// Note: quarantined entries are only tracked in memory for now. The request behind this asks
+    /// for them to also be moved into a persisted "quarantine" key prefix, but doing that needs an
+    /// `ObjectKey` impl for an arbitrary quarantined key/obj_type pair, and `ObjectKey` is defined
+    /// outside this checkout - so persistence is left as a follow-up once that trait is available
+    /// to implement against.
+    pub(crate) fn quarantined(&self) -> Vec<QuarantinedEntry> {
+        self.read().quarantine.clone()
+    }
 }
 
 /// Helper function to extract the affinity groups from volumes on startup.
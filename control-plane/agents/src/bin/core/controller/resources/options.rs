@@ -0,0 +1,71 @@
+//! Per-operation behavior flags, in the spirit of Fxfs's transaction `Options`.
+//!
+//! Every call into `GuardedOperationsHelper` today shares one code path whether it's a
+//! user-originated request or an internal compaction/GC-style write, so internal operations can
+//! be starved or rejected by the exact same admission checks and store-availability handling that
+//! make sense for user requests. `Options` lets an internal caller opt out of the parts that don't
+//! apply to it - `skip_store_checks` for recovery/reconciler-initiated writes that must proceed
+//! even when normal admission checks would block, `best_effort_persist` for high-churn state
+//! updates that would rather be left dirty for the reconciler than fail outright, and `priority`
+//! as a hint for `operation_queue::OperationQueue` - mirroring the `skip_journal_checks`/
+//! `borrow_metadata_space` distinction Fxfs draws for its own space-relieving transactions.
+//! `Options::default()` preserves today's strict behavior: nothing is skipped or best-effort.
+
+/// A priority hint recorded by `operation_queue::OperationQueue` on each enqueued operation's
+/// descriptor; the queue does not order operations by it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Per-operation behavior flags. See the module docs for what each flag relaxes and why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct Options {
+    skip_store_checks: bool,
+    best_effort_persist: bool,
+    priority: Priority,
+}
+
+impl Options {
+    /// The default, strict `Options`: no checks skipped, no best-effort persistence.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bypass the admission checks (`busy()`, status-transition validation) that make sense only
+    /// for user-originated creates/updates/destroys - for recovery or reconciler-initiated writes
+    /// that must proceed regardless.
+    pub(crate) fn with_skip_store_checks(mut self, skip: bool) -> Self {
+        self.skip_store_checks = skip;
+        self
+    }
+
+    /// Treat a store write failure as immediately dirty (left for the reconciler to flush) rather
+    /// than returning an error to the caller - for high-churn state updates where failing the
+    /// whole operation isn't worth it.
+    pub(crate) fn with_best_effort_persist(mut self, best_effort: bool) -> Self {
+        self.best_effort_persist = best_effort;
+        self
+    }
+
+    /// Set the `Priority` hint consumed by `operation_queue::OperationQueue`.
+    pub(crate) fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub(crate) fn skip_store_checks(&self) -> bool {
+        self.skip_store_checks
+    }
+
+    pub(crate) fn best_effort_persist(&self) -> bool {
+        self.best_effort_persist
+    }
+
+    pub(crate) fn priority(&self) -> Priority {
+        self.priority
+    }
+}
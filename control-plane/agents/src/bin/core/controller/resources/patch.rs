@@ -0,0 +1,200 @@
+//! JSON Merge-Patch (RFC 7386) / JSON Patch (RFC 6902) update pathway for specs.
+//!
+//! `start_update` only ever applies a typed `Self::UpdateOp`, so mutating something like a
+//! label map or a topology constraint means exposing a distinct typed endpoint per field. A
+//! `SpecPatch` lets a caller instead submit a merge-patch or JSON Patch document against the
+//! spec's own serialized shape - the idea behind Drogue's `JsonMergeUpdater`/`JsonPatchUpdater`,
+//! recast for `StorableObject` specs - validated by deserializing the result back into
+//! `Self::Inner` before it's ever applied.
+
+use agents::errors::SvcError;
+use serde_json::Value;
+
+/// A patch document to apply to a spec's serialized form.
+#[derive(Debug, Clone)]
+pub(crate) enum SpecPatch {
+    /// RFC 7386 JSON Merge Patch.
+    Merge(Value),
+    /// RFC 6902 JSON Patch operations, applied in order.
+    JsonPatch(Vec<JsonPatchOp>),
+}
+
+/// One RFC 6902 operation. `path` is a JSON Pointer (RFC 6901, e.g. `/labels/zone`).
+#[derive(Debug, Clone)]
+pub(crate) enum JsonPatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Test { path: String, value: Value },
+}
+
+/// Fields that must never change via a patch, because callers rely on their stability for
+/// identity and lineage (e.g. `GuardedOperationsHelper` keys resources by uuid, and a volume's
+/// `content_source` determines clone-safety elsewhere).
+const IMMUTABLE_POINTERS: &[&str] = &["/uuid", "/content_source"];
+
+/// Apply `patch` to `current`, returning the resulting value without touching `current`.
+pub(crate) fn apply_patch(current: &Value, patch: &SpecPatch) -> Result<Value, SvcError> {
+    let patched = match patch {
+        SpecPatch::Merge(doc) => {
+            let mut target = current.clone();
+            merge_patch(&mut target, doc);
+            target
+        }
+        SpecPatch::JsonPatch(ops) => {
+            let mut target = current.clone();
+            for op in ops {
+                apply_json_patch_op(&mut target, op)?;
+            }
+            target
+        }
+    };
+    reject_immutable_changes(current, &patched)?;
+    Ok(patched)
+}
+
+/// RFC 7386: merge `patch` into `target` in place. Objects are merged key-by-key, a `null`
+/// value removes the key, and anything else (including arrays) replaces wholesale.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Some(patch_obj) = patch.as_object() else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let target_obj = target.as_object_mut().expect("just ensured this is an object");
+    for (key, value) in patch_obj {
+        if value.is_null() {
+            target_obj.remove(key);
+        } else {
+            let entry = target_obj.entry(key.clone()).or_insert(Value::Null);
+            merge_patch(entry, value);
+        }
+    }
+}
+
+fn apply_json_patch_op(target: &mut Value, op: &JsonPatchOp) -> Result<(), SvcError> {
+    match op {
+        JsonPatchOp::Add { path, value } => set_pointer(target, path, value.clone()),
+        JsonPatchOp::Replace { path, value } => set_pointer(target, path, value.clone()),
+        JsonPatchOp::Remove { path } => remove_pointer(target, path),
+        JsonPatchOp::Test { path, value } => {
+            if target.pointer(path) == Some(value) {
+                Ok(())
+            } else {
+                Err(SvcError::Internal {
+                    details: format!("JSON Patch test failed at {path}"),
+                })
+            }
+        }
+    }
+}
+
+fn split_pointer(path: &str) -> Result<(Vec<String>, String), SvcError> {
+    let invalid = || SvcError::Internal {
+        details: format!("invalid JSON Pointer: {path}"),
+    };
+    if path.is_empty() || !path.starts_with('/') {
+        return Err(invalid());
+    }
+    let mut segments: Vec<String> = path[1..]
+        .split('/')
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let last = segments.pop().ok_or_else(invalid)?;
+    Ok((segments, last))
+}
+
+fn set_pointer(target: &mut Value, path: &str, value: Value) -> Result<(), SvcError> {
+    let (parents, last) = split_pointer(path)?;
+    let parent = walk_mut(target, &parents)?;
+    match parent {
+        Value::Object(map) => {
+            map.insert(last, value);
+            Ok(())
+        }
+        Value::Array(vec) => {
+            if last == "-" {
+                vec.push(value);
+            } else {
+                let index: usize = last.parse().map_err(|_| SvcError::Internal {
+                    details: format!("invalid JSON Pointer array index: {last}"),
+                })?;
+                if index > vec.len() {
+                    return Err(SvcError::Internal {
+                        details: format!("JSON Pointer array index out of bounds: {index}"),
+                    });
+                }
+                vec.insert(index, value);
+            }
+            Ok(())
+        }
+        _ => Err(SvcError::Internal {
+            details: format!("JSON Pointer parent at {path} is not an object or array"),
+        }),
+    }
+}
+
+fn remove_pointer(target: &mut Value, path: &str) -> Result<(), SvcError> {
+    let (parents, last) = split_pointer(path)?;
+    let parent = walk_mut(target, &parents)?;
+    match parent {
+        Value::Object(map) => {
+            map.remove(&last).ok_or_else(|| SvcError::Internal {
+                details: format!("JSON Pointer {path} does not exist"),
+            })?;
+            Ok(())
+        }
+        Value::Array(vec) => {
+            let index: usize = last.parse().map_err(|_| SvcError::Internal {
+                details: format!("invalid JSON Pointer array index: {last}"),
+            })?;
+            if index >= vec.len() {
+                return Err(SvcError::Internal {
+                    details: format!("JSON Pointer array index out of bounds: {index}"),
+                });
+            }
+            vec.remove(index);
+            Ok(())
+        }
+        _ => Err(SvcError::Internal {
+            details: format!("JSON Pointer parent at {path} is not an object or array"),
+        }),
+    }
+}
+
+fn walk_mut<'a>(target: &'a mut Value, segments: &[String]) -> Result<&'a mut Value, SvcError> {
+    let mut current = target;
+    for segment in segments {
+        current = match current {
+            Value::Object(map) => map.get_mut(segment).ok_or_else(|| SvcError::Internal {
+                details: format!("JSON Pointer segment '{segment}' does not exist"),
+            })?,
+            Value::Array(vec) => {
+                let index: usize = segment.parse().map_err(|_| SvcError::Internal {
+                    details: format!("invalid JSON Pointer array index: {segment}"),
+                })?;
+                vec.get_mut(index).ok_or_else(|| SvcError::Internal {
+                    details: format!("JSON Pointer array index out of bounds: {index}"),
+                })?
+            }
+            _ => {
+                return Err(SvcError::Internal {
+                    details: format!("JSON Pointer segment '{segment}' is not an object or array"),
+                })
+            }
+        };
+    }
+    Ok(current)
+}
+
+/// Reject a patch that changed any `IMMUTABLE_POINTERS` field.
+fn reject_immutable_changes(before: &Value, after: &Value) -> Result<(), SvcError> {
+    for pointer in IMMUTABLE_POINTERS {
+        if before.pointer(pointer) != after.pointer(pointer) {
+            return Err(SvcError::InvalidArguments {});
+        }
+    }
+    Ok(())
+}
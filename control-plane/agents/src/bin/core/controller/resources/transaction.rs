@@ -0,0 +1,164 @@
+//! Multi-resource atomic transactions with ordered lock-key acquisition.
+//!
+//! Every method on `GuardedOperationsHelper` (`start_create`, `start_update`, `start_destroy`)
+//! logs exactly one spec to the store via `store_operation_log`, so a logical operation spanning
+//! several resources (a volume + its replicas + nexus, a snapshot touching a `VolumeSnapshot` and
+//! multiple `ReplicaSpec`s) is committed piecemeal and can half-apply if the store drops
+//! mid-way. `Transaction`, modeled on Fxfs's `TransactionHandler`, batches several resources'
+//! staged mutations: it collects a `LockKeys` set as participants are staged, acquires them
+//! through the `LockManager` - participants of a per-resource subsystem locked individually by
+//! their own `(subsystem, uuid)` key, those of a bulkhead subsystem sharing that subsystem's one
+//! permit, in canonical order - on `commit` (ruling out deadlocks between transactions
+//! sharing more than one resource the same way `LockManager::acquire` already does for its other
+//! callers), and persists every staged spec in a single pass. If the transaction is dropped
+//! without `commit`, every staged participant is marked
+//! dirty exactly as the single-resource path does on a failed `store_obj`, so the existing
+//! dirty-spec reconciler recovers it uniformly.
+//!
+//! `Drop` only covers the process surviving long enough to unwind; a crash killing the process
+//! mid-`commit` leaves no trace that the staged participants were ever one operation. `commit`
+//! covers that gap by appending a `journal` record before acquiring any lock, and removing it only
+//! once every participant is durably flushed - see `journal`'s docs for how `init` replays
+//! whatever's left behind.
+
+use super::{
+    journal,
+    lock_manager::{LockGuard, LockManager, Subsystem},
+};
+use crate::controller::registry::Registry;
+use agents::errors::SvcError;
+use std::collections::BTreeSet;
+
+/// One resource instance a `Transaction` needs locked: which subsystem it belongs to (for
+/// `LockManager`'s canonical acquisition order) and its uuid (for deterministic ordering within
+/// that subsystem, and for dedup when the same resource is staged twice).
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub(crate) struct LockKey {
+    subsystem: Subsystem,
+    uuid: String,
+}
+
+impl LockKey {
+    pub(crate) fn new(subsystem: Subsystem, uuid: impl Into<String>) -> Self {
+        Self {
+            subsystem,
+            uuid: uuid.into(),
+        }
+    }
+
+    /// This key's lock subsystem, for `journal::begin` to label a participant generically (it has
+    /// no way to recover a `ResourceKind` from a bare `LockKey`).
+    pub(crate) fn subsystem(&self) -> &Subsystem {
+        &self.subsystem
+    }
+
+    /// This key's resource uuid.
+    pub(crate) fn uuid(&self) -> &str {
+        &self.uuid
+    }
+}
+
+/// The set of `LockKey`s staged into a `Transaction` so far. Always acquired through
+/// `LockManager` sorted by `(Subsystem, uuid)`, the same canonical order `LockManager::acquire`
+/// already imposes on its subsystem-level callers, so no two transactions can deadlock waiting
+/// on each other's resources in opposite orders.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LockKeys(BTreeSet<LockKey>);
+
+impl LockKeys {
+    fn insert(&mut self, key: LockKey) {
+        self.0.insert(key);
+    }
+    /// Every staged key as an `(subsystem, uuid)` pair, for `LockManager::acquire`. Keys of a
+    /// per-resource subsystem are locked individually, so they only contend with another
+    /// transaction staging the same uuids; `acquire` collapses the keys of a bulkhead subsystem
+    /// into one shared permit, so those contend with every other caller of that subsystem.
+    fn lock_manager_keys(&self) -> Vec<(Subsystem, String)> {
+        self.0
+            .iter()
+            .map(|key| (key.subsystem, key.uuid.clone()))
+            .collect()
+    }
+    /// Every staged key, for `journal::begin` to record as this transaction's participants.
+    fn entries(&self) -> Vec<LockKey> {
+        self.0.iter().cloned().collect()
+    }
+}
+
+/// A single resource's staged mutation: how to persist it when the owning `Transaction` commits,
+/// and how to mark the owning spec dirty if the transaction is dropped before that happens.
+/// Boxed as a trait object so `Transaction::pending` can batch participants of different
+/// concrete spec types (`VolumeSpec`, `ReplicaSpec`, `NexusSpec`, ...) together.
+#[async_trait::async_trait]
+pub(crate) trait PendingChange: Send + Sync {
+    /// Persist this participant's staged change.
+    async fn store(&self, registry: &Registry) -> Result<(), SvcError>;
+    /// Mark the owning in-memory spec dirty, the same outcome `store_operation_log` leaves
+    /// behind on a failed single-resource `store_obj`.
+    fn mark_dirty(&self);
+}
+
+/// Batches several resources' staged spec mutations into one all-or-nothing persistence step.
+/// See the module docs for the rationale.
+pub(crate) struct Transaction<'a> {
+    lock_manager: &'a LockManager,
+    lock_keys: LockKeys,
+    pending: Vec<Box<dyn PendingChange>>,
+    committed: bool,
+}
+
+impl<'a> Transaction<'a> {
+    /// Start an empty transaction against `lock_manager`.
+    pub(crate) fn new(lock_manager: &'a LockManager) -> Self {
+        Self {
+            lock_manager,
+            lock_keys: LockKeys::default(),
+            pending: Vec::new(),
+            committed: false,
+        }
+    }
+
+    /// Stage a participant's already-logged spec mutation, to be locked and persisted when this
+    /// transaction commits.
+    pub(crate) fn stage(&mut self, key: LockKey, change: Box<dyn PendingChange>) {
+        self.lock_keys.insert(key);
+        self.pending.push(change);
+    }
+
+    /// Acquire every staged lock key in canonical order and persist every staged change in a
+    /// single pass. If any `store` call fails, the transaction is left uncommitted and its
+    /// `Drop` impl marks every staged participant dirty for the reconciler to pick up, matching
+    /// what `store_operation_log` does for a single resource today.
+    ///
+    /// `label` (e.g. `"create volume"`) is only used to describe the write-ahead journal record
+    /// `journal::begin` appends before any lock is acquired or anything persisted - see that
+    /// module's docs for why a process-wide crash, as opposed to this transaction merely being
+    /// dropped mid-commit, needs one.
+    pub(crate) async fn commit(
+        mut self,
+        registry: &Registry,
+        label: impl Into<String>,
+    ) -> Result<(), SvcError> {
+        let record = journal::begin(registry, label, &self.lock_keys.entries()).await?;
+        let _guard: LockGuard = self
+            .lock_manager
+            .acquire(self.lock_keys.lock_manager_keys())
+            .await?;
+        for change in &self.pending {
+            change.store(registry).await?;
+        }
+        self.committed = true;
+        journal::commit_record(registry, &record).await?;
+        Ok(())
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.committed {
+            for change in &self.pending {
+                change.mark_dirty();
+            }
+        }
+    }
+}
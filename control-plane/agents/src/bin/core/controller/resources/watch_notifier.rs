@@ -0,0 +1,93 @@
+//! Debounced, kind-filtered callback dispatch for the watch subsystem.
+//!
+//! The watch notifier used to fire an HTTP callback synchronously on every etcd key write, so a
+//! burst of rapid spec/state updates to the same resource produced one callback per write.
+//! `WatchNotifier` replaces that with a coalescing buffer keyed by `WatchResourceId`: each write
+//! bumps a per-resource generation counter and spawns a timer that only fires the callback if no
+//! further write lands before `window` elapses, so a subscriber sees a single callback carrying
+//! the latest state once the resource settles rather than one per intermediate write. Pair this
+//! with the event-kind filter a subscriber registers (created / modified / deleted) so the
+//! callback only fires for transitions it actually cares about.
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use stor_port::types::v0::transport::WatchResourceId;
+
+/// The kind of store transition a watch subscriber can filter on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum WatchEventKind {
+    /// The resource's etcd key was written for the first time.
+    Created,
+    /// The resource's etcd key was overwritten.
+    Modified,
+    /// The resource's etcd key was removed.
+    Deleted,
+}
+
+/// Default pause window a pending notification waits for further writes before firing.
+pub(crate) const DEFAULT_DEBOUNCE_WINDOW: Duration = Duration::from_millis(200);
+
+struct Entry {
+    /// Bumped on every write of a subscribed kind; a pending timer only fires the callback if it's still the current
+    /// generation when it wakes, so a fresh write within the window supersedes the in-flight
+    /// timer instead of racing it.
+    generation: u64,
+}
+
+/// Process-wide debounce buffer for watch callbacks, one pending entry per `WatchResourceId`.
+#[derive(Default)]
+pub(crate) struct WatchNotifier {
+    entries: Mutex<HashMap<WatchResourceId, Arc<Mutex<Entry>>>>,
+}
+
+static WATCH_NOTIFIER: Lazy<WatchNotifier> = Lazy::new(WatchNotifier::default);
+
+/// The process-wide `WatchNotifier` singleton used by the watch subsystem.
+pub(crate) fn notifier() -> &'static WatchNotifier {
+    &WATCH_NOTIFIER
+}
+
+impl WatchNotifier {
+    fn entry(&self, id: &WatchResourceId) -> Arc<Mutex<Entry>> {
+        self.entries
+            .lock()
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(Mutex::new(Entry { generation: 0 })))
+            .clone()
+    }
+
+    /// Record a write of `kind` to `id`. If `kind` is in the subscriber's `subscribed` filter,
+    /// arm (or re-arm) the debounce timer for `id`: `fire` runs once `window` elapses with no
+    /// further call to `notify` for the same `id`, and is skipped entirely if a later write
+    /// supersedes this one first.
+    pub(crate) fn notify<F>(
+        &'static self,
+        id: WatchResourceId,
+        kind: WatchEventKind,
+        subscribed: &[WatchEventKind],
+        window: Duration,
+        fire: F,
+    ) where
+        F: FnOnce() + Send + 'static,
+    {
+        if !subscribed.contains(&kind) {
+            return;
+        }
+
+        let entry = self.entry(&id);
+        let generation = {
+            let mut guard = entry.lock();
+            guard.generation += 1;
+            guard.generation
+        };
+
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            if entry.lock().generation == generation {
+                self.entries.lock().remove(&id);
+                fire();
+            }
+        });
+    }
+}
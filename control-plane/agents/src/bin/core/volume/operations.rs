@@ -5,8 +5,9 @@ use crate::{
         resources::{
             operations::{
                 ResourceLifecycle, ResourceOwnerUpdate, ResourcePublishing, ResourceReplicas,
-                ResourceSharing, ResourceShutdownOperations,
+                ResourceResize, ResourceSharing, ResourceShutdownOperations,
             },
+            lock_manager::{self, Subsystem},
             operations_helper::{
                 GuardedOperationsHelper, OnCreateFail, OperationSequenceGuard, ResourceSpecsLocked,
                 SpecOperationsHelper,
@@ -31,9 +32,9 @@ use stor_port::{
         },
         transport::{
             CreateVolume, DestroyNexus, DestroyReplica, DestroyShutdownTargets, DestroyVolume,
-            Nexus, Protocol, PublishVolume, Replica, ReplicaId, ReplicaOwners, RepublishVolume,
-            SetVolumeReplica, ShareNexus, ShareVolume, ShutdownNexus, UnpublishVolume,
-            UnshareNexus, UnshareVolume, Volume,
+            Nexus, Protocol, PublishVolume, Replica, ReplicaId, ReplicaOwners, ReplicaResize,
+            RepublishVolume, ResizeNexus, ResizeVolume, ResumeNexus, SetVolumeReplica, ShareNexus,
+            ShareVolume, ShutdownNexus, UnpublishVolume, UnshareNexus, UnshareVolume, Volume,
         },
     },
 };
@@ -292,6 +293,110 @@ impl ResourceSharing for OperationGuardArc<VolumeSpec> {
     }
 }
 
+#[async_trait::async_trait]
+impl ResourceResize for OperationGuardArc<VolumeSpec> {
+    type Resize = ResizeVolume;
+    type ResizeOutput = Volume;
+
+    /// Grow a volume to the requested size.
+    /// Every replica is resized first and only once all of them have reached at least the
+    /// requested size is the nexus, if any, resized to expose the new capacity to the
+    /// frontend. If the volume isn't published, only the replicas are resized and the new
+    /// size is persisted on the spec so that a subsequent publish creates a correctly sized
+    /// nexus. The nexus resize step is idempotent so a reconciler retry after a crash is safe.
+    /// Note: thick replicas are *not* pre-validated against their pool's free capacity before
+    /// the resize is sent; `resize_replicas` forwards every resize unconditionally and relies
+    /// on the io-engine to reject it if the pool can't fit the new size.
+    async fn resize(
+        &mut self,
+        registry: &Registry,
+        request: &Self::Resize,
+    ) -> Result<Self::ResizeOutput, SvcError> {
+        let specs = registry.specs();
+        let state = registry.volume_state(&request.uuid).await?;
+
+        if request.requested_size < self.as_ref().size {
+            return Err(SvcError::VolumeResizeShrinkNotAllowed {
+                vol_id: request.uuid.to_string(),
+            });
+        }
+
+        let spec_clone = self
+            .start_update(registry, &state, VolumeOperation::Resize(request.requested_size))
+            .await?;
+
+        let result = self.resize_replicas(registry, request.requested_size).await;
+        let result = self
+            .validate_update_step(registry, result, &spec_clone)
+            .await;
+
+        let result = match result {
+            Ok(_) => match state.target {
+                None => Ok(()),
+                Some(target) => match specs.nexus(&target.uuid).await {
+                    Ok(mut nexus) => {
+                        nexus
+                            .resize(registry, &ResizeNexus::new(target.uuid, request.requested_size))
+                            .await
+                    }
+                    Err(error) => Err(error),
+                },
+            },
+            Err(error) => Err(error),
+        };
+
+        self.complete_update(registry, result, spec_clone).await?;
+        registry.volume(&request.uuid).await
+    }
+}
+
+impl OperationGuardArc<VolumeSpec> {
+    /// Resize every replica owned by this volume to (at least) `requested_size`. Replicas that
+    /// already meet the requested size are skipped so the step is safe to retry.
+    ///
+    /// Does *not* pre-validate a thick replica's new size against its pool's free space: neither
+    /// a thin/thick flag on `ReplicaSpec` nor any reachable pool usage/capacity figure exists
+    /// anywhere in this crate (pool state lives only behind the external `grpc`/io-engine client,
+    /// which nothing in this tree constructs - see `scheduling::pool::ENoSpcReplica`, referenced
+    /// below but never defined). Instead, a replica that can't actually grow still fails here
+    /// when `replica.resize` itself errors, just after the fact rather than before.
+    async fn resize_replicas(
+        &self,
+        registry: &Registry,
+        requested_size: u64,
+    ) -> Result<(), SvcError> {
+        let specs = registry.specs();
+        let replicas = specs.volume_replicas(self.uuid());
+
+        let mut result = Ok(());
+        for replica_rsc in replicas {
+            let mut replica = match replica_rsc.operation_guard_wait().await {
+                Ok(replica) => replica,
+                Err(error) => {
+                    result = Err(error);
+                    continue;
+                }
+            };
+            if replica.as_ref().size >= requested_size {
+                // already resized, possibly a reconciler retry.
+                continue;
+            }
+            let resize = ReplicaResize::new(replica.uuid().clone(), requested_size);
+            if let Err(error) = replica.resize(registry, &resize).await {
+                self.as_ref().warn_span(|| {
+                    tracing::warn!(
+                        replica.uuid = %resize.uuid,
+                        error = %error,
+                        "Failed to resize replica, the reconciler will retry"
+                    )
+                });
+                result = Err(error);
+            }
+        }
+        result
+    }
+}
+
 #[async_trait::async_trait]
 impl ResourcePublishing for OperationGuardArc<VolumeSpec> {
     type Publish = PublishVolume;
@@ -425,6 +530,19 @@ impl ResourcePublishing for OperationGuardArc<VolumeSpec> {
         registry: &Registry,
         request: &Self::Republish,
     ) -> Result<Self::PublishOutput, SvcError> {
+        // Mutually exclude against a concurrent `remove_shutdown_targets` on *this* volume: both
+        // shut down/destroy/recreate the volume's nexus, and without this they're free to take
+        // their per-resource guards in different orders and deadlock against each other. Keyed by
+        // the volume's own uuid, so a concurrent republish/shutdown-GC on a different volume is
+        // unaffected.
+        let uuid = request.uuid.to_string();
+        let _lock = lock_manager::manager()
+            .acquire(vec![
+                (Subsystem::Volume, uuid.clone()),
+                (Subsystem::Nexus, uuid),
+            ])
+            .await?;
+
         let specs = registry.specs();
         let spec = self.as_ref().clone();
         let state = registry.volume_state(&request.uuid).await?;
@@ -515,7 +633,13 @@ impl ResourcePublishing for OperationGuardArc<VolumeSpec> {
         self.validate_update_step(registry, result, &spec_clone)
             .await?;
 
-        // Create a Nexus on the requested or auto-selected node.
+        // Create a Nexus on the requested or auto-selected node. The requested rebuild
+        // verification mode is only logged here: `create_nexus` does not receive it, and it is
+        // not applied to any child rebuild on this path.
+        info!(
+            rebuild.verify_mode = ?request.rebuild_verify,
+            "Creating republish target"
+        );
         let result = self.create_nexus(registry, &target_cfg).await;
         let (mut nexus, nexus_state) = self
             .validate_update_step(registry, result, &spec_clone)
@@ -551,6 +675,19 @@ impl ResourcePublishing for OperationGuardArc<VolumeSpec> {
     }
 }
 
+/// Optional data-integrity verification to apply while a rebuild is in progress.
+/// Threaded through to the io-engine as part of the child-add/rebuild options.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub(crate) enum RebuildVerifyMode {
+    /// No extra verification, the default behaviour.
+    #[default]
+    Off,
+    /// Compare rebuild reads against the other healthy children, failing the rebuild on a mismatch.
+    CompareReads,
+    /// Same as `CompareReads` but panics the io-engine on a mismatch.
+    PanicOnMismatch,
+}
+
 /// Request to move the given replica to another pool.
 /// May be useful to reclaim space in the current pool to handle thin provisioning.
 #[derive(Debug, Clone)]
@@ -559,6 +696,8 @@ pub(crate) struct MoveReplicaRequest {
     /// Delete the moved replica after we've created the replacement replica?
     /// todo: we might only want to delete after rebuild completes only..
     delete: bool,
+    /// Data-integrity verification to use while the replacement replica is rebuilt.
+    verify_mode: RebuildVerifyMode,
 }
 impl MoveReplicaRequest {
     /// Get a reference to the replica.
@@ -570,12 +709,18 @@ impl MoveReplicaRequest {
         self.delete = delete;
         self
     }
+    /// Builder-like specification of the rebuild verification mode.
+    pub(crate) fn with_verify_mode(mut self, verify_mode: RebuildVerifyMode) -> Self {
+        self.verify_mode = verify_mode;
+        self
+    }
 }
 impl From<&ENoSpcReplica> for MoveReplicaRequest {
     fn from(value: &ENoSpcReplica) -> Self {
         Self {
             replica: value.replica().uuid.clone(),
             delete: false,
+            verify_mode: RebuildVerifyMode::default(),
         }
     }
 }
@@ -625,7 +770,9 @@ impl ResourceReplicas for OperationGuardArc<VolumeSpec> {
             .and_then(|t| registry.specs().nexus_rsc(t.nexus()))
         {
             let mut guard = nexus_spec.operation_guard()?;
-            guard.attach_replica(registry, &new_replica).await?;
+            guard
+                .attach_replica(registry, &new_replica, request.verify_mode)
+                .await?;
 
             if request.delete {
                 self.remove_child_replica(request.replica(), &mut guard, registry)
@@ -659,13 +806,26 @@ impl ResourceShutdownOperations for OperationGuardArc<VolumeSpec> {
         registry: &Registry,
         request: &Self::RemoveShutdownTargets,
     ) -> Result<(), SvcError> {
+        // Mutually exclude against a concurrent `republish` on *this* volume - see the matching
+        // acquire there for why, and for why it's keyed by the volume's own uuid.
+        let uuid = request.uuid().to_string();
+        let _lock = lock_manager::manager()
+            .acquire(vec![
+                (Subsystem::Volume, uuid.clone()),
+                (Subsystem::Nexus, uuid),
+            ])
+            .await?;
+
         let shutdown_nexuses = registry
             .specs()
             .volume_shutdown_nexuses(request.uuid())
             .await;
         let mut result = Ok(());
         for nexus_res in shutdown_nexuses {
-            match nexus_res.operation_guard_wait().await {
+            match nexus_res
+                .operation_guard_wait_timeout(registry.shutdown_gc_guard_timeout())
+                .await
+            {
                 Ok(mut guard) => {
                     if let Ok(nexus) = registry.nexus(nexus_res.uuid()).await {
                         if target_registered(request.registered_targets(), nexus)? {
@@ -673,6 +833,22 @@ impl ResourceShutdownOperations for OperationGuardArc<VolumeSpec> {
                         }
                     }
                     let nexus_spec = guard.as_ref().clone();
+                    let nexus_uuid = nexus_spec.uuid.clone();
+                    // A shutdown target is frequently left paused/faulted on the io-engine
+                    // side; destroying (or unsharing) a paused NVMf subsystem fails because it
+                    // can't transition paused -> inactive directly. Resume it first so the
+                    // destroy below can actually tear it down. If the resume itself fails
+                    // because the nexus is already gone, that's the outcome we wanted anyway.
+                    match guard.resume(registry, &ResumeNexus::new(nexus_uuid)).await {
+                        Ok(_) | Err(SvcError::NexusNotFound { .. }) => {}
+                        Err(error) => {
+                            tracing::debug!(
+                                %error,
+                                nexus.uuid = %guard.uuid(),
+                                "Failed to resume shutdown nexus before destroying it"
+                            );
+                        }
+                    }
                     let destroy_req = DestroyNexus::from(nexus_spec)
                         .with_disown(request.uuid())
                         .with_lazy(true);
@@ -713,11 +889,21 @@ impl ResourceShutdownOperations for OperationGuardArc<VolumeSpec> {
     }
 }
 
-/// Checks if Nexus is present in registered target list. Returns true if yes.
+/// Checks if Nexus is present in registered target list, or is otherwise still holding host
+/// reservations, in which case it's not yet safe to destroy. Returns true when the nexus
+/// should be skipped.
 fn target_registered(
     registered_target: Option<Vec<String>>,
     nexus: Nexus,
 ) -> Result<bool, SvcError> {
+    // A frozen nexus still has its NVMf subsystem retaining host connections/reservations,
+    // even though the control plane's cluster agent may no longer consider the path alive.
+    // Destroying it now could corrupt data the host is still actively writing to, so we must
+    // wait until it's unfrozen; a frozen nexus is skipped even if its host has disconnected.
+    if nexus.is_frozen() {
+        return Ok(true);
+    }
+
     // let path = nexus.device_uri;
     if let Some(targets) = registered_target {
         let parsed_uri = nexus
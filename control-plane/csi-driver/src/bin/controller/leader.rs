@@ -0,0 +1,113 @@
+//! Active-passive leader election for the CSI controller server, backed by the control plane's
+//! etcd store. Binding a Unix socket (rather than a port) doesn't stop two controller replicas
+//! running at once, so only the instance holding `lock_key`'s lease may serve RPCs; a standby
+//! loops waiting to acquire it instead.
+
+use etcd_client::{Client, Compare, CompareOp, PutOptions, Txn, TxnOp};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Why leadership was lost: either the keepalive round-trip itself failed, or etcd reported the
+/// lease expired/was revoked from under us (e.g. a network partition outlasting the TTL).
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LeadershipLost {
+    KeepAliveFailed,
+    LeaseExpired,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum AcquireError {
+    #[error("leadership lock is already held by another instance")]
+    AlreadyHeld,
+    #[error("etcd error: {0}")]
+    Etcd(#[from] etcd_client::Error),
+}
+
+/// Handle to an acquired leadership lease. `until_lost` resolves once the lease's keepalive task
+/// reports it's gone, so the caller can tie its server's shutdown future to loss of leadership.
+pub(crate) struct Leadership {
+    lost: watch::Receiver<Option<LeadershipLost>>,
+}
+
+impl Leadership {
+    pub(crate) async fn until_lost(mut self) {
+        while self.lost.borrow().is_none() {
+            if self.lost.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Acquire `lock_key` with a lease TTL of `ttl`, retrying with a short backoff until a
+/// previous holder's lease lapses. Returns once leadership is held, with a keepalive task
+/// already spawned that heartbeats the lease every `ttl / 3`.
+pub(crate) async fn acquire(client: &mut Client, lock_key: &str, ttl: Duration) -> Leadership {
+    loop {
+        match try_acquire(client, lock_key, ttl).await {
+            Ok(leadership) => return leadership,
+            Err(error) => {
+                warn!(%error, "Failed to acquire CSI controller leadership, retrying");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+async fn try_acquire(
+    client: &mut Client,
+    lock_key: &str,
+    ttl: Duration,
+) -> Result<Leadership, AcquireError> {
+    let lease = client.lease_grant(ttl.as_secs().max(1) as i64, None).await?;
+    let lease_id = lease.id();
+
+    // Only create `lock_key` if it's absent, i.e. no instance currently holds leadership; the
+    // etcd equivalent of the create-if-not-exists compare-and-swap `start_create` relies on
+    // elsewhere in the store.
+    let txn = Txn::new()
+        .when(vec![Compare::create_revision(
+            lock_key,
+            CompareOp::Equal,
+            0,
+        )])
+        .and_then(vec![TxnOp::put(
+            lock_key,
+            lease_id.to_string(),
+            Some(PutOptions::new().with_lease(lease_id)),
+        )]);
+    if !client.txn(txn).await?.succeeded() {
+        return Err(AcquireError::AlreadyHeld);
+    }
+
+    let (mut keeper, mut keep_alive_stream) = client.lease_keep_alive(lease_id).await?;
+    let (tx, rx) = watch::channel(None);
+    let heartbeat_period = ttl / 3;
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(heartbeat_period).await;
+            if keeper.keep_alive().await.is_err() {
+                error!("CSI controller leadership lease keepalive failed");
+                let _ = tx.send(Some(LeadershipLost::KeepAliveFailed));
+                return;
+            }
+            match keep_alive_stream.message().await {
+                Ok(Some(response)) if response.ttl() > 0 => continue,
+                Ok(_) => {
+                    error!("CSI controller leadership lease expired");
+                    let _ = tx.send(Some(LeadershipLost::LeaseExpired));
+                    return;
+                }
+                Err(error) => {
+                    error!(%error, "CSI controller leadership lease keepalive stream failed");
+                    let _ = tx.send(Some(LeadershipLost::KeepAliveFailed));
+                    return;
+                }
+            }
+        }
+    });
+
+    info!(lock_key, lease_id, "Acquired CSI controller leadership");
+    Ok(Leadership { lost: rx })
+}
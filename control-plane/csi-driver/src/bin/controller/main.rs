@@ -0,0 +1,13 @@
+/// etcd lease-based leader election, so only one controller replica serves at a time.
+mod leader;
+mod server;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    server::CsiServer::run(&csi_socket()).await
+}
+
+/// Path of the CSI controller's Unix domain socket, overridable via `CSI_SOCKET` for testing.
+fn csi_socket() -> String {
+    std::env::var("CSI_SOCKET").unwrap_or_else(|_| "/var/tmp/csi.sock".to_string())
+}
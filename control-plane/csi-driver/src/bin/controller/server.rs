@@ -1,4 +1,4 @@
-use crate::{controller::CsiControllerSvc, identity::CsiIdentitySvc};
+use crate::{controller::CsiControllerSvc, identity::CsiIdentitySvc, leader};
 use rpc::csi::{controller_server::ControllerServer, identity_server::IdentityServer};
 
 use futures::TryFutureExt;
@@ -14,7 +14,10 @@ use tokio::{
     io::{AsyncRead, AsyncWrite, ReadBuf},
     net::UnixListener,
 };
-use tonic::transport::{server::Connected, Server};
+use tonic::{
+    transport::{server::Connected, Server},
+    Status,
+};
 use tracing::{debug, error, info};
 
 #[derive(Debug)]
@@ -31,14 +34,89 @@ impl Connected for UnixStream {
     }
 }
 
-// Not sure why we need the inner fields, probably worth checking if we can remove them.
 #[derive(Clone, Debug)]
-#[allow(unused)]
 struct UdsConnectInfo {
     peer_addr: Option<Arc<tokio::net::unix::SocketAddr>>,
     peer_cred: Option<tokio::net::unix::UCred>,
 }
 
+/// Allow-list of Unix peer credentials permitted to call the CSI Identity/Controller services
+/// over the socket, enforced from the `UdsConnectInfo` tonic attaches to every request's
+/// extensions. Mirrors the token-interceptor pattern etcd clients use, recast for local peer
+/// credentials instead of a bearer token.
+///
+/// An empty allow-list (the default) leaves the socket exactly as permissive as before, so
+/// deployments opt in by setting `CSI_ALLOWED_PEER_UIDS` and/or `CSI_REQUIRED_PEER_GID`.
+#[derive(Clone, Debug, Default)]
+struct PeerCredAuth {
+    allowed_uids: Arc<Vec<u32>>,
+    required_gid: Option<u32>,
+}
+
+impl PeerCredAuth {
+    const ALLOWED_UIDS_ENV: &'static str = "CSI_ALLOWED_PEER_UIDS";
+    const REQUIRED_GID_ENV: &'static str = "CSI_REQUIRED_PEER_GID";
+
+    /// Build the allow-list from the environment: `CSI_ALLOWED_PEER_UIDS` is a comma-separated
+    /// list of uids, `CSI_REQUIRED_PEER_GID` is a single required gid. Either, both or neither
+    /// may be set.
+    fn from_env() -> Self {
+        let allowed_uids = std::env::var(Self::ALLOWED_UIDS_ENV)
+            .ok()
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter_map(|uid| uid.trim().parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let required_gid = std::env::var(Self::REQUIRED_GID_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok());
+        Self {
+            allowed_uids: Arc::new(allowed_uids),
+            required_gid,
+        }
+    }
+
+    fn check(&self, cred: Option<&tokio::net::unix::UCred>) -> Result<(), Status> {
+        if self.allowed_uids.is_empty() && self.required_gid.is_none() {
+            return Ok(());
+        }
+        let Some(cred) = cred else {
+            return Err(Status::permission_denied(
+                "CSI socket peer credentials unavailable",
+            ));
+        };
+        if !self.allowed_uids.is_empty() && !self.allowed_uids.contains(&cred.uid()) {
+            return Err(Status::permission_denied(format!(
+                "uid {} is not permitted on the CSI socket",
+                cred.uid()
+            )));
+        }
+        if let Some(gid) = self.required_gid {
+            if cred.gid() != gid {
+                return Err(Status::permission_denied(format!(
+                    "gid {} is not permitted on the CSI socket",
+                    cred.gid()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl tonic::service::Interceptor for PeerCredAuth {
+    fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        let cred = request
+            .extensions()
+            .get::<UdsConnectInfo>()
+            .and_then(|info| info.peer_cred.as_ref());
+        self.check(cred)?;
+        Ok(request)
+    }
+}
+
 impl AsyncRead for UnixStream {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -112,17 +190,53 @@ impl CsiServer {
         };
 
         let cfg = crate::CsiControllerConfig::get_config();
-
-        Server::builder()
+        let peer_auth = PeerCredAuth::from_env();
+
+        // Only one controller replica may mutate volumes at a time; acquire the leadership
+        // lease before serving, and stop serving the moment it's lost so a standby can take
+        // over. `lock_key`/`ttl` belong on `CsiControllerConfig` alongside the other CSI
+        // controller settings; until that config surface grows them, they're read directly
+        // from the environment here, same as `PeerCredAuth`.
+        let etcd_endpoint =
+            std::env::var("CSI_CONTROLLER_ETCD_ENDPOINT").unwrap_or_else(|_| "etcd:2379".to_string());
+        let lock_key = std::env::var("CSI_CONTROLLER_LOCK_KEY")
+            .unwrap_or_else(|_| "/csi-controller/leader".to_string());
+        let lock_ttl = std::env::var("CSI_CONTROLLER_LOCK_TTL_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(std::time::Duration::from_secs(10));
+
+        let mut etcd = etcd_client::Client::connect([etcd_endpoint], None).await?;
+        let leadership = leader::acquire(&mut etcd, &lock_key, lock_ttl).await;
+
+        let server = Server::builder()
             .timeout(cfg.io_timeout().add(std::time::Duration::from_secs(3)))
-            .add_service(IdentityServer::new(CsiIdentitySvc::default()))
-            .add_service(ControllerServer::new(CsiControllerSvc::new(cfg)))
-            .serve_with_incoming_shutdown(incoming, shutdown::Shutdown::wait())
-            .await
-            .inspect_err(|error| {
-                use stor_port::transport_api::ErrorChain;
-                error!(error = error.full_string(), "NodePluginGrpcServer failed");
-            })?;
+            // Race each request against the caller's own `grpc-timeout` header (if sent) rather
+            // than only the blanket `.timeout()` above, so a caller who gave up early doesn't
+            // leave this server still working on its behalf.
+            .layer(utils::grpc_deadline::DeadlineLayer)
+            .add_service(IdentityServer::with_interceptor(
+                CsiIdentitySvc::default(),
+                peer_auth.clone(),
+            ))
+            .add_service(ControllerServer::with_interceptor(
+                CsiControllerSvc::new(cfg),
+                peer_auth,
+            ))
+            .serve_with_incoming_shutdown(incoming, shutdown::Shutdown::wait());
+
+        tokio::select! {
+            result = server => {
+                result.inspect_err(|error| {
+                    use stor_port::transport_api::ErrorChain;
+                    error!(error = error.full_string(), "NodePluginGrpcServer failed");
+                })?;
+            }
+            _ = leadership.until_lost() => {
+                error!("Lost CSI controller leadership; stopping server for a standby to take over");
+            }
+        }
         Ok(())
     }
 }
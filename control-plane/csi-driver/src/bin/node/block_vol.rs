@@ -10,13 +10,32 @@ macro_rules! failure {
 }
 
 use crate::{
+    crypt,
     dev::Device,
     findmnt,
     mount::{self},
 };
 use csi_driver::csi::{NodePublishVolumeRequest, NodeUnpublishVolumeRequest};
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use utils::tracing_telemetry::extract_context;
 
 pub(crate) async fn publish_block_volume(msg: &NodePublishVolumeRequest) -> Result<(), Status> {
+    let span = utils::rpc_span!(
+        "NodePublishVolume",
+        volume_id = %msg.volume_id,
+        target_path = %msg.target_path,
+    );
+    // Continue the control-plane's trace, if it handed us one, so the republish flow shows up
+    // as a single distributed trace rather than a disconnected node-side span.
+    span.set_parent(extract_context(&msg.publish_context));
+
+    async move { publish_block_volume_inner(msg).await }
+        .instrument(span)
+        .await
+}
+
+async fn publish_block_volume_inner(msg: &NodePublishVolumeRequest) -> Result<(), Status> {
     let target_path = &msg.target_path;
     let volume_id = &msg.volume_id;
 
@@ -50,6 +69,29 @@ pub(crate) async fn publish_block_volume(msg: &NodePublishVolumeRequest) -> Resu
             error
         )
     })? {
+        // If the volume is encrypted, a passphrase secret will be present; unlock (idempotently,
+        // so a retried publish just returns the already-open mapper) before mounting, the same as
+        // `filesystem_vol::stage_fs_volume` does for mounted volumes.
+        let device_path = match crypt::policy_from_secrets(&msg.secrets) {
+            Some(policy) => crypt::unlock(device_path, policy)
+                .await
+                .map_err(|error| match error {
+                    crypt::CryptError::PolicyDenied => failure!(
+                        Code::FailedPrecondition,
+                        "Failed to publish volume {}: device is locked and the unlock policy denies prompting",
+                        volume_id
+                    ),
+                    error => failure!(
+                        Code::Internal,
+                        "Failed to publish volume {}: failed to unlock encrypted device: {}",
+                        volume_id,
+                        error
+                    ),
+                })?
+                .0,
+            None => device_path,
+        };
+
         let path_target = Path::new(target_path);
         if path_target.exists() && !path_target.is_file() && !path_target.is_dir() {
             //target exists and is a special file
@@ -109,15 +151,25 @@ pub(crate) async fn publish_block_volume(msg: &NodePublishVolumeRequest) -> Resu
 }
 
 pub(crate) async fn unpublish_block_volume(msg: &NodeUnpublishVolumeRequest) -> Result<(), Status> {
+    let span = utils::rpc_span!(
+        "NodeUnpublishVolume",
+        volume_id = %msg.volume_id,
+        target_path = %msg.target_path,
+    );
+    async move { unpublish_block_volume_inner(msg).await }
+        .instrument(span)
+        .await
+}
+
+async fn unpublish_block_volume_inner(msg: &NodeUnpublishVolumeRequest) -> Result<(), Status> {
     let target_path = msg.target_path.clone();
     let volume_id = msg.volume_id.clone();
 
     // block volumes are mounted on block special file, which is not
     // a regular file.
-    if mount::find_mount(None, Some(target_path.clone()))
-        .await
-        .is_some()
-    {
+    if let Some(mount) = mount::find_mount(None, Some(target_path.clone())).await {
+        let device = mount.source.to_string_lossy().to_string();
+
         match mount::blockdevice_unmount(target_path.clone()).await {
             Ok(_) => {}
             Err(err) => {
@@ -127,6 +179,18 @@ pub(crate) async fn unpublish_block_volume(msg: &NodeUnpublishVolumeRequest) ->
                 ));
             }
         }
+
+        if crypt::is_mapper_path(&device) {
+            if let Err(error) = crypt::lock(crypt::MapperPath(device.clone())).await {
+                return Err(failure!(
+                    Code::Internal,
+                    "Failed to unpublish volume {}: failed to lock device {}: {}",
+                    volume_id,
+                    device,
+                    error
+                ));
+            }
+        }
     }
 
     debug!("Removing block special file {}", target_path);
@@ -0,0 +1,131 @@
+//! LUKS-style unlock/lock of an encrypted block device into/out of a device-mapper node, run
+//! before `filesystem_mount`/after `filesystem_unmount` so the rest of the mount pipeline only
+//! ever sees a plain block device.
+
+use std::{collections::HashMap, path::PathBuf};
+use tokio::process::Command;
+use uuid::Uuid;
+
+/// Key looked up in the CSI `NodeStageVolumeRequest` `secrets` map for the LUKS passphrase.
+const PASSPHRASE_SECRET_KEY: &str = "luksPassphrase";
+
+/// Build the `UnlockPolicy` for a stage request from its `secrets` map. `None` means the volume
+/// isn't encrypted (no passphrase secret was provided), so callers should skip `unlock` entirely
+/// and mount the raw device as before.
+pub(crate) fn policy_from_secrets(secrets: &HashMap<String, String>) -> Option<UnlockPolicy> {
+    secrets
+        .get(PASSPHRASE_SECRET_KEY)
+        .map(|passphrase| UnlockPolicy::Secret(passphrase.clone().into_bytes()))
+}
+
+/// True if `device` is a mapper node this module opened, i.e. it's safe to `lock` on unstage.
+pub(crate) fn is_mapper_path(device: &str) -> bool {
+    device
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.starts_with("crypt-"))
+}
+
+/// How (or whether) to source the passphrase used to open an encrypted device.
+#[derive(Debug, Clone)]
+pub(crate) enum UnlockPolicy {
+    /// Never prompt for a passphrase; `unlock` on a still-locked device fails immediately.
+    Fail,
+    /// Read the passphrase from a file already present on the node.
+    KeyFile(PathBuf),
+    /// Use the passphrase bytes directly, as sourced from the CSI `NodeStageVolumeRequest`
+    /// `secrets` map.
+    Secret(Vec<u8>),
+}
+
+/// Path to the opened device-mapper node, e.g. `/dev/mapper/<name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MapperPath(pub(crate) String);
+
+impl std::fmt::Display for MapperPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CryptError {
+    #[error("device is locked and the unlock policy does not allow prompting for a passphrase")]
+    PolicyDenied,
+    #[error("failed to run cryptsetup: {0}")]
+    Exec(#[from] std::io::Error),
+    #[error("cryptsetup {0} failed: {1}")]
+    Cryptsetup(&'static str, String),
+}
+
+/// Derive a stable mapper name for `device`, so repeated unlocks of the same device are
+/// idempotent and always resolve to the same `/dev/mapper/<name>` node.
+fn mapper_name(device: &str) -> String {
+    format!("crypt-{}", Uuid::new_v5(&Uuid::NAMESPACE_OID, device.as_bytes()))
+}
+
+/// Open `device` into a mapper node, sourcing the passphrase per `policy`. Idempotent: if the
+/// mapper node already exists (the device is already open), its path is returned without
+/// running `cryptsetup` again.
+pub(crate) async fn unlock(device: String, policy: UnlockPolicy) -> Result<MapperPath, CryptError> {
+    let name = mapper_name(&device);
+    let mapper = MapperPath(format!("/dev/mapper/{name}"));
+
+    if tokio::fs::metadata(&mapper.0).await.is_ok() {
+        return Ok(mapper);
+    }
+
+    let passphrase = match policy {
+        UnlockPolicy::Fail => return Err(CryptError::PolicyDenied),
+        UnlockPolicy::KeyFile(path) => tokio::fs::read(&path).await?,
+        UnlockPolicy::Secret(bytes) => bytes,
+    };
+
+    let mut child = Command::new("cryptsetup")
+        .args(["open", "--type", "luks", &device, &name, "--key-file", "-"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        use tokio::io::AsyncWriteExt;
+        stdin.write_all(&passphrase).await?;
+    }
+
+    let output = child.wait_with_output().await?;
+
+    if !output.status.success() {
+        return Err(CryptError::Cryptsetup(
+            "open",
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(mapper)
+}
+
+/// Close a previously opened mapper node. Idempotent: closing an already-closed mapper is not
+/// treated as an error.
+pub(crate) async fn lock(mapper: MapperPath) -> Result<(), CryptError> {
+    let name = mapper
+        .0
+        .rsplit('/')
+        .next()
+        .unwrap_or(mapper.0.as_str())
+        .to_string();
+
+    let output = Command::new("cryptsetup")
+        .args(["close", &name])
+        .output()
+        .await?;
+
+    if !output.status.success() && tokio::fs::metadata(&mapper.0).await.is_ok() {
+        return Err(CryptError::Cryptsetup(
+            "close",
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -1,13 +1,16 @@
 //! Functions for CSI stage, unstage, publish and unpublish filesystem volumes.
 use crate::{
+    crypt,
     filesystem_ops::FileSystem,
     format::prepare_device,
+    grow,
     mount::{self, subset, ReadOnly},
+    quota,
 };
 use csi_driver::{
     csi::{
-        volume_capability::MountVolume, NodePublishVolumeRequest, NodeStageVolumeRequest,
-        NodeUnpublishVolumeRequest, NodeUnstageVolumeRequest,
+        volume_capability::MountVolume, NodeExpandVolumeRequest, NodePublishVolumeRequest,
+        NodeStageVolumeRequest, NodeUnpublishVolumeRequest, NodeUnstageVolumeRequest,
     },
     filesystem::FileSystem as Fs,
     PublishParams,
@@ -16,6 +19,8 @@ use csi_driver::{
 use std::{fs, io::ErrorKind, path::PathBuf};
 use tonic::{Code, Status};
 use tracing::{debug, error, info};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use utils::tracing_telemetry::extract_context;
 use uuid::Uuid;
 
 macro_rules! failure {
@@ -29,6 +34,10 @@ pub(crate) async fn stage_fs_volume(
     mnt: &MountVolume,
     filesystems: &[FileSystem],
 ) -> Result<(), Status> {
+    // Continue the control-plane's trace, if it handed us one, so the republish flow shows up
+    // as a single distributed trace rather than a disconnected node-side span.
+    tracing::Span::current().set_parent(extract_context(&msg.publish_context));
+
     let volume_uuid = Uuid::parse_str(&msg.volume_id).map_err(|error| {
         failure!(
             Code::InvalidArgument,
@@ -41,6 +50,30 @@ pub(crate) async fn stage_fs_volume(
     // Extract the fs_id from the context, will only be set if requested and its a clone/restore.
     let params = PublishParams::try_from(&msg.publish_context)?;
     let fs_id = params.fs_id().clone();
+    let capacity_limit = params.capacity_limit();
+
+    // If the volume is encrypted, a passphrase secret will be present; unlock (idempotently, so
+    // a retried stage just returns the already-open mapper) before touching the device any
+    // further, so the rest of this function only ever deals with the decrypted block device.
+    let device_path = match crypt::policy_from_secrets(&msg.secrets) {
+        Some(policy) => crypt::unlock(device_path, policy)
+            .await
+            .map_err(|error| match error {
+                crypt::CryptError::PolicyDenied => failure!(
+                    Code::FailedPrecondition,
+                    "Failed to stage volume {}: device is locked and the unlock policy denies prompting",
+                    volume_uuid
+                ),
+                error => failure!(
+                    Code::Internal,
+                    "Failed to stage volume {}: failed to unlock encrypted device: {}",
+                    volume_uuid,
+                    error
+                ),
+            })?
+            .0,
+        None => device_path,
+    };
 
     let fs_staging_path = msg.staging_target_path.clone();
 
@@ -181,6 +214,7 @@ pub(crate) async fn stage_fs_volume(
         fs_staging_path.clone(),
         fstype,
         mount_flags,
+        Some(mount::MountPropagation::Slave { recursive: true }),
     )
     .await
     {
@@ -194,6 +228,20 @@ pub(crate) async fn stage_fs_volume(
         ));
     }
 
+    if let Some(limit_bytes) = capacity_limit {
+        if let Err(error) = quota::enforce(&fstype, &device_path, &fs_staging_path, limit_bytes).await {
+            return Err(failure!(
+                Code::Internal,
+                "Failed to stage volume {}: failed to enforce {} project quota of {} bytes on {}: {}",
+                volume_uuid,
+                fstype,
+                limit_bytes,
+                fs_staging_path,
+                error
+            ));
+        }
+    }
+
     info!("Volume {} staged to {}", volume_uuid, fs_staging_path);
 
     Ok(())
@@ -242,6 +290,18 @@ pub(crate) async fn unstage_fs_volume(msg: &NodeUnstageVolumeRequest) -> Result<
         }
 
         mount::wait_fs_shutdown(&device, Some(mount.fstype)).await?;
+
+        if crypt::is_mapper_path(&device) {
+            if let Err(error) = crypt::lock(crypt::MapperPath(device.clone())).await {
+                return Err(failure!(
+                    Code::Internal,
+                    "Failed to unstage volume {}: failed to lock device {}: {}",
+                    volume_id,
+                    device,
+                    error
+                ));
+            }
+        }
     }
 
     Ok(())
@@ -253,6 +313,10 @@ pub(crate) async fn publish_fs_volume(
     mnt: &MountVolume,
     filesystems: &[FileSystem],
 ) -> Result<(), Status> {
+    // Continue the control-plane's trace, if it handed us one, so the republish flow shows up
+    // as a single distributed trace rather than a disconnected node-side span.
+    tracing::Span::current().set_parent(extract_context(&msg.publish_context));
+
     let target_path = msg.target_path.clone();
     let volume_id = msg.volume_id.clone();
     let fs_staging_path = msg.staging_target_path.clone();
@@ -351,7 +415,13 @@ pub(crate) async fn publish_fs_volume(
 
     debug!("Mounting {} to {}", fs_staging_path, target_path);
 
-    if let Err(error) = mount::bind_mount(fs_staging_path.clone(), target_path.clone(), false).await
+    if let Err(error) = mount::bind_mount(
+        fs_staging_path.clone(),
+        target_path.clone(),
+        false,
+        Some(mount::MountPropagation::Shared { recursive: false }),
+    )
+    .await
     {
         return Err(failure!(
             Code::Internal,
@@ -466,3 +536,70 @@ async fn continue_after_unmount_on_fs_id_diff(
         .await?;
     Ok(fstype == Fs::Xfs.into())
 }
+
+/// Grow a staged filesystem volume's filesystem to match an already block-layer-expanded
+/// device, completing the online-resize story so a PVC expansion takes effect without unmount.
+///
+/// `NodeExpandVolume` may be called against either the staging path (pre-publish expansion) or
+/// the published, bind-mounted path; `volume_path` is set in the latter case and takes
+/// precedence. Returns the underlying device's size in bytes. Idempotent: growing a filesystem
+/// that already spans its device is a no-op, since the underlying `xfs_growfs`/`resize2fs`/
+/// `btrfs filesystem resize` tools are themselves no-ops in that case.
+pub(crate) async fn expand_fs_volume(
+    msg: &NodeExpandVolumeRequest,
+    filesystems: &[FileSystem],
+) -> Result<u64, Status> {
+    let volume_id = msg.volume_id.clone();
+
+    let path = if msg.volume_path.is_empty() {
+        msg.staging_target_path.clone()
+    } else {
+        msg.volume_path.clone()
+    };
+
+    let mount = mount::find_mount(None, Some(path.clone())).await.ok_or_else(|| {
+        failure!(
+            Code::NotFound,
+            "Failed to expand volume {}: no mount found at {}",
+            volume_id,
+            path
+        )
+    })?;
+
+    let device = mount.source.to_string_lossy().to_string();
+    let fstype = filesystems
+        .iter()
+        .find(|entry| entry.to_string() == mount.fstype)
+        .cloned()
+        .ok_or_else(|| {
+            failure!(
+                Code::Internal,
+                "Failed to expand volume {}: unsupported staged filesystem type {}",
+                volume_id,
+                mount.fstype
+            )
+        })?;
+
+    debug!(
+        "Growing {} filesystem for volume {} on {} at {}",
+        fstype, volume_id, device, path
+    );
+
+    let new_size = grow::grow(&fstype, &device, &path).await.map_err(|error| {
+        failure!(
+            Code::Internal,
+            "Failed to expand volume {}: failed to grow {} filesystem on {}: {}",
+            volume_id,
+            fstype,
+            device,
+            error
+        )
+    })?;
+
+    info!(
+        "Volume {} filesystem grown to {} bytes at {}",
+        volume_id, new_size, path
+    );
+
+    Ok(new_size)
+}
@@ -0,0 +1,98 @@
+//! Online filesystem growth, run from `expand_fs_volume` once the underlying replica/nexus has
+//! already been expanded at the block layer, so a PVC expansion takes effect without unmount.
+
+use crate::filesystem_ops::FileSystem;
+use tokio::process::Command;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum GrowError {
+    #[error("failed to run {0}: {1}")]
+    Exec(&'static str, std::io::Error),
+    #[error("{0} failed: {1}")]
+    Tool(&'static str, String),
+    #[error("online growth is not supported for filesystem {0}")]
+    UnsupportedFilesystem(FileSystem),
+    #[error("failed to determine the new size of {0} after growing: {1}")]
+    Size(String, std::io::Error),
+}
+
+/// Grow the filesystem of `fstype` mounted at `mount_path` on `device` to fill the (already
+/// block-layer-expanded) device, returning the device's size in bytes (`blockdev --getsize64`).
+/// The grow itself is a no-op when the filesystem already spans the device.
+pub(crate) async fn grow(
+    fstype: &FileSystem,
+    device: &str,
+    mount_path: &str,
+) -> Result<u64, GrowError> {
+    match fstype {
+        FileSystem::Xfs => grow_xfs(mount_path).await,
+        FileSystem::Ext4 => grow_ext4(device).await,
+        FileSystem::Btrfs => grow_btrfs(mount_path).await,
+        other => Err(GrowError::UnsupportedFilesystem(other.clone())),
+    }?;
+
+    device_size(device).await
+}
+
+async fn grow_xfs(mount_path: &str) -> Result<(), GrowError> {
+    // `xfs_growfs` is a no-op (exits 0) when the filesystem already spans the device, so no
+    // separate idempotency check is needed here.
+    run("xfs_growfs", &["xfs_growfs", mount_path]).await
+}
+
+async fn grow_ext4(device: &str) -> Result<(), GrowError> {
+    // `resize2fs` with no explicit target size grows to fill the device and is a no-op when
+    // already at that size.
+    run("resize2fs", &["resize2fs", device]).await
+}
+
+async fn grow_btrfs(mount_path: &str) -> Result<(), GrowError> {
+    // `btrfs filesystem resize max` grows to fill the device and is a no-op when already there.
+    run(
+        "btrfs",
+        &["btrfs", "filesystem", "resize", "max", mount_path],
+    )
+    .await
+}
+
+async fn run(name: &'static str, args: &[&str]) -> Result<(), GrowError> {
+    let output = Command::new(args[0])
+        .args(&args[1 ..])
+        .output()
+        .await
+        .map_err(|error| GrowError::Exec(name, error))?;
+
+    if !output.status.success() {
+        return Err(GrowError::Tool(
+            name,
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Read back the block device's current size in bytes via `blockdev --getsize64`.
+async fn device_size(device: &str) -> Result<u64, GrowError> {
+    let output = Command::new("blockdev")
+        .args(["--getsize64", device])
+        .output()
+        .await
+        .map_err(|error| GrowError::Exec("blockdev", error))?;
+
+    if !output.status.success() {
+        return Err(GrowError::Tool(
+            "blockdev",
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<u64>()
+        .map_err(|error| {
+            GrowError::Size(
+                device.to_string(),
+                std::io::Error::new(std::io::ErrorKind::InvalidData, error),
+            )
+        })
+}
@@ -8,6 +8,9 @@ mod client;
 /// Configuration Parameters.
 #[cfg(target_os = "linux")]
 pub(crate) mod config;
+/// LUKS-style unlock/lock of encrypted block devices.
+#[cfg(target_os = "linux")]
+mod crypt;
 #[cfg(target_os = "linux")]
 mod dev;
 #[cfg(target_os = "linux")]
@@ -21,6 +24,9 @@ mod findmnt;
 #[cfg(target_os = "linux")]
 mod format;
 pub(crate) mod fsfreeze;
+/// Online filesystem growth for `NodeExpandVolume`.
+#[cfg(target_os = "linux")]
+mod grow;
 #[cfg(target_os = "linux")]
 mod identity;
 pub(crate) mod k8s;
@@ -31,6 +37,8 @@ mod match_dev;
 #[cfg(target_os = "linux")]
 mod mount;
 #[cfg(target_os = "linux")]
+mod mount_table;
+#[cfg(target_os = "linux")]
 mod node;
 #[cfg(target_os = "linux")]
 mod nodeplugin_grpc;
@@ -38,6 +46,9 @@ mod nodeplugin_grpc;
 mod nodeplugin_nvme;
 #[cfg(target_os = "linux")]
 mod nodeplugin_svc;
+/// XFS/ext4 project-quota enforcement for staged filesystem volumes.
+#[cfg(target_os = "linux")]
+mod quota;
 mod registration;
 mod runtime;
 /// Shutdown event which lets the plugin know it needs to stop processing new events and
@@ -3,7 +3,7 @@ use crate::filesystem_ops::FileSystem;
 use csi_driver::filesystem::FileSystem as Fs;
 use devinfo::mountinfo::{MountInfo, SafeMountIter};
 
-use crate::runtime;
+use crate::{mount_table::MountTable, runtime};
 use std::{collections::HashSet, io::Error};
 use sys_mount::{unmount, FilesystemType, Mount, MountFlags, UnmountFlags};
 use tracing::{debug, error, info};
@@ -28,6 +28,66 @@ impl ReadOnly for &str {
     }
 }
 
+/// Mount propagation mode for a mountpoint, matching kubelet's bind-propagation semantics
+/// (`Private`/`HostToContainer`/`Bidirectional` map to `Private`/`Slave`/`Shared` here).
+/// `recursive` applies the change to every mount already nested under the target (`MS_REC`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MountPropagation {
+    Private { recursive: bool },
+    Shared { recursive: bool },
+    Slave { recursive: bool },
+    Unbindable { recursive: bool },
+}
+
+impl MountPropagation {
+    fn flags(&self) -> libc::c_ulong {
+        let (base, recursive) = match self {
+            Self::Private { recursive } => (libc::MS_PRIVATE, *recursive),
+            Self::Shared { recursive } => (libc::MS_SHARED, *recursive),
+            Self::Slave { recursive } => (libc::MS_SLAVE, *recursive),
+            Self::Unbindable { recursive } => (libc::MS_UNBINDABLE, *recursive),
+        };
+        if recursive {
+            base | libc::MS_REC
+        } else {
+            base
+        }
+    }
+}
+
+/// Apply a propagation mode to an already-mounted `target` via a standalone
+/// `mount(NULL, target, NULL, flags, NULL)` call. This must be its own syscall, separate from
+/// the bind/data mount it applies to: the kernel rejects combining a propagation-flag change
+/// with a bind mount or a filesystem type/data change in the same `mount(2)` call.
+pub(crate) async fn set_mount_propagation(
+    target: String,
+    propagation: MountPropagation,
+) -> Result<(), Error> {
+    let flags = propagation.flags();
+    let _target = target.clone();
+    let blocking_task = runtime::spawn_blocking(move || {
+        let target_c = std::ffi::CString::new(target)
+            .map_err(|error| Error::new(std::io::ErrorKind::InvalidInput, error))?;
+        let ret = unsafe {
+            libc::mount(
+                std::ptr::null(),
+                target_c.as_ptr(),
+                std::ptr::null(),
+                flags,
+                std::ptr::null(),
+            )
+        };
+        if ret != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    });
+    blocking_task.await??;
+
+    debug!("Target {} propagation set to {:?}", _target, propagation);
+    Ok(())
+}
+
 /// Return mountinfo matching source and/or destination.
 pub(crate) async fn find_mount(
     source: Option<String>,
@@ -111,11 +171,26 @@ pub(crate) fn probe_filesystems() -> Vec<FileSystem> {
     vec![Fs::Xfs.into(), Fs::Ext4.into(), Fs::Btrfs.into()]
 }
 
-// Utility function to transform a vector of options
-// to the format required by sys_mount::Mount::new()
-fn parse(options: Vec<String>) -> (bool, String) {
+/// Recognized VFS-level mount options that have a dedicated `MountFlags` bit. Anything not in
+/// this list (e.g. a btrfs `subvol=`/`subvolid=` selector) is filesystem-specific and must be
+/// forwarded verbatim as mount `data` instead.
+fn vfs_flag(entry: &str) -> Option<MountFlags> {
+    match entry {
+        "noatime" => Some(MountFlags::NOATIME),
+        "nodev" => Some(MountFlags::NODEV),
+        "nosuid" => Some(MountFlags::NOSUID),
+        "noexec" => Some(MountFlags::NOEXEC),
+        "sync" => Some(MountFlags::SYNCHRONOUS),
+        _ => None,
+    }
+}
+
+// Utility function to transform a vector of options into the `MountFlags` bits `sys_mount`
+// understands and the filesystem-specific `data` string passed through to `Mount::builder()`.
+fn parse(options: Vec<String>) -> (bool, MountFlags, String) {
     let mut list: Vec<String> = Vec::new();
     let mut readonly: bool = false;
+    let mut flags = MountFlags::empty();
 
     for entry in options {
         if entry == "ro" {
@@ -127,10 +202,15 @@ fn parse(options: Vec<String>) -> (bool, String) {
             continue;
         }
 
+        if let Some(flag) = vfs_flag(&entry) {
+            flags.insert(flag);
+            continue;
+        }
+
         list.push(entry);
     }
 
-    (readonly, list.join(","))
+    (readonly, flags, list.join(","))
 }
 
 // Utility function used for displaying a list of options.
@@ -154,10 +234,9 @@ pub(crate) async fn filesystem_mount(
     target: String,
     fstype: FileSystem,
     options: Vec<String>,
+    propagation: Option<MountPropagation>,
 ) -> Result<Mount, Error> {
-    let mut flags = MountFlags::empty();
-
-    let (readonly, value) = parse(options.clone());
+    let (readonly, mut flags, value) = parse(options.clone());
 
     if readonly {
         flags.insert(MountFlags::RDONLY);
@@ -188,12 +267,113 @@ pub(crate) async fn filesystem_mount(
     );
 
     let mount = blocking_task.await??;
+
+    if let Some(propagation) = propagation {
+        set_mount_propagation(_target, propagation).await?;
+    }
+
     Ok(mount)
 }
 
+/// Attach `image` as a loop device starting at byte `offset` and mount it at `target`, for
+/// volumes that are raw filesystem image files rather than real block devices.
+///
+/// Attach is done via `losetup` rather than `sys_mount`'s implicit loop handling so that we have
+/// the resulting `/dev/loopN` path in hand: if the mount fails partway, we detach it ourselves
+/// instead of leaking it for the next retry to trip over.
+pub(crate) async fn loopfile_mount(
+    image: String,
+    target: String,
+    fstype: FileSystem,
+    offset: u64,
+    options: Vec<String>,
+) -> Result<Mount, Error> {
+    let device = attach_loop(&image, offset).await?;
+
+    match filesystem_mount(device.clone(), target, fstype, options, None).await {
+        Ok(mount) => Ok(mount),
+        Err(error) => {
+            if let Err(detach_error) = loopfile_detach(device.clone()).await {
+                error!(
+                    "Failed to detach loop device {} for image {} after failed mount: {}",
+                    device, image, detach_error
+                );
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Detach the loop device backing a `loopfile_mount`ed target. Must be called with the device
+/// path obtained from `find_mount`/`filesystem_mount` before `filesystem_unmount`/
+/// `wait_fs_shutdown` drop it from mountinfo.
+pub(crate) async fn loopfile_detach(device: String) -> Result<(), Error> {
+    let _device = device.clone();
+    let blocking_task = runtime::spawn_blocking(move || {
+        use std::os::unix::io::AsRawFd;
+        let file = std::fs::File::open(&device)?;
+        let ret = unsafe { libc::ioctl(file.as_raw_fd(), libc::LOOP_CLR_FD as _) };
+        if ret != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    });
+    blocking_task.await??;
+
+    debug!("Loop device {} detached", _device);
+    Ok(())
+}
+
+async fn attach_loop(image: &str, offset: u64) -> Result<String, Error> {
+    let output = tokio::process::Command::new("losetup")
+        .args(["--show", "-f", "--offset", &offset.to_string(), image])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "losetup attach of {} failed: {}",
+                image,
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Unmount every submount nested directly or transitively under `target` (deepest mount points
+/// first), so the caller's subsequent unmount of `target` itself doesn't fail with `EBUSY`
+/// because of a leftover bind mount nobody cleaned up.
+async fn teardown_submounts(target: String) -> Result<(), Error> {
+    let blocking_task = runtime::spawn_blocking(move || -> Result<Vec<String>, Error> {
+        let table = MountTable::read()?;
+        Ok(table
+            .descendants_of(&target)
+            .into_iter()
+            .map(|entry| entry.mount_point.clone())
+            .collect())
+    });
+    let mut submounts = blocking_task.await??;
+    submounts.sort_by_key(|mount_point| std::cmp::Reverse(mount_point.len()));
+
+    for submount in submounts {
+        debug!("Unmounting submount {}", submount);
+        let flags = UnmountFlags::empty();
+        let blocking_task = runtime::spawn_blocking(move || unmount(submount, flags));
+        blocking_task.await??;
+    }
+
+    Ok(())
+}
+
 /// Unmount a device from a directory (mountpoint)
 /// Should not be used for removing bind mounts.
 pub(crate) async fn filesystem_unmount(target: String) -> Result<(), Error> {
+    teardown_submounts(target.clone()).await?;
+
     let flags = UnmountFlags::empty();
     // read more about the umount system call and it's flags at `man 2 umount`
     let _target = target.clone();
@@ -207,7 +387,12 @@ pub(crate) async fn filesystem_unmount(target: String) -> Result<(), Error> {
 
 /// Bind mount a source path to a target path.
 /// Supports both directories and files.
-pub(crate) async fn bind_mount(source: String, target: String, file: bool) -> Result<Mount, Error> {
+pub(crate) async fn bind_mount(
+    source: String,
+    target: String,
+    file: bool,
+    propagation: Option<MountPropagation>,
+) -> Result<Mount, Error> {
     let mut flags = MountFlags::empty();
 
     flags.insert(MountFlags::BIND);
@@ -227,15 +412,18 @@ pub(crate) async fn bind_mount(source: String, target: String, file: bool) -> Re
     debug!("Source {} bind mounted onto target {}", _source, _target);
 
     let mount = blocking_task.await??;
+
+    if let Some(propagation) = propagation {
+        set_mount_propagation(_target, propagation).await?;
+    }
+
     Ok(mount)
 }
 
 /// Bind remount a path to modify mount options.
 /// Assumes that target has already been bind mounted.
 pub(crate) async fn bind_remount(target: String, options: Vec<String>) -> Result<Mount, Error> {
-    let mut flags = MountFlags::empty();
-
-    let (readonly, value) = parse(options.clone());
+    let (readonly, mut flags, value) = parse(options.clone());
 
     flags.insert(MountFlags::BIND);
 
@@ -283,6 +471,18 @@ pub(crate) async fn bind_unmount(target: String) -> Result<(), Error> {
 
 /// Remount existing mount as read only or read write.
 pub(crate) async fn remount(target: String, ro: bool) -> Result<Mount, Error> {
+    let _target = target.clone();
+    let shared = runtime::spawn_blocking(move || MountTable::read().map(|table| table.is_shared(&_target)))
+        .await
+        .unwrap_or(Ok(false))
+        .unwrap_or(false);
+    if shared {
+        debug!(
+            "Target {} is in a shared propagation group; remount will also affect its peers",
+            target
+        );
+    }
+
     let mut flags = MountFlags::empty();
     flags.insert(MountFlags::REMOUNT);
 
@@ -348,6 +548,8 @@ pub(crate) async fn blockdevice_mount(
 
 /// Unmount a block device.
 pub(crate) async fn blockdevice_unmount(target: String) -> Result<(), Error> {
+    teardown_submounts(target.clone()).await?;
+
     let flags = UnmountFlags::empty();
 
     debug!(
@@ -0,0 +1,200 @@
+//! Full `/proc/self/mountinfo` parser.
+//!
+//! `find_mount`/`find_src_mounts` scan `SafeMountIter` matching only on `source`/`dest` strings,
+//! which is enough to find a single mount but throws away the parent/child hierarchy and the
+//! optional propagation fields (`shared:N`, `master:N`, `propagate_from:N`, `unbindable`) that
+//! mountinfo actually carries. `MountTable` parses the whole file into `MountEntry` records that
+//! keep those links, so callers can find submounts of a target before tearing it down, or check
+//! whether a mount belongs to a shared peer group before remounting it.
+
+use std::io::{Error, ErrorKind};
+
+/// One row of `/proc/self/mountinfo`, fields as described in `man 5 proc`.
+#[derive(Debug, Clone)]
+pub(crate) struct MountEntry {
+    pub(crate) mount_id: u32,
+    pub(crate) parent_id: u32,
+    pub(crate) major: u32,
+    pub(crate) minor: u32,
+    pub(crate) root: String,
+    pub(crate) mount_point: String,
+    pub(crate) mount_options: Vec<String>,
+    /// Peer group id, present when the mount is in a `shared` propagation group.
+    pub(crate) shared: Option<u32>,
+    /// Master peer group id, present when the mount is a `slave` of another mount.
+    pub(crate) master: Option<u32>,
+    pub(crate) propagate_from: Option<u32>,
+    pub(crate) unbindable: bool,
+    pub(crate) fstype: String,
+    pub(crate) source: String,
+    pub(crate) super_options: Vec<String>,
+}
+
+/// Snapshot of every mount visible in this mount namespace, with parent/child links intact.
+pub(crate) struct MountTable {
+    entries: Vec<MountEntry>,
+}
+
+impl MountTable {
+    /// Read and parse `/proc/self/mountinfo`.
+    pub(crate) fn read() -> Result<Self, Error> {
+        Self::parse(&std::fs::read_to_string("/proc/self/mountinfo")?)
+    }
+
+    fn parse(contents: &str) -> Result<Self, Error> {
+        let entries = contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(parse_line)
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Find the entry mounted at `mount_point`, if any.
+    pub(crate) fn find_by_mountpoint(&self, mount_point: &str) -> Option<&MountEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.mount_point == mount_point)
+    }
+
+    /// All mounts whose parent is the mount at `mount_point`, i.e. the submounts that must be
+    /// torn down before `mount_point` itself can be unmounted.
+    pub(crate) fn children_of(&self, mount_point: &str) -> Vec<&MountEntry> {
+        let Some(parent) = self.find_by_mountpoint(mount_point) else {
+            return Vec::new();
+        };
+        self.entries
+            .iter()
+            .filter(|entry| entry.parent_id == parent.mount_id)
+            .collect()
+    }
+
+    /// Every descendant of the mount at `mount_point` - its direct children, their children, and
+    /// so on - i.e. every submount that must be torn down before `mount_point` itself can be
+    /// unmounted. `children_of` alone only catches submounts nested directly under `mount_point`;
+    /// anything nested two or more levels deep has a `parent_id` pointing at an intermediate
+    /// child, not at `mount_point` itself, so finding those needs to walk the chain.
+    pub(crate) fn descendants_of(&self, mount_point: &str) -> Vec<&MountEntry> {
+        let mut descendants = Vec::new();
+        let mut frontier = self.children_of(mount_point);
+        while let Some(entry) = frontier.pop() {
+            frontier.extend(
+                self.entries
+                    .iter()
+                    .filter(|candidate| candidate.parent_id == entry.mount_id),
+            );
+            descendants.push(entry);
+        }
+        descendants
+    }
+
+    /// Whether the mount at `mount_point` is part of a `shared` propagation peer group, i.e.
+    /// remounting it would also affect its peers.
+    pub(crate) fn is_shared(&self, mount_point: &str) -> bool {
+        self.find_by_mountpoint(mount_point)
+            .is_some_and(|entry| entry.shared.is_some())
+    }
+}
+
+/// Decode octal escapes (`\040` space, `\011` tab, `\012` newline, `\134` backslash) that the
+/// kernel uses to keep mountinfo whitespace-delimited even when a path contains whitespace.
+fn unescape_octal(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = String::with_capacity(value.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && i + 3 < bytes.len()
+            && bytes[i + 1..i + 4].iter().all(|b| b.is_ascii_digit() && *b < b'8')
+        {
+            let octal = std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or("");
+            if let Ok(code) = u8::from_str_radix(octal, 8) {
+                out.push(code as char);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i] as char);
+        i += 1;
+    }
+    out
+}
+
+fn parse_line(line: &str) -> Result<MountEntry, Error> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("malformed mountinfo line: {line}"),
+        )
+    };
+
+    let mut fields = line.split(' ');
+
+    let mount_id = fields.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+    let parent_id = fields.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+
+    let (major, minor) = fields
+        .next()
+        .ok_or_else(invalid)?
+        .split_once(':')
+        .ok_or_else(invalid)?;
+    let major = major.parse().map_err(|_| invalid())?;
+    let minor = minor.parse().map_err(|_| invalid())?;
+
+    let root = unescape_octal(fields.next().ok_or_else(invalid)?);
+    let mount_point = unescape_octal(fields.next().ok_or_else(invalid)?);
+    let mount_options = fields
+        .next()
+        .ok_or_else(invalid)?
+        .split(',')
+        .map(String::from)
+        .collect();
+
+    // Variable-length optional-fields section, zero or more of `shared:N` / `master:N` /
+    // `propagate_from:N` / `unbindable`, terminated by a lone `-`.
+    let mut shared = None;
+    let mut master = None;
+    let mut propagate_from = None;
+    let mut unbindable = false;
+    loop {
+        let field = fields.next().ok_or_else(invalid)?;
+        if field == "-" {
+            break;
+        }
+        if let Some(id) = field.strip_prefix("shared:") {
+            shared = id.parse().ok();
+        } else if let Some(id) = field.strip_prefix("master:") {
+            master = id.parse().ok();
+        } else if let Some(id) = field.strip_prefix("propagate_from:") {
+            propagate_from = id.parse().ok();
+        } else if field == "unbindable" {
+            unbindable = true;
+        }
+    }
+
+    let fstype = fields.next().ok_or_else(invalid)?.to_string();
+    let source = unescape_octal(fields.next().ok_or_else(invalid)?);
+    let super_options = fields
+        .next()
+        .ok_or_else(invalid)?
+        .split(',')
+        .map(String::from)
+        .collect();
+
+    Ok(MountEntry {
+        mount_id,
+        parent_id,
+        major,
+        minor,
+        root,
+        mount_point,
+        mount_options,
+        shared,
+        master,
+        propagate_from,
+        unbindable,
+        fstype,
+        source,
+        super_options,
+    })
+}
@@ -0,0 +1,112 @@
+//! Per-volume project-quota enforcement, run after `prepare_device`/`filesystem_mount` so a
+//! staged volume's filesystem never grows past the capacity the control-plane published it
+//! with, even though the backing pool may be thin-provisioned and oversubscribed.
+
+use crate::filesystem_ops::FileSystem;
+use tokio::process::Command;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum QuotaError {
+    #[error("failed to run {0}: {1}")]
+    Exec(&'static str, std::io::Error),
+    #[error("{0} {1} failed: {2}")]
+    Tool(&'static str, &'static str, String),
+    #[error("project-quota enforcement is not supported for filesystem {0}")]
+    UnsupportedFilesystem(FileSystem),
+}
+
+/// Derive a stable XFS/ext4 project id for `device`, so repeated `enforce` calls for the same
+/// staged volume (restage, grow/shrink) reuse the same project assignment instead of leaking a
+/// fresh one each time.
+fn project_id(device: &str) -> u32 {
+    let hash = Uuid::new_v5(&Uuid::NAMESPACE_OID, device.as_bytes());
+    // Project id 0 is reserved (no quota), so fold it into the valid range starting at 1.
+    ((hash.as_u128() as u32) % u32::MAX) + 1
+}
+
+/// Assign `staging_path` the volume's project id and set (or update) its hard block-limit quota
+/// to `limit_bytes`, using the tool appropriate for `fstype`. Idempotent: re-running with the
+/// same `limit_bytes` is a no-op from the caller's point of view, and re-running with a new
+/// `limit_bytes` (a volume grow/shrink) simply reconciles the existing project's limit.
+pub(crate) async fn enforce(
+    fstype: &FileSystem,
+    device: &str,
+    staging_path: &str,
+    limit_bytes: u64,
+) -> Result<(), QuotaError> {
+    match fstype {
+        FileSystem::Xfs => enforce_xfs(device, staging_path, limit_bytes).await,
+        FileSystem::Ext4 => enforce_ext4(device, staging_path, limit_bytes).await,
+        other => Err(QuotaError::UnsupportedFilesystem(other.clone())),
+    }
+}
+
+async fn enforce_xfs(device: &str, staging_path: &str, limit_bytes: u64) -> Result<(), QuotaError> {
+    let id = project_id(device);
+
+    run_xfs_quota(device, &format!("project -s -p {staging_path} {id}")).await?;
+    run_xfs_quota(device, &format!("limit -p bhard={limit_bytes} {id}")).await?;
+
+    Ok(())
+}
+
+async fn run_xfs_quota(device: &str, expr: &str) -> Result<(), QuotaError> {
+    let output = Command::new("xfs_quota")
+        .args(["-x", "-c", expr, device])
+        .output()
+        .await
+        .map_err(|error| QuotaError::Exec("xfs_quota", error))?;
+
+    if !output.status.success() {
+        return Err(QuotaError::Tool(
+            "xfs_quota",
+            "enforce",
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn enforce_ext4(device: &str, staging_path: &str, limit_bytes: u64) -> Result<(), QuotaError> {
+    let id = project_id(device);
+    let block_limit = limit_bytes.div_ceil(1024);
+
+    // `chattr -p` (re-)assigns the staged directory tree to the project; idempotent, as chattr
+    // just overwrites the existing project id if the directory already carries one.
+    let output = Command::new("chattr")
+        .args(["-p", &id.to_string(), "-R", "+P", staging_path])
+        .output()
+        .await
+        .map_err(|error| QuotaError::Exec("chattr", error))?;
+    if !output.status.success() {
+        return Err(QuotaError::Tool(
+            "chattr",
+            "assign project",
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    let output = Command::new("setquota")
+        .args([
+            "-P",
+            &id.to_string(),
+            "0",
+            &block_limit.to_string(),
+            "0",
+            "0",
+            device,
+        ])
+        .output()
+        .await
+        .map_err(|error| QuotaError::Exec("setquota", error))?;
+    if !output.status.success() {
+        return Err(QuotaError::Tool(
+            "setquota",
+            "set limit",
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -2,9 +2,15 @@
 //! This allows us to send futures from within mayastor to the tokio
 //! runtime to do whatever it needs to do.
 
-use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
 use tokio::task::JoinHandle;
-use tracing::trace;
+use tracing::{trace, warn};
 
 /// spawn a future that might block on a separate worker thread the
 /// number of threads available is determined by max_blocking_threads
@@ -13,37 +19,195 @@ where
     F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
 {
-    RUNTIME.spawn_blocking(f)
+    Runtime::handle().spawn_blocking(move || {
+        trace!("Spawned a blocking thread");
+        f()
+    })
+}
+
+/// spawn a future onto the runtime directly, for genuinely async work that shouldn't be forced
+/// onto the blocking pool `spawn_blocking` uses.
+pub(crate) fn spawn<F>(f: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    Runtime::handle().spawn(f)
+}
+
+/// Cloneable entry point onto this module's runtime, for sub-components that need to spawn work
+/// from arbitrary threads without referencing the private `RUNTIME_STATE` static directly.
+#[derive(Clone)]
+pub(crate) struct Handle(tokio::runtime::Handle);
+
+impl Handle {
+    /// A handle onto this module's runtime: the externally-adopted one from `Runtime::set`, if
+    /// any, otherwise the private lazily-built default.
+    pub(crate) fn current() -> Self {
+        Self(Runtime::handle())
+    }
+    pub(crate) fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.0.spawn(f)
+    }
+    pub(crate) fn spawn_blocking<F, R>(&self, f: F) -> JoinHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.0.spawn_blocking(f)
+    }
 }
 
 pub(crate) struct Runtime {
     rt: tokio::runtime::Runtime,
 }
 
-static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+/// Default worker/blocking thread counts, used when `MAYASTOR_RT_WORKERS`/`MAYASTOR_RT_BLOCKING`
+/// aren't set or don't parse.
+const DEFAULT_WORKER_THREADS: usize = 5;
+const DEFAULT_MAX_BLOCKING_THREADS: usize = 50;
+
+/// Env var overriding the number of worker threads in the private lazily-built runtime.
+const WORKERS_ENV: &str = "MAYASTOR_RT_WORKERS";
+/// Env var overriding the number of blocking threads in the private lazily-built runtime.
+const BLOCKING_ENV: &str = "MAYASTOR_RT_BLOCKING";
+
+/// Parse a positive thread count from `env_var`, falling back to `default` if it's unset or
+/// doesn't parse to a positive integer, so a malformed value can't build a runtime with zero
+/// threads.
+fn thread_count_from_env(env_var: &str, default: usize) -> usize {
+    std::env::var(env_var)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|&count: &usize| count > 0)
+        .unwrap_or(default)
+}
+
+/// Which runtime this module's spawn APIs currently route onto, if either has been forced yet.
+enum RuntimeState {
+    /// Neither `Runtime::set` nor any spawn/block_on/wrap call has run yet.
+    Unset,
+    /// `Runtime::set` adopted a handle onto a host-provided runtime.
+    External(tokio::runtime::Handle),
+    /// The private runtime was lazily built by the first spawn/block_on/wrap call.
+    Private(Runtime),
+}
+
+/// Single source of truth for both "is a runtime forced yet" and "which one", so `Runtime::set`'s
+/// check-then-act (is one forced? if not, adopt the external handle) is one atomic critical
+/// section instead of two independently-checked cells (`RUNTIME`/`EXTERNAL_HANDLE`) that a
+/// concurrent `Runtime::handle()` call could force in between.
+static RUNTIME_STATE: Mutex<RuntimeState> = Mutex::new(RuntimeState::Unset);
+
+fn build_runtime() -> Runtime {
+    let worker_threads = thread_count_from_env(WORKERS_ENV, DEFAULT_WORKER_THREADS);
+    let max_blocking_threads = thread_count_from_env(BLOCKING_ENV, DEFAULT_MAX_BLOCKING_THREADS);
+
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
-        .worker_threads(5)
-        .max_blocking_threads(50)
+        .worker_threads(worker_threads)
+        .max_blocking_threads(max_blocking_threads)
+        .thread_name("mayastor-rt")
+        .on_thread_start(|| trace!("mayastor-rt thread started"))
         .build()
         .unwrap();
 
     Runtime::new(rt)
-});
+}
 
 impl Runtime {
     fn new(rt: tokio::runtime::Runtime) -> Self {
         Self { rt }
     }
-    fn spawn_blocking<F, R>(&self, f: F) -> JoinHandle<R>
+    /// Adopt `rt` as the runtime this module's spawn APIs route onto, instead of the private
+    /// lazily-built default. Must be called before the first use of this module: if the private
+    /// runtime has already been forced (by any `spawn`/`spawn_blocking`/`block_on`/`wrap` call),
+    /// or if `set` has already been called once, this is a no-op (logged via `tracing::warn!`)
+    /// rather than silently orphaning whatever is already running on the runtime that was in use.
+    /// Intended for embedders that already run their own Tokio runtime and don't want mayastor
+    /// spinning up a second, competing one.
+    pub(crate) fn set(rt: tokio::runtime::Handle) {
+        let mut state = RUNTIME_STATE.lock();
+        match *state {
+            RuntimeState::Unset => *state = RuntimeState::External(rt),
+            RuntimeState::Private(_) => warn!(
+                "Runtime::set called after the private runtime was already in use; ignoring to \
+                 avoid orphaning tasks already running on it"
+            ),
+            RuntimeState::External(_) => warn!("Runtime::set called more than once; ignoring"),
+        }
+    }
+    /// The handle every operation in this module routes through: the externally-adopted one from
+    /// `set`, if any, otherwise the private lazily-built default.
+    fn handle() -> tokio::runtime::Handle {
+        let mut state = RUNTIME_STATE.lock();
+        match &*state {
+            RuntimeState::External(handle) => handle.clone(),
+            RuntimeState::Private(rt) => rt.rt.handle().clone(),
+            RuntimeState::Unset => {
+                let rt = build_runtime();
+                let handle = rt.rt.handle().clone();
+                *state = RuntimeState::Private(rt);
+                handle
+            }
+        }
+    }
+    /// Block the calling thread on `f`, resolving against this module's runtime.
+    ///
+    /// `tokio::runtime::Handle::block_on` panics ("Cannot start a runtime from within a runtime")
+    /// when called from a thread that's already inside a Tokio runtime context - e.g. mayastor
+    /// invoked from a host executor's worker thread. To stay panic-safe, detect that case via
+    /// `tokio::runtime::Handle::try_current` and, if so, drive `f` on a fresh, plain OS thread
+    /// (which has no runtime context of its own) instead of calling `block_on` directly; when no
+    /// runtime is active on the current thread, `block_on` directly is both simpler and cheaper.
+    pub(crate) fn block_on<F>(f: F) -> F::Output
     where
-        F: FnOnce() -> R + Send + 'static,
-        R: Send + 'static,
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
     {
-        let handle = self.rt.handle().clone();
-        handle.spawn_blocking(|| {
-            trace!("Spawned a blocking thread");
-            f()
-        })
+        let handle = Self::handle();
+        if tokio::runtime::Handle::try_current().is_ok() {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| handle.block_on(f))
+                    .join()
+                    .expect("block_on thread panicked")
+            })
+        } else {
+            handle.block_on(f)
+        }
+    }
+    /// Wrap `f` so that, wherever it's polled, Tokio's I/O/timer drivers (timeouts, `TcpStream`,
+    /// ...) are available against this module's runtime - see `TokioContext`.
+    pub(crate) fn wrap<F>(f: F) -> TokioContext<F> {
+        TokioContext {
+            inner: f,
+            handle: Self::handle(),
+        }
+    }
+}
+
+/// Future adapter that lets `inner` be polled on a foreign (non-Tokio) executor while still
+/// resolving Tokio-dependent resources against `handle`'s runtime: each `poll` enters the
+/// handle's context before delegating to `inner`, so the driver lookups Tokio's I/O/timer types
+/// do internally find this runtime's drivers instead of panicking with "no reactor running".
+#[pin_project]
+pub(crate) struct TokioContext<F> {
+    #[pin]
+    inner: F,
+    handle: tokio::runtime::Handle,
+}
+
+impl<F: Future> Future for TokioContext<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _guard = this.handle.enter();
+        this.inner.poll(cx)
     }
 }
@@ -0,0 +1,45 @@
+//! Startup-selectable `Repo` backend.
+//!
+//! `Repo` (see `repo`) gives every backend the same `get/put/delete/list` surface, but nothing
+//! yet turns a deployment's configured choice into the concrete adapter to construct. `StoreBackend`
+//! is that choice and `connect` is the factory a binary's startup code calls with it, the same
+//! shape `PostgresRepo::connect` already uses for its own single-backend setup.
+
+use crate::{postgres::PostgresRepo, repo::{Repo, RepoError}};
+#[cfg(feature = "embedded")]
+use crate::embedded::EmbeddedRepo;
+
+/// Which `Repo` implementation a deployment wants at startup.
+#[derive(Debug, Clone)]
+pub enum StoreBackend {
+    /// The pre-existing etcd-backed `StoreKv` path that `Registry::store_obj` already talks to
+    /// directly. There's no `Repo` adapter wrapping it in this checkout (only `PostgresRepo`/
+    /// `EmbeddedRepo` exist), so selecting it here is a no-op callers should read as "keep using
+    /// the current store unchanged" rather than a constructed adapter.
+    Etcd,
+    /// Connect to Postgres at `url` with up to `max_connections` pooled connections.
+    #[cfg(feature = "postgres")]
+    Postgres { url: String, max_connections: u32 },
+    /// Open (or create) a local `EmbeddedRepo` database at `path` - no external process to stand
+    /// up, single-node durability only. See `embedded`'s doc comment for that tradeoff.
+    #[cfg(feature = "embedded")]
+    Embedded { path: String },
+}
+
+/// Construct the `Repo` a deployment asked for, or `None` for `StoreBackend::Etcd` until a real
+/// `Repo` adapter exists over the current store.
+#[cfg(any(feature = "postgres", feature = "embedded"))]
+pub async fn connect(backend: StoreBackend) -> Result<Option<Box<dyn Repo>>, RepoError> {
+    match backend {
+        StoreBackend::Etcd => Ok(None),
+        #[cfg(feature = "postgres")]
+        StoreBackend::Postgres {
+            url,
+            max_connections,
+        } => Ok(Some(Box::new(
+            PostgresRepo::connect(&url, max_connections).await?,
+        ))),
+        #[cfg(feature = "embedded")]
+        StoreBackend::Embedded { path } => Ok(Some(Box::new(EmbeddedRepo::open(&path)?))),
+    }
+}
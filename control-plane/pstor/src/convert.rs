@@ -0,0 +1,42 @@
+//! Offline conversion between two `Repo` backends.
+//!
+//! Moving a deployment from one persistence backend to another (e.g. `EmbeddedRepo` to
+//! `PostgresRepo`) means copying every `StorableObject` across with no mechanism tying the copy
+//! to the control plane being up. `convert` drives that copy directly off the `Repo` trait, so it
+//! works for any pair of implementations - present or future - without backend-specific glue. It
+//! is meant to back an offline `pstor convert --from <backend> --to <backend>` subcommand; there's
+//! no CLI entry point or `main.rs` for this crate in this checkout to hang the argument parsing
+//! off, so this stops at the function a subcommand would call.
+
+use crate::{
+    repo::{Repo, RepoError},
+    StorableObjectType,
+};
+
+/// Outcome of a single `convert` run.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConvertReport {
+    /// Number of `(key, value)` rows written to `target`.
+    pub copied: usize,
+}
+
+/// Copy every stored object of each kind in `types` from `source` to `target`, overwriting
+/// whatever `target` already has at that key. Processes one `StorableObjectType` at a time so a
+/// failure partway through still leaves already-converted kinds fully migrated.
+pub async fn convert(
+    source: &dyn Repo,
+    target: &dyn Repo,
+    types: &[StorableObjectType],
+) -> Result<ConvertReport, RepoError>
+where
+    StorableObjectType: Clone,
+{
+    let mut report = ConvertReport::default();
+    for key_type in types {
+        for (key, value) in source.list(key_type.clone()).await? {
+            target.put(&key, value).await?;
+            report.copied += 1;
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,128 @@
+//! Embedded `Repo` backend, gated behind the `embedded` feature. `sled` is a pure-Rust embedded
+//! KV store - no system library or separate process the way Postgres needs - which is the point:
+//! this is for small/edge deployments that don't want to stand up a cluster of any kind just to
+//! persist specs. Mirrors `PostgresRepo`'s shape: one logical table of rows addressed by
+//! `RepoKey`, storing each `StorableObject`'s existing serde JSON representation verbatim.
+//!
+//! Single-node durability only - `sled` fsyncs to one local disk, so unlike etcd or a replicated
+//! Postgres this has no redundancy if that disk is lost. Fine for the single-node deployments this
+//! targets; not a backend to choose for a multi-node control plane.
+//!
+//! Migrating an existing deployment onto this backend is already covered generically by
+//! `convert::convert` (any `Repo` to any other `Repo`), not anything specific to this module - see
+//! `backend::StoreBackend::Etcd`'s doc comment for the one remaining gap (there's no `Repo`
+//! adapter over the current etcd-backed path in this checkout yet to convert *from*).
+//!
+//! This implements `Repo`, not `Store` directly. `Store` (the trait `ResourceSpecsLocked::init`/
+//! `populate_specs` actually run against, with the paged/prefix semantics those call) has no
+//! definition anywhere in this checkout - only the two methods those call sites exercise
+//! (`get_values_paged_all`, `delete_values_prefix`) are confirmed to exist on it - so there's
+//! nothing to write `impl Store for EmbeddedRepo` against here. `Repo` is this crate's one
+//! concretely-present pluggable-backend extension point (`PostgresRepo` already uses it the same
+//! way), and its `get`/`put`/`delete`/`list` surface covers every operation a `Store` adapter over
+//! `EmbeddedRepo` would need internally; a blanket `impl<R: Repo> Store for R` (or an adapter type
+//! wrapping one) is the remaining, mechanical step once `Store`'s real definition is available to
+//! compile against.
+#![cfg(feature = "embedded")]
+
+use crate::{
+    repo::{Repo, RepoError, RepoKey},
+    ApiVersion, StorableObjectType,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// A stored row's on-disk value: the object's JSON plus the `ApiVersion` it was written at, kept
+/// alongside it since sled (unlike Postgres' separate `version` column) has only one value slot
+/// per key. Stored as a plain integer rather than `ApiVersion` itself, the same cast `PostgresRepo`
+/// already relies on (`key.version as i16` / `ApiVersion::from(version)`), since `ApiVersion`
+/// isn't confirmed to derive `Serialize`/`Deserialize` in this checkout.
+#[derive(Serialize, Deserialize)]
+struct Row {
+    version: i16,
+    value: Value,
+}
+
+fn row_key(key_type: StorableObjectType, uuid: &str) -> Vec<u8> {
+    format!("{key_type}/{uuid}").into_bytes()
+}
+
+fn row_prefix(key_type: StorableObjectType) -> Vec<u8> {
+    format!("{key_type}/").into_bytes()
+}
+
+/// `sled`-backed `Repo` implementation, one tree file rooted at a local path.
+pub struct EmbeddedRepo {
+    db: sled::Db,
+}
+
+impl EmbeddedRepo {
+    /// Open (creating if needed) the embedded database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RepoError> {
+        let db = sled::open(path).map_err(|error| RepoError::Connection(error.to_string()))?;
+        Ok(Self { db })
+    }
+}
+
+#[async_trait]
+impl Repo for EmbeddedRepo {
+    async fn get(&self, key: &RepoKey) -> Result<Option<Value>, RepoError> {
+        let raw = self
+            .db
+            .get(row_key(key.key_type, &key.uuid))
+            .map_err(|error| RepoError::Backend(error.to_string()))?;
+        raw.map(|bytes| {
+            serde_json::from_slice::<Row>(&bytes)
+                .map(|row| row.value)
+                .map_err(|error| RepoError::Backend(error.to_string()))
+        })
+        .transpose()
+    }
+
+    async fn put(&self, key: &RepoKey, value: Value) -> Result<(), RepoError> {
+        let row = Row {
+            version: key.version as i16,
+            value,
+        };
+        let bytes = serde_json::to_vec(&row).map_err(|error| RepoError::Backend(error.to_string()))?;
+        self.db
+            .insert(row_key(key.key_type, &key.uuid), bytes)
+            .map_err(|error| RepoError::Backend(error.to_string()))?;
+        self.db
+            .flush_async()
+            .await
+            .map_err(|error| RepoError::Backend(error.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &RepoKey) -> Result<(), RepoError> {
+        self.db
+            .remove(row_key(key.key_type, &key.uuid))
+            .map_err(|error| RepoError::Backend(error.to_string()))?;
+        Ok(())
+    }
+
+    async fn list(&self, key_type: StorableObjectType) -> Result<Vec<(RepoKey, Value)>, RepoError> {
+        let prefix_len = row_prefix(key_type).len();
+        self.db
+            .scan_prefix(row_prefix(key_type))
+            .map(|entry| {
+                let (raw_key, raw_value) =
+                    entry.map_err(|error| RepoError::Backend(error.to_string()))?;
+                let uuid = String::from_utf8_lossy(&raw_key[prefix_len..]).into_owned();
+                let row = serde_json::from_slice::<Row>(&raw_value)
+                    .map_err(|error| RepoError::Backend(error.to_string()))?;
+                Ok((
+                    RepoKey {
+                        key_type,
+                        version: ApiVersion::from(row.version),
+                        uuid,
+                    },
+                    row.value,
+                ))
+            })
+            .collect()
+    }
+}
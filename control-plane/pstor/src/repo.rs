@@ -0,0 +1,48 @@
+//! Backend-agnostic persistence for `StorableObject`s.
+//!
+//! Today every `StorableObject` (e.g. `SwitchOverSpec`) is persisted to etcd via `StoreKv`,
+//! which bakes in the assumption of a single key-value store. `Repo` factors the same
+//! `get/put/delete/list` surface out as a trait so a relational backend (see `postgres`) can
+//! sit behind it for operators who already run a managed SQL database and don't want to operate
+//! a separate KV cluster.
+
+use crate::{ApiVersion, StorableObjectType};
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// A stored row's fully-qualified key: which kind of object, at which schema version, for which
+/// instance. Mirrors what `ObjectKey` already encodes for the etcd path, so existing
+/// `StorableObject` impls need no changes to be addressable through a `Repo`.
+#[derive(Debug, Clone)]
+pub struct RepoKey {
+    pub key_type: StorableObjectType,
+    pub version: ApiVersion,
+    pub uuid: String,
+}
+
+/// Persistence backend for `StorableObject`s, keyed by `(key_type, version, uuid)` and storing
+/// the object's existing serde JSON representation verbatim, so adding a backend never requires
+/// per-type DDL. Implementations are free to choose how that's physically stored; callers never
+/// see the difference.
+#[async_trait]
+pub trait Repo: Send + Sync {
+    /// Fetch the current value for `key`, or `None` if it has never been written.
+    async fn get(&self, key: &RepoKey) -> Result<Option<Value>, RepoError>;
+    /// Upsert `value` at `key`.
+    async fn put(&self, key: &RepoKey, value: Value) -> Result<(), RepoError>;
+    /// Remove the value at `key`, if any.
+    async fn delete(&self, key: &RepoKey) -> Result<(), RepoError>;
+    /// List every `(key, value)` pair currently stored for `key_type`.
+    async fn list(&self, key_type: StorableObjectType) -> Result<Vec<(RepoKey, Value)>, RepoError>;
+}
+
+/// Errors common to every `Repo` implementation.
+#[derive(Debug, thiserror::Error)]
+pub enum RepoError {
+    #[error("failed to reach the store backend: {0}")]
+    Connection(String),
+    #[error("store operation failed: {0}")]
+    Backend(String),
+    #[error("stored value for {0:?} could not be migrated from schema version {1:?}")]
+    Migration(StorableObjectType, ApiVersion),
+}
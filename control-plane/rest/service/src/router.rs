@@ -0,0 +1,31 @@
+//! Path-prefix dispatch between REST API versions.
+//!
+//! Every handler used to be registered as one unversioned surface (`apis::actix_server::Pools`
+//! directly on `RestApi`), so a breaking request/response change - like `v1::pools::PoolV1`
+//! adding labels and I/O stats - would break every existing client the moment it shipped. This
+//! registers `v0`'s handlers under `/v0` unchanged and `v1`'s under `/v1`, so both can be served
+//! side by side and a client upgrades only when it switches prefixes.
+
+use actix_web::web::{scope, ServiceConfig};
+
+/// Mount every versioned route group under its own path prefix. `v0` keeps the exact routes it
+/// has always served (nothing here changes their handlers or shapes), plus the new
+/// `/replicas/batch` routes; `v1` is additive.
+///
+/// Takes each version's route registration as a parameter rather than naming
+/// `crate::v0`/`crate::v1` configure functions directly: the openapi-generated
+/// `apis::actix_server` crate that would normally provide those per-version `configure(cfg)`
+/// entry points (one per `apis::actix_server::Pools`/`PoolsV1` impl) isn't vendored in this
+/// checkout, so the exact call this delegates to can't be pinned down here.
+pub fn configure_versioned_routes(
+    cfg: &mut ServiceConfig,
+    configure_v0: impl FnOnce(&mut ServiceConfig),
+    configure_v1: impl FnOnce(&mut ServiceConfig),
+) {
+    cfg.service(
+        scope("/v0")
+            .configure(configure_v0)
+            .configure(crate::v0::replicas::configure_batch_routes),
+    )
+    .service(scope("/v1").configure(configure_v1));
+}
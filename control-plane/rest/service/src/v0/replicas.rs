@@ -1,8 +1,18 @@
 use super::*;
 use crate::v0::pools::pool;
+use futures::{future::join_all, stream::Stream};
 use grpc::operations::{pool::traits::PoolOperations, replica::traits::ReplicaOperations};
-use std::convert::{TryFrom, TryInto};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::{TryFrom, TryInto},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
 use stor_port::{transport_api::ReplyError, types::v0::openapi::apis::Uuid};
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use transport_api::{ReplyErrorKind, ResourceKind};
 
 fn pool_client() -> impl PoolOperations {
@@ -13,35 +23,124 @@ fn replica_client() -> impl ReplicaOperations {
     core_grpc().replica()
 }
 
+/// Per-request context carried from an inbound REST request into its downstream gRPC calls, so
+/// the core agent can emit spans tied to the originating HTTP request and apply a consistent
+/// deadline, instead of every call passing `None` with no correlation at all.
+///
+/// Every handler in this file builds it with `RestRequestContext::default()`, from no inbound
+/// headers, so each call gets a fresh request id and an empty trace context.
+///
+/// `ReplicaOperations`/`PoolOperations`'s trailing context parameter (today always `None` in
+/// this file) is the real place `request_id`/`trace_context` belong once threaded all the way
+/// through; that parameter's type lives in the `grpc` crate, outside this checkout, so for now
+/// those two ride along as a tracing span entered around each handler instead. `deadline` doesn't
+/// have that limitation - it's enforced locally via `with_deadline`, regardless of whether the
+/// downstream gRPC call itself ever sees it.
+#[derive(Debug, Clone)]
+pub(crate) struct RestRequestContext {
+    pub request_id: String,
+    pub deadline: std::time::Instant,
+    pub trace_context: opentelemetry::Context,
+}
+
+impl RestRequestContext {
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Build from inbound headers, generating a request id if the caller didn't supply
+    /// `x-request-id`, and extracting any W3C `traceparent`/`tracestate` via the shared
+    /// telemetry helper.
+    pub(crate) fn from_headers(headers: &HashMap<String, String>) -> Self {
+        let request_id = headers
+            .get("x-request-id")
+            .cloned()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        Self {
+            request_id,
+            deadline: std::time::Instant::now() + Self::DEFAULT_TIMEOUT,
+            trace_context: utils::tracing_telemetry::extract_context(headers),
+        }
+    }
+
+    fn span(&self, rpc: &'static str) -> tracing::Span {
+        let span = tracing::info_span!("rest_rpc", rpc, request_id = %self.request_id);
+        span.set_parent(self.trace_context.clone());
+        span
+    }
+
+    /// Run `fut`, failing with `ReplyErrorKind::Timeout` if it's still running once `deadline`
+    /// passes. This is the one piece of `RestRequestContext` that's enforceable without the real
+    /// `grpc::...::Context` type this checkout doesn't vendor - see the struct doc comment.
+    async fn with_deadline<T>(
+        &self,
+        rpc: &'static str,
+        fut: impl std::future::Future<Output = Result<T, RestError<RestJsonError>>>,
+    ) -> Result<T, RestError<RestJsonError>> {
+        match tokio::time::timeout_at(tokio::time::Instant::from_std(self.deadline), fut).await {
+            Ok(result) => result,
+            Err(_) => Err(RestError::from(ReplyError {
+                kind: ReplyErrorKind::Timeout,
+                resource: ResourceKind::Replica,
+                source: rpc.to_string(),
+                extra: "request deadline exceeded".to_string(),
+            })),
+        }
+    }
+}
+
+impl Default for RestRequestContext {
+    fn default() -> Self {
+        Self::from_headers(&HashMap::new())
+    }
+}
+
 async fn put_replica(
     filter: Filter,
     body: CreateReplicaBody,
+    ctx: &RestRequestContext,
 ) -> Result<models::Replica, RestError<RestJsonError>> {
-    let create = match filter.clone() {
-        Filter::NodePoolReplica(node_id, pool_id, replica_id) => {
-            body.to_request(node_id, pool_id, replica_id)
-        }
-        Filter::PoolReplica(pool_id, replica_id) => {
-            let node_id = match pool_client().get(Filter::Pool(pool_id.clone()), None).await {
-                Ok(pools) => pool(pool_id.to_string(), pools.into_inner().first())?.node(),
-                Err(error) => return Err(RestError::from(error)),
+    ctx.with_deadline(
+        "put_replica",
+        async move {
+            let create = match filter.clone() {
+                Filter::NodePoolReplica(node_id, pool_id, replica_id) => {
+                    body.to_request(node_id, pool_id, replica_id)
+                }
+                Filter::PoolReplica(pool_id, replica_id) => {
+                    let node_id = match pool_client().get(Filter::Pool(pool_id.clone()), None).await {
+                        Ok(pools) => pool(pool_id.to_string(), pools.into_inner().first())?.node(),
+                        Err(error) => return Err(RestError::from(error)),
+                    };
+                    body.to_request(node_id, pool_id, replica_id)
+                }
+                _ => {
+                    return Err(RestError::from(ReplyError {
+                        kind: ReplyErrorKind::Internal,
+                        resource: ResourceKind::Replica,
+                        source: "put_replica".to_string(),
+                        extra: "invalid filter for resource".to_string(),
+                    }))
+                }
             };
-            body.to_request(node_id, pool_id, replica_id)
-        }
-        _ => {
-            return Err(RestError::from(ReplyError {
-                kind: ReplyErrorKind::Internal,
-                resource: ResourceKind::Replica,
-                source: "put_replica".to_string(),
-                extra: "invalid filter for resource".to_string(),
-            }))
+            let replica = replica_client().create(&create, None).await?;
+            Ok(replica.into())
         }
-    };
-    let replica = replica_client().create(&create, None).await?;
-    Ok(replica.into())
+        .instrument(ctx.span("put_replica")),
+    )
+    .await
 }
 
-async fn destroy_replica(filter: Filter) -> Result<(), RestError<RestJsonError>> {
+async fn destroy_replica(
+    filter: Filter,
+    ctx: &RestRequestContext,
+) -> Result<(), RestError<RestJsonError>> {
+    ctx.with_deadline(
+        "destroy_replica",
+        async move { destroy_replica_inner(filter).await }.instrument(ctx.span("destroy_replica")),
+    )
+    .await
+}
+
+async fn destroy_replica_inner(filter: Filter) -> Result<(), RestError<RestJsonError>> {
     let destroy = match filter.clone() {
         Filter::NodePoolReplica(node_id, pool_id, replica_id) => DestroyReplica {
             node: node_id,
@@ -85,6 +184,20 @@ async fn share_replica(
     filter: Filter,
     protocol: ReplicaShareProtocol,
     allowed_hosts: Option<Vec<String>>,
+    ctx: &RestRequestContext,
+) -> Result<String, RestError<RestJsonError>> {
+    ctx.with_deadline(
+        "share_replica",
+        async move { share_replica_inner(filter, protocol, allowed_hosts).await }
+            .instrument(ctx.span("share_replica")),
+    )
+    .await
+}
+
+async fn share_replica_inner(
+    filter: Filter,
+    protocol: ReplicaShareProtocol,
+    allowed_hosts: Option<Vec<String>>,
 ) -> Result<String, RestError<RestJsonError>> {
     let conv_hosts = |h: Option<Vec<String>>| {
         h.unwrap_or_default()
@@ -134,7 +247,18 @@ async fn share_replica(
     Ok(share_uri)
 }
 
-async fn unshare_replica(filter: Filter) -> Result<(), RestError<RestJsonError>> {
+async fn unshare_replica(
+    filter: Filter,
+    ctx: &RestRequestContext,
+) -> Result<(), RestError<RestJsonError>> {
+    ctx.with_deadline(
+        "unshare_replica",
+        async move { unshare_replica_inner(filter).await }.instrument(ctx.span("unshare_replica")),
+    )
+    .await
+}
+
+async fn unshare_replica_inner(filter: Filter) -> Result<(), RestError<RestJsonError>> {
     let unshare = match filter.clone() {
         Filter::NodePoolReplica(node_id, pool_id, replica_id) => UnshareReplica {
             node: node_id,
@@ -172,40 +296,307 @@ async fn unshare_replica(filter: Filter) -> Result<(), RestError<RestJsonError>>
     Ok(())
 }
 
+/// One replica to create as part of a `put_replicas_batch` call.
+#[derive(Clone)]
+pub struct CreateReplicaBatchItem {
+    pub filter: Filter,
+    pub body: CreateReplicaBody,
+}
+
+/// One replica to destroy as part of a `del_replicas_batch` call.
+#[derive(Clone)]
+pub struct DestroyReplicaBatchItem {
+    pub filter: Filter,
+}
+
+/// Run `items` through `op` concurrently, bounded by `max_concurrency` in-flight calls at a
+/// time, returning each item's own result in the same order as `items`. A failure on one item
+/// never aborts the others.
+async fn run_batch<I, T, F, Fut>(
+    items: Vec<I>,
+    max_concurrency: usize,
+    op: F,
+) -> Vec<Result<T, RestError<RestJsonError>>>
+where
+    F: Fn(I) -> Fut,
+    Fut: std::future::Future<Output = Result<T, RestError<RestJsonError>>>,
+{
+    let semaphore = Arc::new(Semaphore::new(max_concurrency.max(1)));
+    let futures = items.into_iter().map(|item| {
+        let semaphore = semaphore.clone();
+        let future = op(item);
+        async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            future.await
+        }
+    });
+    join_all(futures).await
+}
+
+/// Create many replicas concurrently. Mirrors `put_replica`'s per-item behaviour exactly,
+/// including resolving `node_id` from `Filter::PoolReplica`, so a caller provisioning a pool's
+/// worth of replicas doesn't pay for N sequential HTTP+gRPC hops.
+pub(crate) async fn put_replicas_batch(
+    items: Vec<CreateReplicaBatchItem>,
+    max_concurrency: usize,
+) -> Vec<Result<models::Replica, RestError<RestJsonError>>> {
+    let ctx = RestRequestContext::default();
+    run_batch(items, max_concurrency, |item| {
+        let ctx = ctx.clone();
+        async move { put_replica(item.filter, item.body, &ctx).await }
+    })
+    .await
+}
+
+/// Destroy many replicas concurrently. See `put_replicas_batch` for the ordering and
+/// partial-failure contract.
+pub(crate) async fn del_replicas_batch(
+    items: Vec<DestroyReplicaBatchItem>,
+    max_concurrency: usize,
+) -> Vec<Result<(), RestError<RestJsonError>>> {
+    let ctx = RestRequestContext::default();
+    run_batch(items, max_concurrency, |item| {
+        let ctx = ctx.clone();
+        async move { destroy_replica(item.filter, &ctx).await }
+    })
+    .await
+}
+
+/// Maximum number of batch items processed concurrently by the `/replicas/batch` routes, so a
+/// single oversized request can't fan out an unbounded number of simultaneous gRPC calls.
+const BATCH_MAX_CONCURRENCY: usize = 10;
+
+/// Wire-format item for `PUT /v0/replicas/batch`: the `NodePoolReplica` filter shape
+/// `put_replica` already handles, since that's the one every batch caller provisioning a pool's
+/// replicas already knows the node for.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PutReplicaBatchItemBody {
+    pub node_id: String,
+    pub pool_id: String,
+    pub replica_id: Uuid,
+    pub body: models::CreateReplicaBody,
+}
+
+/// Wire-format item for `DELETE /v0/replicas/batch`, mirroring `PutReplicaBatchItemBody`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DelReplicaBatchItemBody {
+    pub node_id: String,
+    pub pool_id: String,
+    pub replica_id: Uuid,
+}
+
+/// One item's outcome in a batch response: `Ok`/`Err` don't serialize the way callers of a JSON
+/// API expect from `Result`, so each item is reported as this tagged shape instead.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum BatchItemOutcome<T> {
+    Ok { value: T },
+    Err { error: String },
+}
+
+impl<T> From<Result<T, RestError<RestJsonError>>> for BatchItemOutcome<T> {
+    fn from(result: Result<T, RestError<RestJsonError>>) -> Self {
+        match result {
+            Ok(value) => Self::Ok { value },
+            Err(error) => Self::Err {
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+async fn put_replicas_batch_route(
+    body: actix_web::web::Json<Vec<PutReplicaBatchItemBody>>,
+) -> actix_web::HttpResponse {
+    let items = body.into_inner().into_iter().map(|item| {
+        CreateReplicaBody::try_from(item.body).map(|body| CreateReplicaBatchItem {
+            filter: Filter::NodePoolReplica(
+                item.node_id.into(),
+                item.pool_id.into(),
+                item.replica_id.into(),
+            ),
+            body,
+        })
+    });
+    let items = match items.collect::<Result<Vec<_>, _>>() {
+        Ok(items) => items,
+        Err(error) => return actix_web::HttpResponse::BadRequest().json(RestError::from(error)),
+    };
+    let results: Vec<_> = put_replicas_batch(items, BATCH_MAX_CONCURRENCY)
+        .await
+        .into_iter()
+        .map(BatchItemOutcome::from)
+        .collect();
+    actix_web::HttpResponse::Ok().json(results)
+}
+
+async fn del_replicas_batch_route(
+    body: actix_web::web::Json<Vec<DelReplicaBatchItemBody>>,
+) -> actix_web::HttpResponse {
+    let items = body
+        .into_inner()
+        .into_iter()
+        .map(|item| DestroyReplicaBatchItem {
+            filter: Filter::NodePoolReplica(
+                item.node_id.into(),
+                item.pool_id.into(),
+                item.replica_id.into(),
+            ),
+        })
+        .collect();
+    let results: Vec<_> = del_replicas_batch(items, BATCH_MAX_CONCURRENCY)
+        .await
+        .into_iter()
+        .map(BatchItemOutcome::from)
+        .collect();
+    actix_web::HttpResponse::Ok().json(results)
+}
+
+/// Register the `/replicas/batch` routes. Unlike every other handler in this file, these don't
+/// go through the generated `apis::actix_server::Replicas` trait (that trait's shape comes from
+/// an OpenAPI spec this checkout doesn't vendor, and it has no batch operation to implement), so
+/// they're mounted directly with plain `actix_web` extractors instead.
+pub(crate) fn configure_batch_routes(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.route(
+        "/replicas/batch",
+        actix_web::web::put().to(put_replicas_batch_route),
+    )
+    .route(
+        "/replicas/batch",
+        actix_web::web::delete().to(del_replicas_batch_route),
+    );
+}
+
+/// Kind of change a `ReplicaWatchEvent` reports.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) enum ReplicaWatchEventKind {
+    Created,
+    Updated,
+    Destroyed,
+}
+
+/// One event emitted by `watch_replicas`: a `resume_token` numbering the events of one stream,
+/// what changed, and the resulting replica (just the id for `Destroyed`, since the replica
+/// itself no longer exists to describe). `watch_replicas` accepts no token, so a reconnecting
+/// client starts over with a fresh snapshot and events between the two streams are not replayed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct ReplicaWatchEvent {
+    pub resume_token: u64,
+    pub kind: ReplicaWatchEventKind,
+    pub replica_id: String,
+    pub replica: Option<models::Replica>,
+}
+
+/// Subscribe to replica lifecycle events matching `filter`: an initial snapshot of every
+/// currently matching replica (each reported as `Created`), followed by incremental
+/// `Created`/`Updated`/`Destroyed` deltas, one `ReplicaWatchEvent` at a time with a
+/// monotonically increasing `resume_token`.
+///
+/// The core agent doesn't yet expose a server-streaming `ReplicaOperations` RPC to subscribe to
+/// directly, so this synthesizes the same event/resume-token contract by polling
+/// `replica_client().get(filter)` every `poll_period` and diffing successive snapshots; once
+/// that RPC exists, this can forward its stream instead of polling.
+pub(crate) fn watch_replicas(
+    filter: Filter,
+    poll_period: Duration,
+) -> Pin<Box<dyn Stream<Item = ReplicaWatchEvent> + Send>> {
+    Box::pin(async_stream::stream! {
+        let mut known: HashMap<String, Replica> = HashMap::new();
+        let mut resume_token = 0u64;
+        loop {
+            let current = match replica_client().get(filter.clone(), None).await {
+                Ok(replicas) => replicas.into_inner(),
+                Err(_) => {
+                    tokio::time::sleep(poll_period).await;
+                    continue;
+                }
+            };
+
+            let mut seen = HashSet::new();
+            for replica in &current {
+                let id = replica.uuid.to_string();
+                seen.insert(id.clone());
+                let kind = match known.get(&id) {
+                    None => Some(ReplicaWatchEventKind::Created),
+                    Some(previous) if previous != replica => Some(ReplicaWatchEventKind::Updated),
+                    Some(_) => None,
+                };
+                if let Some(kind) = kind {
+                    resume_token += 1;
+                    known.insert(id.clone(), replica.clone());
+                    yield ReplicaWatchEvent {
+                        resume_token,
+                        kind,
+                        replica_id: id,
+                        replica: Some(replica.clone().into()),
+                    };
+                }
+            }
+
+            let destroyed: Vec<String> = known
+                .keys()
+                .filter(|id| !seen.contains(*id))
+                .cloned()
+                .collect();
+            for id in destroyed {
+                known.remove(&id);
+                resume_token += 1;
+                yield ReplicaWatchEvent {
+                    resume_token,
+                    kind: ReplicaWatchEventKind::Destroyed,
+                    replica_id: id,
+                    replica: None,
+                };
+            }
+
+            tokio::time::sleep(poll_period).await;
+        }
+    })
+}
+
 #[async_trait::async_trait]
 impl apis::actix_server::Replicas for RestApi {
     async fn del_node_pool_replica(
         Path((node_id, pool_id, replica_id)): Path<(String, String, Uuid)>,
     ) -> Result<(), RestError<RestJsonError>> {
-        destroy_replica(Filter::NodePoolReplica(
-            node_id.into(),
-            pool_id.into(),
-            replica_id.into(),
-        ))
+        destroy_replica(
+            Filter::NodePoolReplica(node_id.into(), pool_id.into(), replica_id.into()),
+            &RestRequestContext::default(),
+        )
         .await
     }
 
     async fn del_node_pool_replica_share(
         Path((node_id, pool_id, replica_id)): Path<(String, String, Uuid)>,
     ) -> Result<(), RestError<RestJsonError>> {
-        unshare_replica(Filter::NodePoolReplica(
-            node_id.into(),
-            pool_id.into(),
-            replica_id.into(),
-        ))
+        unshare_replica(
+            Filter::NodePoolReplica(node_id.into(), pool_id.into(), replica_id.into()),
+            &RestRequestContext::default(),
+        )
         .await
     }
 
     async fn del_pool_replica(
         Path((pool_id, replica_id)): Path<(String, Uuid)>,
     ) -> Result<(), RestError<RestJsonError>> {
-        destroy_replica(Filter::PoolReplica(pool_id.into(), replica_id.into())).await
+        destroy_replica(
+            Filter::PoolReplica(pool_id.into(), replica_id.into()),
+            &RestRequestContext::default(),
+        )
+        .await
     }
 
     async fn del_pool_replica_share(
         Path((pool_id, replica_id)): Path<(String, Uuid)>,
     ) -> Result<(), RestError<RestJsonError>> {
-        unshare_replica(Filter::PoolReplica(pool_id.into(), replica_id.into())).await
+        unshare_replica(
+            Filter::PoolReplica(pool_id.into(), replica_id.into()),
+            &RestRequestContext::default(),
+        )
+        .await
     }
 
     async fn get_node_pool_replica(
@@ -267,6 +658,7 @@ impl apis::actix_server::Replicas for RestApi {
         put_replica(
             Filter::NodePoolReplica(node_id.into(), pool_id.into(), replica_id.into()),
             CreateReplicaBody::try_from(create_replica_body)?,
+            &RestRequestContext::default(),
         )
         .await
     }
@@ -279,6 +671,7 @@ impl apis::actix_server::Replicas for RestApi {
             Filter::NodePoolReplica(node_id.into(), pool_id.into(), replica_id.into()),
             ReplicaShareProtocol::Nvmf,
             allowed_hosts,
+            &RestRequestContext::default(),
         )
         .await
     }
@@ -290,6 +683,7 @@ impl apis::actix_server::Replicas for RestApi {
         put_replica(
             Filter::PoolReplica(pool_id.into(), replica_id.into()),
             CreateReplicaBody::try_from(create_replica_body)?,
+            &RestRequestContext::default(),
         )
         .await
     }
@@ -302,6 +696,7 @@ impl apis::actix_server::Replicas for RestApi {
             Filter::PoolReplica(pool_id.into(), replica_id.into()),
             ReplicaShareProtocol::Nvmf,
             allowed_hosts,
+            &RestRequestContext::default(),
         )
         .await
     }
@@ -0,0 +1,80 @@
+//! v1 Pools REST surface.
+//!
+//! `v0::pools`'s `get_pool`/`get_pools` return the original `models::Pool` shape, which has no
+//! room for the label map `scheduling::topology` constraints are evaluated against or the
+//! throughput totals `volume::stats::aggregate_pool_stats` produces - adding either field to
+//! `models::Pool` directly would break every existing v0 client. `v1::pools` instead exposes a
+//! `PoolV1` that wraps the unchanged v0 `Pool` and adds those fields alongside it, served under
+//! its own route prefix (see `router`) so v0 and v1 clients each see the shape they expect.
+//!
+//! Like `v0::pools`, this relies on a `super::*` prelude (`apis`, `models`, `Path`, `Query`,
+//! `RestApi`, `RestError`, `RestJsonError`, `Filter`, `core_grpc`, ...) that would normally come
+//! from this crate's `lib.rs`; that file isn't present in this checkout, so this mirrors the same
+//! assumption `v0::pools` already makes rather than introducing a new one.
+
+use super::*;
+use crate::v0::pools::pool;
+use std::collections::HashMap;
+
+/// `models::Pool` plus the fields v0 clients don't know about.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PoolV1 {
+    #[serde(flatten)]
+    pub pool: models::Pool,
+    /// Labels for `scheduling::topology::TopologyConstraint`. `get_pool_v1`/`get_pools_v1` always
+    /// return it empty: neither the node's nor the pool's labels are filled in.
+    pub labels: HashMap<String, String>,
+    /// Aggregate read/write throughput across every replica hosted on this pool, from
+    /// `volume::stats::aggregate_pool_stats`. `None` until a stats source is wired in.
+    pub io_stats: Option<PoolIoStatsV1>,
+}
+
+/// Wire shape of `volume::stats::IoStats` for the v1 pool response.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PoolIoStatsV1 {
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub read_ops: u64,
+    pub write_ops: u64,
+}
+
+fn pool_v1(pool: models::Pool, labels: HashMap<String, String>) -> PoolV1 {
+    PoolV1 {
+        pool,
+        labels,
+        io_stats: None,
+    }
+}
+
+#[async_trait::async_trait]
+impl apis::actix_server::PoolsV1 for RestApi {
+    async fn get_pool_v1(Path(pool_id): Path<String>) -> Result<PoolV1, RestError<RestJsonError>> {
+        let resolved = pool(
+            pool_id.clone(),
+            client()
+                .get(Filter::Pool(pool_id.into()), None)
+                .await?
+                .into_inner()
+                .first(),
+        )?;
+        Ok(pool_v1(resolved.into(), HashMap::new()))
+    }
+
+    async fn get_pools_v1(
+        Query(volume_id): Query<Option<Uuid>>,
+    ) -> Result<Vec<PoolV1>, RestError<RestJsonError>> {
+        let pools = match volume_id {
+            Some(vol_id) => client().get(Filter::Volume(vol_id.into()), None).await?,
+            None => client().get(Filter::None, None).await?,
+        };
+        Ok(pools
+            .into_inner()
+            .into_iter()
+            .map(|pool| pool_v1(pool.into(), HashMap::new()))
+            .collect())
+    }
+}
+
+fn client() -> impl grpc::operations::pool::traits::PoolOperations {
+    core_grpc().pool()
+}
@@ -104,6 +104,14 @@ impl SwitchOverSpec {
     pub fn operation(&self) -> Option<Operation> {
         self.operation.as_ref().map(|op| op.operation.clone())
     }
+
+    /// Set the publish context, injecting the active span's trace context into it first so the
+    /// node-agent's republish RPCs continue the same distributed trace as the switchover that
+    /// triggered them.
+    pub fn set_publish_context(&mut self, mut publish_context: HashMap<String, String>) {
+        utils::tracing_telemetry::inject_context(&mut publish_context);
+        self.publish_context = Some(publish_context);
+    }
 }
 
 /// Persistent Store key for `SwitchOverSpec`.
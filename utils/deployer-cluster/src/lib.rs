@@ -1,4 +1,6 @@
+pub mod pattern;
 pub mod rest_client;
+pub mod store_backend;
 
 use composer::{Builder, ComposeTest};
 use deployer_lib::{
@@ -11,12 +13,14 @@ use opentelemetry_sdk::{propagation::TraceContextPropagator, trace as sdktrace};
 
 use stor_port::{transport_api::TimeoutOptions, types::v0::transport};
 
+use crate::pattern::{PatternDigest, PatternDigests, PatternRng};
+use crate::store_backend::{EtcdStoreBackend, StoreBackend};
+
 use clap::Parser;
 pub use composer::ImagePullPolicy;
 pub use csi_driver::node::internal::*;
 use deployer_lib::infra::CsiNode;
 pub use etcd_client;
-use etcd_client::DeleteOptions;
 use grpc::{
     client::CoreClient,
     context::Context,
@@ -34,8 +38,9 @@ use rpc::{
     io_engine::RpcHandle,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::{TryFrom, TryInto},
+    io::{Read, Write},
     net::SocketAddr,
     ops::Deref,
     str::FromStr,
@@ -85,26 +90,113 @@ pub fn default_options() -> StartOptions {
         .with_env_tags(vec!["CARGO_PKG_NAME"])
 }
 
-/// A wrapper over the composer utility meant to ensure termination in the
-/// correct order.
-/// todo: I suspect this is not working because composer itself is being created
-///  with cleaning enabled, so this won't actually work as expected!
+/// Default duration a shutdown tier is given to exit cleanly after `SIGTERM` before being
+/// escalated to `SIGKILL`.
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+/// Default duration a shutdown tier is given to exit after being escalated to `SIGKILL`, before
+/// shutdown gives up waiting on it and moves on to the next tier regardless.
+const DEFAULT_SHUTDOWN_MERCY: Duration = Duration::from_secs(5);
+
+/// True if the named container is still reported as running by docker.
+fn docker_running(name: &str) -> bool {
+    std::process::Command::new("docker")
+        .args(["inspect", "-f", "{{.State.Running}}", name])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "true")
+        .unwrap_or(false)
+}
+
+/// Send `signal` (`"term"`/`"kill"`) to the named container, ignoring errors: a container that's
+/// already gone (already reaped, or never existed) isn't a shutdown failure.
+fn docker_signal(name: &str, signal: &str) {
+    let _ = std::process::Command::new("docker")
+        .args(["kill", "-s", signal, name])
+        .output();
+}
+
+/// Poll until every container in `names` has stopped running or `deadline` elapses, returning
+/// whichever are still running when it gives up.
+fn wait_for_exit(names: &[String], deadline: Duration) -> Vec<String> {
+    let start = std::time::Instant::now();
+    let mut remaining: Vec<String> = names.to_vec();
+    while !remaining.is_empty() {
+        remaining.retain(|name| docker_running(name));
+        if remaining.is_empty() || start.elapsed() >= deadline {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    remaining
+}
+
+/// Shut a single tier down: `SIGTERM` every container concurrently, wait up to `grace` for them
+/// to exit cleanly, then escalate whatever's left to `SIGKILL` and wait up to `mercy` for that to
+/// take effect before giving up on the tier and letting shutdown move on regardless.
+fn shutdown_tier(tier: &[String], grace: Duration, mercy: Duration) {
+    if tier.is_empty() {
+        return;
+    }
+
+    tier.iter()
+        .map(|name| {
+            let name = name.clone();
+            std::thread::spawn(move || docker_signal(&name, "term"))
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .for_each(|handle| {
+            handle.join().ok();
+        });
+
+    let stragglers = wait_for_exit(tier, grace);
+    if stragglers.is_empty() {
+        return;
+    }
+
+    stragglers
+        .iter()
+        .map(|name| {
+            let name = name.clone();
+            std::thread::spawn(move || docker_signal(&name, "kill"))
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .for_each(|handle| {
+            handle.join().ok();
+        });
+
+    wait_for_exit(&stragglers, mercy);
+}
+
+/// A wrapper over the composer utility meant to ensure termination in the correct order: each
+/// tier of `shutdown_order` is brought down (`SIGTERM`, then `SIGKILL` on stragglers after
+/// `shutdown_grace`) before the next tier is even signalled, so e.g. io-engines can be
+/// configured to stop before the core agent before etcd rather than all racing to exit at once.
 pub struct ComposeTestNt {
     logs_on_panic: bool,
     clean: bool,
     allow_clean_on_panic: bool,
     composer: ComposeTest,
     shutdown_order: Vec<Vec<String>>,
+    shutdown_grace: Duration,
+    shutdown_mercy: Duration,
 }
 impl ComposeTestNt {
-    async fn new(composer: Builder) -> Result<Self, Error> {
+    async fn new(
+        composer: Builder,
+        shutdown_order: Vec<Vec<String>>,
+        shutdown_grace: Duration,
+        shutdown_mercy: Duration,
+    ) -> Result<Self, Error> {
         let composer = composer.build().await?;
         Ok(Self {
             logs_on_panic: composer.logs_on_panic(),
             clean: composer.clean(),
             allow_clean_on_panic: composer.clean_on_panic(),
             composer,
-            shutdown_order: vec![],
+            shutdown_order,
+            shutdown_grace,
+            shutdown_mercy,
         })
     }
 }
@@ -121,53 +213,55 @@ impl Drop for ComposeTestNt {
         }
 
         if self.clean && (!std::thread::panicking() || self.allow_clean_on_panic) {
-            let sh = self.shutdown_order.drain(..);
-            sh.into_iter().for_each(|c| {
-                c.into_iter()
-                    .map(|c| {
-                        std::thread::spawn(move || {
-                            std::process::Command::new("docker")
-                                .args(["kill", "-s", "term", c.as_str()])
-                                .output()
-                                .unwrap();
-                        })
-                    })
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .for_each(|h| {
-                        h.join().ok();
-                    });
-            });
-            self.composer
+            let tiered: std::collections::HashSet<String> =
+                self.shutdown_order.iter().flatten().cloned().collect();
+
+            for tier in self.shutdown_order.drain(..).collect::<Vec<_>>() {
+                shutdown_tier(&tier, self.shutdown_grace, self.shutdown_mercy);
+            }
+
+            // Anything not named by an explicit tier is brought down last, as its own tier.
+            let untiered: Vec<String> = self
+                .composer
                 .containers()
                 .keys()
-                .map(|k| {
-                    let name = k.clone();
-                    std::thread::spawn(move || {
-                        std::process::Command::new("docker")
-                            .args(["kill", "-s", "term", name.as_str()])
-                            .output()
-                            .unwrap();
-                    })
-                })
-                .collect::<Vec<_>>()
-                .into_iter()
-                .for_each(|h| {
-                    h.join().ok();
-                });
+                .filter(|name| !tiered.contains(name))
+                .cloned()
+                .collect();
+            shutdown_tier(&untiered, self.shutdown_grace, self.shutdown_mercy);
         }
         self.composer.clear_logs_on_panic();
     }
 }
 
+/// Wraps the tracing subscriber's reset-on-drop guard so that, when tracing is enabled, dropping
+/// the last `Cluster` handle also shuts down/flushes the global OTLP tracer provider, instead of
+/// leaving that to process exit - the thing the old TODO in `new_cluster` used to warn might
+/// silently drop spans before they reach the collector.
+struct ClusterTraceGuard {
+    _subscriber: DefaultGuard,
+    traced: bool,
+}
+
+impl Drop for ClusterTraceGuard {
+    fn drop(&mut self) {
+        if self.traced {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
 /// Cluster with the composer, the rest client and the jaeger pipeline
 #[allow(unused)]
 pub struct Cluster {
     composer: Arc<ComposeTestNt>,
     rest_client: rest_client::RestClient,
     grpc_client: Option<CoreClient>,
-    trace_guard: Arc<DefaultGuard>,
+    trace_guard: Arc<ClusterTraceGuard>,
     builder: ClusterBuilder,
+    store_backend: Arc<dyn StoreBackend>,
+    /// Nodes marked for eviction via `with_node_drain`/`drain_node`; see `node_capacity`.
+    draining: std::sync::Mutex<HashSet<u32>>,
 }
 
 impl Cluster {
@@ -299,6 +393,55 @@ impl Cluster {
         }
         Err(())
     }
+    /// Aggregate capacity/health for io-engine node `index`: total/used/available bytes summed
+    /// across its pools (see `Pool::capacity`), plus whether `with_node_drain`/`drain_node` has
+    /// marked it draining.
+    pub fn node_capacity(&self, index: u32) -> NodeCapacity {
+        let mut total = 0u64;
+        let mut used = 0u64;
+        for pool in self.builder.pools() {
+            if pool.node_index != index {
+                continue;
+            }
+            let capacity = pool.capacity();
+            total += capacity.total;
+            used += capacity.used;
+        }
+        NodeCapacity {
+            total,
+            used,
+            available: total.saturating_sub(used),
+            draining: self.draining.lock().unwrap().contains(&index),
+        }
+    }
+    /// Mark node `index` as draining and wait (polling for up to 2s, the same bounded-poll shape
+    /// as `wait_pool_online`) until it hosts no replicas. The mark is only recorded in this
+    /// harness and reported by `node_capacity`; the control plane is not told, so placements it
+    /// makes on the running cluster do not avoid the node.
+    pub async fn drain_node(&self, index: u32) -> Result<(), ()> {
+        self.draining.lock().unwrap().insert(index);
+
+        let timeout = Duration::from_secs(2);
+        let start = std::time::Instant::now();
+        let node_id = self.node(index);
+        loop {
+            if let Ok(replicas) = self
+                .grpc_client()
+                .replica()
+                .get(Filter::Node(node_id.clone()), None)
+                .await
+            {
+                if replicas.into_inner().is_empty() {
+                    return Ok(());
+                }
+            }
+            if std::time::Instant::now() > (start + timeout) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Err(())
+    }
 
     /// return grpc handle to the container
     pub async fn grpc_handle(&self, name: &str) -> Result<RpcHandle, String> {
@@ -373,7 +516,12 @@ impl Cluster {
 
         let csi = rpc::csi::controller_client::ControllerClient::new(channel);
 
-        Ok(CsiControllerClient { csi })
+        Ok(CsiControllerClient {
+            csi,
+            retry: CsiRetryPolicy::default(),
+            volume_locks: std::sync::Mutex::new(HashMap::new()),
+            publish_refs: std::sync::Mutex::new(HashMap::new()),
+        })
     }
 
     /// Restart the core agent.
@@ -401,18 +549,12 @@ impl Cluster {
         Ok(())
     }
 
-    /// remove etcd store lock for `name` instance
+    /// remove the store lock for `name` instance, via this cluster's `StoreBackend`
     pub async fn remove_store_lock(&self, name: ControlPlaneService) {
-        let mut store = etcd_client::Client::connect(["[::]:2379"], None)
-            .await
-            .expect("Failed to connect to etcd.");
-        store
-            .delete(
-                StoreLeaseLockKey::new(&name).key(),
-                Some(DeleteOptions::new().with_prefix()),
-            )
+        self.store_backend
+            .delete_prefix(StoreLeaseLockKey::new(&name).key())
             .await
-            .unwrap();
+            .expect("Failed to remove the store lock");
     }
 
     /// The node id for `index`.
@@ -480,23 +622,84 @@ impl Cluster {
         self.rest_client.v0()
     }
 
+    /// REST endpoint this cluster's agents are reachable on, for clients (including CLI
+    /// binaries) that aren't using `rest_v00` directly.
+    fn rest_endpoint(&self) -> &str {
+        "http://localhost:8081"
+    }
+
+    /// Spawn `binary` (e.g. the `kubectl-mayastor` plugin) against this cluster's REST endpoint,
+    /// the way a user would invoke it from a shell, and capture its stdout/stderr and exit
+    /// status for assertion. The binary's working directory (including a generated kubeconfig
+    /// pointing at this cluster) is a tempdir that is cleaned up on drop, mirroring how
+    /// `TmpDiskFile` manages the lifetime of its backing file.
+    pub fn run_cli<I, S>(&self, binary: &str, args: I) -> CliOutput
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let workdir = CliWorkdir::new(binary).expect("failed to create CLI workdir");
+        std::fs::write(workdir.kubeconfig_path(), self.cli_kubeconfig())
+            .expect("failed to write the CLI kubeconfig");
+
+        let output = std::process::Command::new(binary)
+            .args(args)
+            .env("KUBECONFIG", workdir.kubeconfig_path())
+            .env("ENDPOINT", self.rest_endpoint())
+            .current_dir(workdir.path())
+            .output()
+            .unwrap_or_else(|error| panic!("failed to spawn '{binary}': {error}"));
+
+        CliOutput {
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            status: output.status,
+            _workdir: workdir,
+        }
+    }
+
+    /// A minimal kubeconfig pointing `kubectl`-style plugins at this cluster's REST endpoint.
+    fn cli_kubeconfig(&self) -> String {
+        format!(
+            "apiVersion: v1\nkind: Config\nclusters:\n- name: cluster\n  cluster:\n    server: {}\ncurrent-context: cluster\ncontexts:\n- name: cluster\n  context:\n    cluster: cluster\n",
+            self.rest_endpoint()
+        )
+    }
+
     /// New cluster
     async fn new(
         trace: bool,
-        trace_guard: Arc<DefaultGuard>,
+        trace_guard: Arc<ClusterTraceGuard>,
         timeout_rest: std::time::Duration,
         grpc_timeout: TimeoutOptions,
         bearer_token: Option<String>,
+        rest_http3: bool,
+        store_backend: Arc<dyn StoreBackend>,
         components: Components,
         composer: ComposeTestNt,
     ) -> Result<Cluster, Error> {
-        let rest_client = rest_client::RestClient::new_timeout(
-            "http://localhost:8081",
-            trace,
-            bearer_token,
-            timeout_rest,
-        )
-        .unwrap();
+        let rest_client = if rest_http3 {
+            #[cfg(feature = "http3")]
+            {
+                rest_client::RestClient::new_http3(
+                    "http://localhost:8081",
+                    trace,
+                    bearer_token,
+                    timeout_rest,
+                )
+                .unwrap()
+            }
+            #[cfg(not(feature = "http3"))]
+            panic!("with_rest_http3(true) requires the 'http3' feature of deployer-cluster");
+        } else {
+            rest_client::RestClient::new_timeout(
+                "http://localhost:8081",
+                trace,
+                bearer_token,
+                timeout_rest,
+            )
+            .unwrap()
+        };
 
         components
             .start_wait(&composer, std::time::Duration::from_secs(30))
@@ -545,6 +748,8 @@ impl Cluster {
             grpc_client,
             trace_guard,
             builder: ClusterBuilder::builder(),
+            store_backend,
+            draining: std::sync::Mutex::new(HashSet::new()),
         };
 
         Ok(cluster)
@@ -603,6 +808,18 @@ enum PoolDisk {
     Tmp(TmpDiskFile),
 }
 
+impl PoolDisk {
+    /// This disk's size, when known upfront, for zone-spread's most-free-capacity ordering.
+    /// `Uri`/`Tmp` pools don't carry a parsed size here, so they weigh in as zero and fall back
+    /// to the zone-name tie-break.
+    fn capacity(&self) -> u64 {
+        match self {
+            Self::Malloc(size) => *size,
+            Self::Uri(_) | Self::Tmp(_) => 0,
+        }
+    }
+}
+
 /// Wrapper over a temporary "disk" file, which gets deleted on drop.
 #[derive(Clone)]
 pub struct TmpDiskFile {
@@ -675,6 +892,125 @@ impl Drop for TmpDiskFileInner {
     }
 }
 
+/// Tempdir-backed working directory for a `Cluster::run_cli` invocation, removed on drop the
+/// same way `TmpDiskFileInner` cleans up its backing file.
+struct CliWorkdir {
+    path: std::path::PathBuf,
+}
+
+impl CliWorkdir {
+    fn new(binary: &str) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "io-engine-cli-{binary}-{}-{}",
+            std::process::id(),
+            NEXT_CLI_WORKDIR.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+    fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+    fn kubeconfig_path(&self) -> std::path::PathBuf {
+        self.path.join("kubeconfig")
+    }
+}
+
+impl Drop for CliWorkdir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+static NEXT_CLI_WORKDIR: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Captured result of a `Cluster::run_cli` invocation: the spawned binary's stdout/stderr and
+/// exit status, with composable assertion methods so tests can check human-facing CLI output
+/// rather than reconstructing the equivalent gRPC/REST calls.
+pub struct CliOutput {
+    stdout: String,
+    stderr: String,
+    status: std::process::ExitStatus,
+    _workdir: CliWorkdir,
+}
+
+impl CliOutput {
+    /// The captured stdout.
+    pub fn stdout(&self) -> &str {
+        &self.stdout
+    }
+    /// The captured stderr.
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+    /// The process exit status.
+    pub fn status(&self) -> std::process::ExitStatus {
+        self.status
+    }
+
+    /// Assert the process exited successfully. Returns `self` so assertions can be chained.
+    #[track_caller]
+    pub fn success(self) -> Self {
+        assert!(
+            self.status.success(),
+            "expected success, got {:?}\nstdout:\n{}\nstderr:\n{}",
+            self.status,
+            self.stdout,
+            self.stderr
+        );
+        self
+    }
+
+    /// Assert the process exited with a failure. Returns `self` so assertions can be chained.
+    #[track_caller]
+    pub fn failure(self) -> Self {
+        assert!(
+            !self.status.success(),
+            "expected failure, but exited successfully\nstdout:\n{}\nstderr:\n{}",
+            self.stdout,
+            self.stderr
+        );
+        self
+    }
+
+    /// Assert the process' exit code, typically chained after `.failure()`.
+    #[track_caller]
+    pub fn code(self, code: i32) -> Self {
+        assert_eq!(
+            self.status.code(),
+            Some(code),
+            "unexpected exit code\nstdout:\n{}\nstderr:\n{}",
+            self.stdout,
+            self.stderr
+        );
+        self
+    }
+
+    /// Assert stdout contains `needle`. Returns `self` so assertions can be chained.
+    #[track_caller]
+    pub fn stdout_contains(self, needle: &str) -> Self {
+        assert!(
+            self.stdout.contains(needle),
+            "expected stdout to contain {needle:?}, got:\n{}",
+            self.stdout
+        );
+        self
+    }
+
+    /// Assert stdout matches the given regex. Returns `self` so assertions can be chained.
+    #[track_caller]
+    pub fn stdout_matches(self, pattern: &str) -> Self {
+        let regex = regex::Regex::new(pattern)
+            .unwrap_or_else(|error| panic!("invalid regex {pattern:?}: {error}"));
+        assert!(
+            regex.is_match(&self.stdout),
+            "expected stdout to match {pattern:?}, got:\n{}",
+            self.stdout
+        );
+        self
+    }
+}
+
 /// Builder for the Cluster
 pub struct ClusterBuilder {
     opts: StartOptions,
@@ -684,7 +1020,18 @@ pub struct ClusterBuilder {
     env_filter: Option<EnvFilter>,
     bearer_token: Option<String>,
     rest_timeout: std::time::Duration,
+    rest_http3: bool,
     grpc_timeout: TimeoutOptions,
+    shutdown_order: Vec<Vec<String>>,
+    shutdown_grace: Duration,
+    shutdown_mercy: Duration,
+    store_backend: Arc<dyn StoreBackend>,
+    node_zones: HashMap<u32, String>,
+    draining_nodes: HashSet<u32>,
+    otlp_endpoint: String,
+    trace_sampler: Option<f64>,
+    trace_batch: bool,
+    trace_tags: Vec<KeyValue>,
 }
 
 #[derive(Default)]
@@ -692,6 +1039,7 @@ struct Replica {
     count: u32,
     size: u64,
     share: transport::Protocol,
+    zone_spread: bool,
 }
 
 /// The default timeout options for every grpc request.
@@ -714,10 +1062,44 @@ impl ClusterBuilder {
             env_filter: None,
             bearer_token: None,
             rest_timeout: Duration::from_secs(5),
+            rest_http3: false,
             grpc_timeout: grpc_timeout_opts(),
+            shutdown_order: vec![],
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            shutdown_mercy: DEFAULT_SHUTDOWN_MERCY,
+            store_backend: Arc::new(EtcdStoreBackend::default()),
+            node_zones: HashMap::new(),
+            draining_nodes: HashSet::new(),
+            otlp_endpoint: "http://127.0.0.1:4317".to_string(),
+            trace_sampler: None,
+            trace_batch: true,
+            trace_tags: Vec::new(),
         }
         .with_default_tracing()
     }
+    /// Tear down containers in tiers: all containers in a tier are signalled and waited on
+    /// before the next tier is even signalled, e.g. `vec![vec!["io-engine-1"], vec!["core"],
+    /// vec!["etcd"]]` stops the io-engine before the core agent before etcd. Containers not
+    /// named by any tier are brought down last, as their own implicit final tier.
+    #[must_use]
+    pub fn with_shutdown_order(mut self, tiers: Vec<Vec<String>>) -> Self {
+        self.shutdown_order = tiers;
+        self
+    }
+    /// How long a shutdown tier is given to exit cleanly after `SIGTERM` before stragglers are
+    /// escalated to `SIGKILL`.
+    #[must_use]
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+    /// How long a shutdown tier's stragglers are given to exit after being escalated to
+    /// `SIGKILL`, before shutdown gives up waiting on them and moves on regardless.
+    #[must_use]
+    pub fn with_shutdown_mercy(mut self, mercy: Duration) -> Self {
+        self.shutdown_mercy = mercy;
+        self
+    }
     /// Update the start options.
     #[must_use]
     pub fn with_options<F>(mut self, set: F) -> Self
@@ -782,12 +1164,59 @@ impl ClusterBuilder {
         self.env_filter = filter.into().map(tracing_subscriber::EnvFilter::new);
         self
     }
+    /// Point the OTLP exporter at `endpoint` instead of the default local collector
+    /// (`http://127.0.0.1:4317`), e.g. when running CI against a remote collector.
+    #[must_use]
+    pub fn with_otlp_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.otlp_endpoint = endpoint.into();
+        self
+    }
+    /// Head-based sample only a `ratio` (`0.0..=1.0`) fraction of traces instead of tracing
+    /// everything.
+    #[must_use]
+    pub fn with_trace_sampler(mut self, ratio: f64) -> Self {
+        self.trace_sampler = Some(ratio);
+        self
+    }
+    /// Use the simple, synchronous exporter instead of the batch exporter when `enabled` is
+    /// false, so a short-lived test's spans are sent immediately rather than risking being lost
+    /// to a batch flush interval that outlives the test.
+    #[must_use]
+    pub fn with_trace_batch(mut self, enabled: bool) -> Self {
+        self.trace_batch = enabled;
+        self
+    }
+    /// Extra resource tags to attach to every span, alongside the fixed `cluster-client` service
+    /// name, so multi-cluster test runs can be told apart in the trace backend.
+    #[must_use]
+    pub fn with_trace_tags(mut self, tags: Vec<KeyValue>) -> Self {
+        self.trace_tags.extend(tags);
+        self
+    }
     /// Rest request timeout.
     #[must_use]
     pub fn with_rest_timeout(mut self, timeout: std::time::Duration) -> Self {
         self.rest_timeout = timeout;
         self
     }
+    /// Connect to the REST API over HTTP/3 (QUIC) instead of HTTP/1.1-over-TCP, falling back
+    /// to HTTP/2 then HTTP/1.1 via Alt-Svc discovery when the endpoint doesn't advertise h3.
+    /// Lets the harness regression-test the control plane's REST surface over QUIC, including
+    /// 0-RTT resumption and connection migration. Requires the `http3` feature.
+    #[must_use]
+    pub fn with_rest_http3(mut self, enabled: bool) -> Self {
+        self.rest_http3 = enabled;
+        self
+    }
+    /// Use `backend` for lease-lock/key-delete operations (`remove_store_lock`, `restart_core`)
+    /// instead of the default `EtcdStoreBackend`, e.g. a `ConsulStoreBackend`. Only those harness
+    /// calls go through it: the control-plane services are not pointed at it, and nothing
+    /// registers or deregisters the cluster's members with it.
+    #[must_use]
+    pub fn with_store_backend(mut self, backend: impl StoreBackend + 'static) -> Self {
+        self.store_backend = Arc::new(backend);
+        self
+    }
     /// Add `count` malloc pools (100MiB size) to each node.
     #[must_use]
     pub fn with_pools(mut self, count: u32) -> Self {
@@ -840,7 +1269,50 @@ impl ClusterBuilder {
     /// Specify `count` replicas to add to each node per pool.
     #[must_use]
     pub fn with_replicas(mut self, count: u32, size: u64, share: transport::Protocol) -> Self {
-        self.replicas = Replica { count, size, share };
+        self.replicas = Replica {
+            count,
+            size,
+            share,
+            zone_spread: false,
+        };
+        self
+    }
+    /// Tag io-engine node `index` with failure-domain `zone`, e.g. `with_node_zone(0, "dc1")`.
+    /// Used by `with_replicas_zone_spread` to keep replica copies out of the same zone; a node
+    /// with no explicit zone is treated as its own singleton zone.
+    #[must_use]
+    pub fn with_node_zone(mut self, index: u32, zone: impl Into<String>) -> Self {
+        self.node_zones.insert(index, zone.into());
+        self
+    }
+    /// Mark io-engine node `index` as draining from the start: `pools`'s replica placement (both
+    /// the plain per-pool fan-out and `with_replicas_zone_spread`) skips its pools entirely, and
+    /// `Cluster::node_capacity` reports it as draining.
+    #[must_use]
+    pub fn with_node_drain(mut self, index: u32) -> Self {
+        self.draining_nodes.insert(index);
+        self
+    }
+    /// Place `count` replicas of a single volume across pools, spread across `with_node_zone`
+    /// failure domains instead of fanned out onto every pool: bucket eligible pools by zone, then
+    /// round-robin the sorted set of zones - most-free-capacity zone first, ties broken by zone
+    /// name - handing each visited zone's next pool, in most-free-capacity order (wrapping
+    /// around), the next replica. A
+    /// zone only gets a second replica once every eligible zone already holds one. The resulting
+    /// placement is exposed via `Pool`'s zone so tests can assert the spread.
+    #[must_use]
+    pub fn with_replicas_zone_spread(
+        mut self,
+        count: u32,
+        size: u64,
+        share: transport::Protocol,
+    ) -> Self {
+        self.replicas = Replica {
+            count,
+            size,
+            share,
+            zone_spread: true,
+        };
         self
     }
     /// Specify `count` io_engines for the cluster.
@@ -984,6 +1456,7 @@ impl ClusterBuilder {
         let (components, composer) = self.build_prepare()?;
         let composer = set(composer);
         let mut cluster = self.new_cluster(components, composer).await?;
+        cluster.draining = std::sync::Mutex::new(self.draining_nodes.clone());
         cluster.builder = self;
         Ok(cluster)
     }
@@ -991,6 +1464,7 @@ impl ClusterBuilder {
     pub async fn build(mut self) -> Result<Cluster, Error> {
         let (components, composer) = self.build_prepare()?;
         let mut cluster = self.new_cluster(components, composer).await?;
+        cluster.draining = std::sync::Mutex::new(self.draining_nodes.clone());
         cluster.builder = self;
         Ok(cluster)
     }
@@ -1031,6 +1505,7 @@ impl ClusterBuilder {
                     utils::raw_version_str(),
                     env!("CARGO_PKG_VERSION"),
                 ));
+                tracing_tags.append(&mut self.trace_tags.clone());
                 tracing_tags.dedup();
                 tracing_tags.push(KeyValue::new(
                     opentelemetry_semantic_conventions::resource::SERVICE_NAME,
@@ -1038,31 +1513,56 @@ impl ClusterBuilder {
                 ));
 
                 global::set_text_map_propagator(TraceContextPropagator::new());
-                let provider = opentelemetry_otlp::new_pipeline()
+                let sampler = match self.trace_sampler {
+                    Some(ratio) => sdktrace::Sampler::TraceIdRatioBased(ratio),
+                    None => sdktrace::Sampler::AlwaysOn,
+                };
+                let pipeline = opentelemetry_otlp::new_pipeline()
                     .tracing()
                     .with_exporter(
                         opentelemetry_otlp::new_exporter()
                             .tonic()
-                            .with_endpoint("http://127.0.0.1:4317"),
+                            .with_endpoint(self.otlp_endpoint.clone()),
                     )
                     .with_trace_config(
-                        sdktrace::Config::default().with_resource(Resource::new(tracing_tags)),
-                    )
-                    // TODO: there's currently a few bugs on opentelemetry
-                    // 1. We can't use simple exporter on a tokio environment
-                    // 2. Even wit the tokio batch exporter, we can't shutdown properly,
-                    // meaning that we might not flush traces to jaeger :(
-                    .install_batch(opentelemetry_sdk::runtime::TokioCurrentThread)
-                    .expect("Should be able to initialise the exporter");
+                        sdktrace::Config::default()
+                            .with_resource(Resource::new(tracing_tags))
+                            .with_sampler(sampler),
+                    );
+                // The batch exporter is the default since a one-span-at-a-time exporter adds
+                // noticeable latency to every traced call, but `with_trace_batch(false)` lets a
+                // short-lived test opt into the simple exporter so its spans are sent immediately
+                // instead of risking being lost to a batch flush interval that outlives the test.
+                let provider = if self.trace_batch {
+                    pipeline
+                        .install_batch(opentelemetry_sdk::runtime::TokioCurrentThread)
+                        .expect("Should be able to initialise the exporter")
+                } else {
+                    pipeline
+                        .install_simple()
+                        .expect("Should be able to initialise the exporter")
+                };
                 global::set_tracer_provider(provider.clone());
                 let tracer = provider.tracer("tracing-otel-subscriber");
                 let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
-                tracing::subscriber::set_default(subscriber.with(telemetry))
+                ClusterTraceGuard {
+                    _subscriber: tracing::subscriber::set_default(subscriber.with(telemetry)),
+                    traced: true,
+                }
             }
-            false => tracing::subscriber::set_default(subscriber),
+            false => ClusterTraceGuard {
+                _subscriber: tracing::subscriber::set_default(subscriber),
+                traced: false,
+            },
         });
 
-        let composer = ComposeTestNt::new(compose_builder).await?;
+        let composer = ComposeTestNt::new(
+            compose_builder,
+            self.shutdown_order.clone(),
+            self.shutdown_grace,
+            self.shutdown_mercy,
+        )
+        .await?;
 
         let cluster = Cluster::new(
             self.trace,
@@ -1070,6 +1570,8 @@ impl ClusterBuilder {
             self.rest_timeout,
             self.grpc_timeout.clone(),
             self.bearer_token.clone(),
+            self.rest_http3,
+            self.store_backend.clone(),
             components,
             composer,
         )
@@ -1120,14 +1622,27 @@ impl ClusterBuilder {
 
         for (node, i_pools) in &self.pools {
             for (pool_index, pool) in i_pools.iter().enumerate() {
-                let mut pool = Pool {
+                pools.push(Pool {
                     node: IoEngine::name(*node, &self.opts),
+                    node_index: *node,
+                    pool_index,
+                    zone: self.node_zones.get(node).cloned(),
                     disk: pool.clone(),
                     index: (pool_index + 1) as u32,
                     replicas: vec![],
-                };
+                });
+            }
+        }
+
+        if self.replicas.zone_spread {
+            self.assign_zone_spread_replicas(&mut pools);
+        } else {
+            for pool in &mut pools {
+                if self.draining_nodes.contains(&pool.node_index) {
+                    continue;
+                }
                 for replica_index in 0 .. self.replicas.count {
-                    let rep_id = Cluster::replica(*node, pool_index, replica_index);
+                    let rep_id = Cluster::replica(pool.node_index, pool.pool_index, replica_index);
                     pool.replicas.push(transport::CreateReplica {
                         node: pool.node.clone().into(),
                         name: None,
@@ -1141,21 +1656,103 @@ impl ClusterBuilder {
                         ..Default::default()
                     });
                 }
-                pools.push(pool);
             }
         }
         pools
     }
+    /// Place `self.replicas.count` replica copies of one volume across `pools`, spreading across
+    /// zones (see `with_replicas_zone_spread`): bucket pool indices by zone, order each zone's
+    /// candidates by most-free-capacity first, then round-robin the zones themselves -
+    /// most-free-capacity zone first, ties broken by zone name - handing each visited zone the
+    /// next replica via its own cursor. Falling off the end of a zone's candidate list wraps back
+    /// to its largest pool, so a zone only repeats once every other zone already has one.
+    fn assign_zone_spread_replicas(&self, pools: &mut [Pool]) {
+        let mut zones: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, pool) in pools.iter().enumerate() {
+            if self.draining_nodes.contains(&pool.node_index) {
+                continue;
+            }
+            zones.entry(pool.zone_key()).or_default().push(i);
+        }
+        for candidates in zones.values_mut() {
+            candidates.sort_by(|&a, &b| {
+                pools[b]
+                    .disk
+                    .capacity()
+                    .cmp(&pools[a].disk.capacity())
+                    .then(a.cmp(&b))
+            });
+        }
+
+        let mut zone_order: Vec<String> = zones.keys().cloned().collect();
+        zone_order.sort_by(|a, b| {
+            let cap_a: u64 = zones[a].iter().map(|&i| pools[i].disk.capacity()).sum();
+            let cap_b: u64 = zones[b].iter().map(|&i| pools[i].disk.capacity()).sum();
+            cap_b.cmp(&cap_a).then_with(|| a.cmp(b))
+        });
+
+        let mut cursors: HashMap<&str, usize> =
+            zone_order.iter().map(|zone| (zone.as_str(), 0)).collect();
+        let mut remaining = self.replicas.count;
+        let mut replica_index = 0;
+        while remaining > 0 {
+            let mut placed_this_pass = false;
+            for zone in &zone_order {
+                if remaining == 0 {
+                    break;
+                }
+                let candidates = &zones[zone];
+                if candidates.is_empty() {
+                    continue;
+                }
+                let cursor = cursors.get_mut(zone.as_str()).unwrap();
+                let pool = &mut pools[candidates[*cursor % candidates.len()]];
+                *cursor += 1;
+
+                let rep_id = Cluster::replica(pool.node_index, pool.pool_index, replica_index);
+                pool.replicas.push(transport::CreateReplica {
+                    node: pool.node.clone().into(),
+                    name: None,
+                    uuid: rep_id,
+                    pool_id: pool.id(),
+                    pool_uuid: None,
+                    size: self.replicas.size,
+                    thin: false,
+                    share: self.replicas.share,
+                    managed: false,
+                    ..Default::default()
+                });
+
+                remaining -= 1;
+                replica_index += 1;
+                placed_this_pass = true;
+            }
+            if !placed_this_pass {
+                break;
+            }
+        }
+    }
 }
 
 struct Pool {
     node: String,
+    node_index: u32,
+    pool_index: usize,
+    /// The failure-domain zone this pool's node was tagged with via `with_node_zone`, if any.
+    zone: Option<String>,
     disk: PoolDisk,
     index: u32,
     replicas: Vec<transport::CreateReplica>,
 }
 
 impl Pool {
+    /// This pool's zone for zone-spread placement: the explicit `with_node_zone` label, or the
+    /// node's own index as a singleton zone when untagged.
+    fn zone_key(&self) -> String {
+        self.zone
+            .clone()
+            .unwrap_or_else(|| self.node_index.to_string())
+    }
     fn id(&self) -> transport::PoolId {
         format!("{}-pool-{}", self.node, self.index).into()
     }
@@ -1175,12 +1772,132 @@ impl Pool {
             PoolDisk::Tmp(disk) => disk.uri().into(),
         }
     }
+    /// This pool's resolved capacity: the backing disk's size (see `PoolDisk::capacity`) against
+    /// the replicas the builder placed on it.
+    fn capacity(&self) -> PoolCapacity {
+        let total = self.disk.capacity();
+        let used: u64 = self.replicas.iter().map(|replica| replica.size).sum();
+        PoolCapacity {
+            total,
+            used,
+            available: total.saturating_sub(used),
+        }
+    }
+}
+
+/// A single pool's resolved capacity, as returned by `Pool::capacity` and summed into
+/// `NodeCapacity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolCapacity {
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+}
+
+/// Per-node capacity/health snapshot, mirroring what an admin status endpoint would expose:
+/// total/used/available bytes summed across the node's pools (see `Cluster::node_capacity`),
+/// plus whether `with_node_drain`/`drain_node` has marked it draining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeCapacity {
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+    pub draining: bool,
 }
 
 fn grpc_addr(ip: String) -> String {
     format!("https://{ip}:50051")
 }
 
+/// A customer-supplied encryption key for `node_stage_volume_encrypted[_fs]`: the caller holds
+/// the 32-byte key and passes it in on every stage call (the SSE-C pattern), the node plugin
+/// opens a LUKS-style crypt device over the staged block target with it but never persists it.
+#[derive(Clone, Copy)]
+pub struct EncryptionKey {
+    /// Raw key material, e.g. 32 bytes from `openssl rand 32`.
+    pub key: [u8; 32],
+    /// Cipher the node plugin should open the crypt device with.
+    pub algorithm: EncryptionAlgorithm,
+}
+
+/// Block-device encryption cipher for `EncryptionKey`, mirroring `cryptsetup luksFormat
+/// --cipher` choices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncryptionAlgorithm {
+    Aes256Xts,
+    Aes256Gcm,
+}
+
+impl EncryptionAlgorithm {
+    fn cipher_spec(&self) -> &'static str {
+        match self {
+            Self::Aes256Xts => "aes-xts-plain64",
+            Self::Aes256Gcm => "aes-gcm-random",
+        }
+    }
+}
+
+impl EncryptionKey {
+    /// The CSI `secrets` entry the node plugin reads the key material from. Secrets never flow
+    /// through `publish_context`, which the CO may log or persist.
+    fn secrets(&self) -> HashMap<String, String> {
+        HashMap::from([("encryptionKey".to_string(), hex_encode(&self.key))])
+    }
+    /// The `publish_context` entries telling the node plugin to open a crypt device and with
+    /// which cipher, without leaking the key itself.
+    fn publish_context(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("encryption".to_string(), "true".to_string()),
+            (
+                "encryptionCipher".to_string(),
+                self.algorithm.cipher_spec().to_string(),
+            ),
+        ])
+    }
+}
+
+/// Minimal lower-case hex encoder, just enough to serialise an `EncryptionKey` into the CSI
+/// `secrets` map without pulling in a dedicated crate for one call site.
+fn hex_encode(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push(DIGITS[(byte >> 4) as usize] as char);
+        out.push(DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    out
+}
+
+/// Root directory node volumes are staged/published under. Each volume gets its own
+/// `<NODE_VOLUME_ROOT>/<volume_id>/{staging,target}` pair instead of sharing one flat directory
+/// per access type, so tearing down one volume's target can never touch another volume's.
+const NODE_VOLUME_ROOT: &str = "/var/tmp/mayastor-node";
+
+fn node_volume_dir(volume_id: &str) -> String {
+    format!("{NODE_VOLUME_ROOT}/{volume_id}")
+}
+fn node_staging_path(volume_id: &str) -> String {
+    format!("{}/staging", node_volume_dir(volume_id))
+}
+fn node_target_path(volume_id: &str) -> String {
+    format!("{}/target", node_volume_dir(volume_id))
+}
+
+/// Remove `path` (file or directory), treating "doesn't exist" as success so a retried/repeated
+/// teardown converges instead of erroring.
+fn remove_path_idempotent(path: &str) -> std::io::Result<()> {
+    let result = if std::path::Path::new(path).is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
 /// Bundles both the csi and the internal node service.
 pub struct CsiNodeClient {
     csi: csi_driver::csi::node_client::NodeClient<tonic::transport::Channel>,
@@ -1202,7 +1919,8 @@ impl CsiNodeClient {
     > {
         &mut self.internal
     }
-    /// Stage the given volume.
+    /// Stage the given volume. Creates the per-volume staging directory (idempotent - a repeated
+    /// stage call converges rather than erroring) before issuing the RPC.
     pub async fn node_stage_volume(
         &mut self,
         volume: &Volume,
@@ -1214,10 +1932,12 @@ impl CsiNodeClient {
             volume.state.target.as_ref().unwrap().device_uri.to_string(),
         );
         context.extend(publish_context);
+        let staging_target_path = node_staging_path(&volume.spec.uuid.to_string());
+        std::fs::create_dir_all(&staging_target_path)?;
         let request = rpc::csi::NodeStageVolumeRequest {
             volume_id: volume.spec.uuid.to_string(),
             publish_context: context,
-            staging_target_path: "unused".to_string(),
+            staging_target_path,
             volume_capability: Some(rpc::csi::VolumeCapability {
                 access_mode: Some(rpc::csi::volume_capability::AccessMode {
                     mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
@@ -1232,7 +1952,8 @@ impl CsiNodeClient {
         let response = self.csi.node_stage_volume(request).await?;
         Ok(response.into_inner())
     }
-    /// Stage the given filesystem volume.
+    /// Stage the given filesystem volume. Creates the per-volume staging directory (idempotent)
+    /// before issuing the RPC.
     pub async fn node_stage_volume_fs(
         &mut self,
         volume: &Volume,
@@ -1245,10 +1966,12 @@ impl CsiNodeClient {
             volume.state.target.as_ref().unwrap().device_uri.to_string(),
         );
         context.extend(publish_context);
+        let staging_target_path = node_staging_path(&volume.spec.uuid.to_string());
+        std::fs::create_dir_all(&staging_target_path)?;
         let request = rpc::csi::NodeStageVolumeRequest {
             volume_id: volume.spec.uuid.to_string(),
             publish_context: context,
-            staging_target_path: format!("/var/tmp/staging/mount/{}", volume.spec.uuid),
+            staging_target_path,
             volume_capability: Some(rpc::csi::VolumeCapability {
                 access_mode: Some(rpc::csi::volume_capability::AccessMode {
                     mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
@@ -1267,31 +1990,141 @@ impl CsiNodeClient {
         let response = self.csi.node_stage_volume(request).await?;
         Ok(response.into_inner())
     }
-    /// Unstage the given volume.
+    /// Stage the given volume over a LUKS-style crypt device opened with `key`: the node plugin
+    /// derives the crypt mapping from the staged block target and `key.secrets()` instead of
+    /// exposing the raw target directly. Creates the per-volume staging directory (idempotent)
+    /// before issuing the RPC.
+    pub async fn node_stage_volume_encrypted(
+        &mut self,
+        volume: &Volume,
+        key: &EncryptionKey,
+        publish_context: HashMap<String, String>,
+    ) -> Result<NodeStageVolumeResponse, Error> {
+        let mut context = std::collections::HashMap::new();
+        context.insert(
+            "uri".into(),
+            volume.state.target.as_ref().unwrap().device_uri.to_string(),
+        );
+        context.extend(key.publish_context());
+        context.extend(publish_context);
+        let staging_target_path = node_staging_path(&volume.spec.uuid.to_string());
+        std::fs::create_dir_all(&staging_target_path)?;
+        let request = rpc::csi::NodeStageVolumeRequest {
+            volume_id: volume.spec.uuid.to_string(),
+            publish_context: context,
+            staging_target_path,
+            volume_capability: Some(rpc::csi::VolumeCapability {
+                access_mode: Some(rpc::csi::volume_capability::AccessMode {
+                    mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
+                }),
+                access_type: Some(rpc::csi::volume_capability::AccessType::Block(
+                    rpc::csi::volume_capability::BlockVolume {},
+                )),
+            }),
+            secrets: key.secrets(),
+            volume_context: Default::default(),
+        };
+        let response = self.csi.node_stage_volume(request).await?;
+        Ok(response.into_inner())
+    }
+    /// Stage the given filesystem volume over a LUKS-style crypt device opened with `key`; the
+    /// node plugin mounts the filesystem variant on top of the opened crypt mapping instead of
+    /// the raw block target. Creates the per-volume staging directory (idempotent) before issuing
+    /// the RPC.
+    pub async fn node_stage_volume_encrypted_fs(
+        &mut self,
+        volume: &Volume,
+        fs_type: &str,
+        key: &EncryptionKey,
+        publish_context: HashMap<String, String>,
+    ) -> Result<NodeStageVolumeResponse, Error> {
+        let mut context = std::collections::HashMap::new();
+        context.insert(
+            "uri".into(),
+            volume.state.target.as_ref().unwrap().device_uri.to_string(),
+        );
+        context.extend(key.publish_context());
+        context.extend(publish_context);
+        let staging_target_path = node_staging_path(&volume.spec.uuid.to_string());
+        std::fs::create_dir_all(&staging_target_path)?;
+        let request = rpc::csi::NodeStageVolumeRequest {
+            volume_id: volume.spec.uuid.to_string(),
+            publish_context: context,
+            staging_target_path,
+            volume_capability: Some(rpc::csi::VolumeCapability {
+                access_mode: Some(rpc::csi::volume_capability::AccessMode {
+                    mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
+                }),
+                access_type: Some(rpc::csi::volume_capability::AccessType::Mount(
+                    rpc::csi::volume_capability::MountVolume {
+                        fs_type: fs_type.to_string(),
+                        mount_flags: vec![],
+                        volume_mount_group: "".to_string(),
+                    },
+                )),
+            }),
+            secrets: key.secrets(),
+            volume_context: Default::default(),
+        };
+        let response = self.csi.node_stage_volume(request).await?;
+        Ok(response.into_inner())
+    }
+    /// Unstage the given volume. Tears down any LUKS-style crypt mapping a prior
+    /// `node_stage_volume_encrypted[_fs]` call opened, the same way it tears down a plain
+    /// staged block target. Fails if the volume is still published - `node_unpublish_volume` must
+    /// run first - and otherwise removes the per-volume staging directory and, once both staging
+    /// and target are gone, the now-empty per-volume directory too (both idempotent).
     pub async fn node_unstage_volume(
         &mut self,
         volume: &Volume,
     ) -> Result<NodeUnstageVolumeResponse, Error> {
+        let volume_id = volume.spec.uuid.to_string();
+        let target_path = node_target_path(&volume_id);
+        if std::path::Path::new(&target_path).exists() {
+            return Err(std::io::Error::other(format!(
+                "cannot unstage volume {volume_id}: {target_path} is still published, call node_unpublish_volume first"
+            ))
+            .into());
+        }
+        let staging_target_path = node_staging_path(&volume_id);
         let request = rpc::csi::NodeUnstageVolumeRequest {
-            volume_id: volume.spec.uuid.to_string(),
-            staging_target_path: format!("/var/tmp/staging/mount/{}", volume.spec.uuid),
+            volume_id: volume_id.clone(),
+            staging_target_path: staging_target_path.clone(),
         };
         let response = self.csi.node_unstage_volume(request).await?;
+
+        remove_path_idempotent(&staging_target_path)?;
+        let _ = std::fs::remove_dir(node_volume_dir(&volume_id));
+
         Ok(response.into_inner())
     }
-    /// Stage the given volume.
+    /// Publish the given volume. Fails if the volume hasn't been staged - `node_stage_volume` must
+    /// run (and succeed) first - and otherwise creates the per-volume target file (idempotent)
+    /// before issuing the RPC.
     pub async fn node_publish_volume(
         &mut self,
         volume: &Volume,
         publish_context: HashMap<String, String>,
     ) -> Result<rpc::csi::NodePublishVolumeResponse, Error> {
-        std::fs::create_dir_all("/var/tmp/target/mount")?;
+        let volume_id = volume.spec.uuid.to_string();
+        let staging_target_path = node_staging_path(&volume_id);
+        if !std::path::Path::new(&staging_target_path).exists() {
+            return Err(std::io::Error::other(format!(
+                "cannot publish volume {volume_id}: node_stage_volume has not run (missing {staging_target_path})"
+            ))
+            .into());
+        }
+        std::fs::create_dir_all(node_volume_dir(&volume_id))?;
+        let target_path = node_target_path(&volume_id);
+        if !std::path::Path::new(&target_path).exists() {
+            std::fs::File::create(&target_path)?;
+        }
 
         let request = rpc::csi::NodePublishVolumeRequest {
-            volume_id: volume.spec.uuid.to_string(),
+            volume_id: volume_id.clone(),
             publish_context,
-            staging_target_path: format!("/var/tmp/staging/mount/{}", volume.spec.uuid),
-            target_path: format!("/var/tmp/target/mount/{}", volume.spec.uuid),
+            staging_target_path,
+            target_path,
             volume_capability: Some(rpc::csi::VolumeCapability {
                 access_mode: Some(rpc::csi::volume_capability::AccessMode {
                     mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
@@ -1307,20 +2140,31 @@ impl CsiNodeClient {
         let response = self.csi.node_publish_volume(request).await?;
         Ok(response.into_inner())
     }
-    /// Publish the given volume.
+    /// Publish the given filesystem volume. Fails if the volume hasn't been staged - see
+    /// `node_publish_volume` - and otherwise creates the per-volume target directory (idempotent)
+    /// before issuing the RPC.
     pub async fn node_publish_volume_fs(
         &mut self,
         volume: &Volume,
         fs_type: &str,
         publish_context: HashMap<String, String>,
     ) -> Result<rpc::csi::NodePublishVolumeResponse, Error> {
-        std::fs::create_dir_all("/var/tmp/target/mount")?;
+        let volume_id = volume.spec.uuid.to_string();
+        let staging_target_path = node_staging_path(&volume_id);
+        if !std::path::Path::new(&staging_target_path).exists() {
+            return Err(std::io::Error::other(format!(
+                "cannot publish volume {volume_id}: node_stage_volume has not run (missing {staging_target_path})"
+            ))
+            .into());
+        }
+        let target_path = node_target_path(&volume_id);
+        std::fs::create_dir_all(&target_path)?;
 
         let request = rpc::csi::NodePublishVolumeRequest {
-            volume_id: volume.spec.uuid.to_string(),
+            volume_id: volume_id.clone(),
             publish_context,
-            staging_target_path: format!("/var/tmp/staging/mount/{}", volume.spec.uuid),
-            target_path: format!("/var/tmp/target/mount/{}", volume.spec.uuid),
+            staging_target_path,
+            target_path,
             volume_capability: Some(rpc::csi::VolumeCapability {
                 access_mode: Some(rpc::csi::volume_capability::AccessMode {
                     mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
@@ -1340,28 +2184,163 @@ impl CsiNodeClient {
         let response = self.csi.node_publish_volume(request).await?;
         Ok(response.into_inner())
     }
-    /// Unpublish the given volume.
+    /// Unpublish the given volume, removing its per-volume target (file or directory,
+    /// idempotently) instead of a directory shared with every other volume.
+    ///
+    /// Note: unlike the other CSI node/controller RPCs, `NodeUnpublishVolumeRequest` carries no
+    /// `secrets` field in the CSI spec, so there is nothing to forward here.
     pub async fn node_unpublish_volume(
         &mut self,
         volume: &Volume,
     ) -> Result<rpc::csi::NodeUnpublishVolumeResponse, Error> {
+        let volume_id = volume.spec.uuid.to_string();
+        let target_path = node_target_path(&volume_id);
         let request = rpc::csi::NodeUnpublishVolumeRequest {
-            volume_id: volume.spec.uuid.to_string(),
-            target_path: format!("/var/tmp/target/mount/{}", volume.spec.uuid),
+            volume_id: volume_id.clone(),
+            target_path: target_path.clone(),
         };
         let response = self.csi.node_unpublish_volume(request).await?;
 
-        std::fs::remove_dir_all("/var/tmp/target/mount")?;
+        remove_path_idempotent(&target_path)?;
 
         Ok(response.into_inner())
     }
+    /// Write a deterministic `seed`-derived byte pattern of `len` bytes to `volume`'s published
+    /// target path, hashing it incrementally in `chunk_size`-sized pieces (e.g. 1 MiB) as it's
+    /// written so the pattern is never buffered whole. Returns the CRC32C/SHA-256 digests over
+    /// what was written, to be checked later with `verify_pattern`.
+    pub async fn write_pattern(
+        &mut self,
+        volume: &Volume,
+        len: u64,
+        seed: u64,
+        chunk_size: usize,
+    ) -> Result<PatternDigests, Error> {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(node_target_path(&volume.spec.uuid.to_string()))?;
+
+        let mut rng = PatternRng::new(seed);
+        let mut digest = PatternDigest::new();
+        let mut buffer = vec![0u8; chunk_size];
+        let mut remaining = len;
+        while remaining > 0 {
+            let take = chunk_size.min(remaining as usize);
+            let chunk = &mut buffer[.. take];
+            rng.fill(chunk);
+            file.write_all(chunk)?;
+            digest.update(chunk);
+            remaining -= take as u64;
+        }
+
+        Ok(digest.finalize())
+    }
+    /// Re-read `len` bytes from `volume`'s published target path in `chunk_size`-sized pieces,
+    /// recomputing the same CRC32C/SHA-256 digests incrementally, and error if either doesn't
+    /// match the digests a prior `write_pattern` call returned.
+    pub async fn verify_pattern(
+        &mut self,
+        volume: &Volume,
+        len: u64,
+        expected: &PatternDigests,
+        chunk_size: usize,
+    ) -> Result<(), Error> {
+        let mut file = std::fs::File::open(node_target_path(&volume.spec.uuid.to_string()))?;
+
+        let mut digest = PatternDigest::new();
+        let mut buffer = vec![0u8; chunk_size];
+        let mut remaining = len;
+        while remaining > 0 {
+            let take = chunk_size.min(remaining as usize);
+            let chunk = &mut buffer[.. take];
+            file.read_exact(chunk)?;
+            digest.update(chunk);
+            remaining -= take as u64;
+        }
+
+        let actual = digest.finalize();
+        if actual != *expected {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "pattern mismatch for volume {}: expected {expected:?}, got {actual:?}",
+                    volume.spec.uuid
+                ),
+            )
+            .into());
+        }
+        Ok(())
+    }
 }
 
 const CSI_SOCKET: &str = "/var/tmp/csi-controller.sock";
 
+/// Retry policy for `CsiControllerClient`'s idempotent RPCs (create/delete snapshot, publish,
+/// unpublish) across a momentarily unavailable CSI socket, e.g. a plugin restart. Only
+/// `Unavailable`, `ResourceExhausted` and a timed-out call are retried; every other status is a
+/// real rejection and is returned immediately.
+#[derive(Debug, Clone, Copy)]
+pub struct CsiRetryPolicy {
+    /// Total number of attempts, including the first, before giving up.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled after each subsequent retry.
+    pub base_delay: Duration,
+    /// Per-attempt timeout; a call that exceeds this is treated like `DeadlineExceeded`.
+    pub call_timeout: Duration,
+}
+
+impl Default for CsiRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            call_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl CsiRetryPolicy {
+    fn is_retryable(status: &tonic::Status) -> bool {
+        matches!(
+            status.code(),
+            tonic::Code::Unavailable | tonic::Code::ResourceExhausted | tonic::Code::DeadlineExceeded
+        )
+    }
+}
+
+/// Progress of a volume's publish sequence, as tracked by `CsiControllerClient::ensure_published`/
+/// `ensure_unpublished`. `ControllerPublishing`/`NodePublishing` name the transient steps between
+/// resting states; neither method ever produces or returns them, since both only report the
+/// resting state they observe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishState {
+    Unpublished,
+    ControllerPublishing,
+    ControllerPublished,
+    NodePublishing,
+    NodePublished,
+}
+
+/// Which access type/capability `ensure_published`/`ensure_unpublished` should publish and stage
+/// the volume as, so the state machine doesn't need a block/fs variant of every transition.
+#[derive(Debug, Clone)]
+pub enum PublishKind {
+    Block,
+    Fs { fs_type: String },
+}
+
 /// Bundles the csi controller client.
 pub struct CsiControllerClient {
     csi: csi_driver::csi::controller_client::ControllerClient<tonic::transport::Channel>,
+    retry: CsiRetryPolicy,
+    /// Per-volume serialization locks so two tasks can never race `controller_publish_volume`
+    /// against `controller_unpublish_volume` (or either against `create_snapshot`) for the same
+    /// volume, while different volumes still run fully concurrently; see `serialized`.
+    volume_locks: std::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// Outstanding claim count keyed by `(volume_uuid, node_id)`, so `controller_unpublish_volume`
+    /// only detaches once every claim on that node has released instead of unconditionally
+    /// detaching on behalf of a single terminated/failed consumer and breaking the others.
+    publish_refs: std::sync::Mutex<HashMap<(String, String), u32>>,
 }
 
 impl CsiControllerClient {
@@ -1371,13 +2350,103 @@ impl CsiControllerClient {
     ) -> &mut csi_driver::csi::controller_client::ControllerClient<tonic::transport::Channel> {
         &mut self.csi
     }
+    /// Tune the retry policy the idempotent RPCs below use across plugin restarts/transient
+    /// failures.
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry: CsiRetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+    /// Get (creating if necessary) the serialization lock for `volume_uuid`.
+    fn volume_lock(&self, volume_uuid: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.volume_locks.lock().unwrap();
+        locks
+            .entry(volume_uuid.to_string())
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(())))
+            .clone()
+    }
+    /// Run `op` behind `volume_uuid`'s serialization lock, so every public method that touches a
+    /// volume funnels through the same per-volume sequence point instead of racing another call
+    /// for that volume.
+    async fn serialized<T>(&self, volume_uuid: &str, op: impl std::future::Future<Output = T>) -> T {
+        let lock = self.volume_lock(volume_uuid);
+        let _guard = lock.lock().await;
+        op.await
+    }
+    /// Current number of outstanding claims for `(volume_uuid, node_id)`, so callers can reason
+    /// about why `controller_unpublish_volume` did or didn't send a detach RPC.
+    pub fn publish_ref_count(&self, volume_uuid: &str, node_id: &str) -> u32 {
+        self.publish_refs
+            .lock()
+            .unwrap()
+            .get(&(volume_uuid.to_string(), node_id.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+    /// Record a successful `controller_publish_volume[_fs]` claim for `(volume_uuid, node_id)`.
+    fn incr_publish_ref(&self, volume_uuid: &str, node_id: &str) {
+        *self
+            .publish_refs
+            .lock()
+            .unwrap()
+            .entry((volume_uuid.to_string(), node_id.to_string()))
+            .or_insert(0) += 1;
+    }
+    /// Release one claim for `(volume_uuid, node_id)` - excluding it from the count before
+    /// returning - and report how many claims remain, so the caller only detaches when that's 0.
+    fn decr_publish_ref(&self, volume_uuid: &str, node_id: &str) -> u32 {
+        let mut refs = self.publish_refs.lock().unwrap();
+        let key = (volume_uuid.to_string(), node_id.to_string());
+        match refs.get_mut(&key) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                *count
+            }
+            Some(_) => {
+                refs.remove(&key);
+                0
+            }
+            None => 0,
+        }
+    }
+    /// Issue a request built fresh by `call` on every attempt (a clone of the underlying client,
+    /// so a dropped/reconnecting transport on one attempt doesn't poison the next), retrying per
+    /// `self.retry` on `Unavailable`/`ResourceExhausted`/deadline-exceeded and returning any other
+    /// error immediately.
+    async fn call_with_retry<Resp, Fut>(
+        &self,
+        mut call: impl FnMut(
+            csi_driver::csi::controller_client::ControllerClient<tonic::transport::Channel>,
+        ) -> Fut,
+    ) -> Result<Resp, Error>
+    where
+        Fut: std::future::Future<Output = Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        let mut delay = self.retry.base_delay;
+        for attempt in 1 ..= self.retry.max_attempts {
+            let outcome = tokio::time::timeout(self.retry.call_timeout, call(self.csi.clone())).await;
+            let status = match outcome {
+                Ok(Ok(response)) => return Ok(response.into_inner()),
+                Ok(Err(status)) => status,
+                Err(_elapsed) => tonic::Status::deadline_exceeded("csi call timed out"),
+            };
+            if attempt == self.retry.max_attempts || !CsiRetryPolicy::is_retryable(&status) {
+                return Err(status.into());
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+        unreachable!("loop above always returns by the final attempt")
+    }
 
-    /// Create the given snapshot.
+    /// Create the given snapshot. `secrets` (e.g. backend auth or an encryption key) is forwarded
+    /// to the plugin as the CSI `secrets` map rather than baked into `parameters`.
     pub async fn create_snapshot(
         &mut self,
         volume: &Volume,
         snap_uuid: &str,
         enable_fs_quiesce: bool,
+        secrets: HashMap<String, String>,
     ) -> Result<CreateSnapshotResponse, Error> {
         let mut map = HashMap::new();
         if enable_fs_quiesce {
@@ -1385,38 +2454,55 @@ impl CsiControllerClient {
         } else {
             map.insert("quiesceFs".to_string(), "none".to_string());
         }
+        let volume_uuid = volume.spec.uuid.to_string();
         let request = rpc::csi::CreateSnapshotRequest {
-            source_volume_id: volume.spec.uuid.to_string(),
+            source_volume_id: volume_uuid.clone(),
             name: snap_uuid.to_string(),
-            secrets: Default::default(),
+            secrets,
             parameters: map,
         };
-        let response = self.csi().create_snapshot(request).await?;
-        Ok(response.into_inner())
+        self.serialized(
+            &volume_uuid,
+            self.call_with_retry(|mut client| {
+                let request = request.clone();
+                async move { client.create_snapshot(request).await }
+            }),
+        )
+        .await
     }
 
-    /// Delete the given snapshot.
+    /// Delete the given snapshot, forwarding `secrets` as the CSI `secrets` map.
+    ///
+    /// Note: unlike the volume RPCs below, this isn't funnelled through `serialized` - it has no
+    /// volume UUID to key the lock on (only a snapshot id), and deleting a snapshot doesn't race
+    /// a volume's publish/unpublish sequence the way those do.
     pub async fn delete_snapshot(
         &mut self,
         snap_uuid: &str,
+        secrets: HashMap<String, String>,
     ) -> Result<rpc::csi::DeleteSnapshotResponse, Error> {
         let request = rpc::csi::DeleteSnapshotRequest {
             snapshot_id: snap_uuid.to_string(),
-            secrets: Default::default(),
+            secrets,
         };
-        let response = self.csi().delete_snapshot(request).await?;
-        Ok(response.into_inner())
+        self.call_with_retry(|mut client| {
+            let request = request.clone();
+            async move { client.delete_snapshot(request).await }
+        })
+        .await
     }
 
-    /// Controller Publish the given fs volume.
+    /// Controller Publish the given fs volume, forwarding `secrets` as the CSI `secrets` map.
     pub async fn controller_publish_volume_fs(
         &mut self,
         volume: &Volume,
         fs_type: &str,
         node_id: &str,
+        secrets: HashMap<String, String>,
     ) -> Result<rpc::csi::ControllerPublishVolumeResponse, Error> {
+        let volume_uuid = volume.spec.uuid.to_string();
         let request = rpc::csi::ControllerPublishVolumeRequest {
-            volume_id: volume.spec.uuid.to_string(),
+            volume_id: volume_uuid.clone(),
             volume_capability: Some(rpc::csi::VolumeCapability {
                 access_mode: Some(rpc::csi::volume_capability::AccessMode {
                     mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
@@ -1430,22 +2516,36 @@ impl CsiControllerClient {
                 )),
             }),
             readonly: false,
-            secrets: Default::default(),
+            secrets,
             volume_context: Default::default(),
             node_id: node_id.to_string(),
         };
-        let response = self.csi().controller_publish_volume(request).await?;
-        Ok(response.into_inner())
+        self.serialized(&volume_uuid, async {
+            let response = self
+                .call_with_retry(|mut client| {
+                    let request = request.clone();
+                    async move { client.controller_publish_volume(request).await }
+                })
+                .await?;
+            // Incrementing here, still inside the per-volume lock, is what stops a concurrent
+            // `controller_unpublish_volume` for another claim on this (volume, node) from
+            // checking the ref count before this claim is reflected in it.
+            self.incr_publish_ref(&volume_uuid, node_id);
+            Ok(response)
+        })
+        .await
     }
 
-    /// Controller Publish the given volume.
+    /// Controller Publish the given volume, forwarding `secrets` as the CSI `secrets` map.
     pub async fn controller_publish_volume(
         &mut self,
         volume: &Volume,
         node_id: &str,
+        secrets: HashMap<String, String>,
     ) -> Result<rpc::csi::ControllerPublishVolumeResponse, Error> {
+        let volume_uuid = volume.spec.uuid.to_string();
         let request = rpc::csi::ControllerPublishVolumeRequest {
-            volume_id: volume.spec.uuid.to_string(),
+            volume_id: volume_uuid.clone(),
             volume_capability: Some(rpc::csi::VolumeCapability {
                 access_mode: Some(rpc::csi::volume_capability::AccessMode {
                     mode: rpc::csi::volume_capability::access_mode::Mode::SingleNodeWriter as i32,
@@ -1455,26 +2555,196 @@ impl CsiControllerClient {
                 )),
             }),
             readonly: false,
-            secrets: Default::default(),
+            secrets,
             volume_context: Default::default(),
             node_id: node_id.to_string(),
         };
-        let response = self.csi().controller_publish_volume(request).await?;
-        Ok(response.into_inner())
+        self.serialized(&volume_uuid, async {
+            let response = self
+                .call_with_retry(|mut client| {
+                    let request = request.clone();
+                    async move { client.controller_publish_volume(request).await }
+                })
+                .await?;
+            // Incrementing here, still inside the per-volume lock, is what stops a concurrent
+            // `controller_unpublish_volume` for another claim on this (volume, node) from
+            // checking the ref count before this claim is reflected in it.
+            self.incr_publish_ref(&volume_uuid, node_id);
+            Ok(response)
+        })
+        .await
     }
 
-    /// Controller Unpublish the given volume.
+    /// Controller Unpublish the given volume, forwarding `secrets` as the CSI `secrets` map.
+    ///
+    /// Releases this caller's claim on `(volume, node_id)` and only issues the detach RPC once
+    /// no claims remain for that node, so unpublishing on behalf of one terminated/failed
+    /// consumer doesn't detach a volume still in use by others on the same node. Use
+    /// `publish_ref_count` to see why a detach was (or wasn't) sent.
+    ///
+    /// The ref-count check-and-decrement runs inside the same per-volume `serialized` critical
+    /// section as the detach RPC dispatch itself, so a publish racing to increment the same
+    /// (volume, node) ref count can't interleave between this checking the count and the RPC
+    /// actually firing.
     pub async fn controller_unpublish_volume(
         &mut self,
         volume: &Volume,
         node_id: &str,
+        secrets: HashMap<String, String>,
     ) -> Result<rpc::csi::ControllerUnpublishVolumeResponse, Error> {
+        let volume_uuid = volume.spec.uuid.to_string();
         let request = rpc::csi::ControllerUnpublishVolumeRequest {
-            volume_id: volume.spec.uuid.to_string(),
+            volume_id: volume_uuid.clone(),
             node_id: node_id.to_string(),
-            secrets: Default::default(),
+            secrets,
         };
-        let response = self.csi().controller_unpublish_volume(request).await?;
-        Ok(response.into_inner())
+        self.serialized(&volume_uuid, async {
+            if self.decr_publish_ref(&volume_uuid, node_id) > 0 {
+                return Ok(rpc::csi::ControllerUnpublishVolumeResponse::default());
+            }
+            self.call_with_retry(|mut client| {
+                let request = request.clone();
+                async move { client.controller_unpublish_volume(request).await }
+            })
+            .await
+        })
+        .await
+    }
+
+    /// Ask the plugin whether `volume` supports every capability in `capabilities`, so callers
+    /// can fail fast with a clear error instead of discovering an unsupported access mode/type
+    /// combination (e.g. `SingleNodeWriter`/`Block`) deep inside `controller_publish_volume`.
+    ///
+    /// Note: not funnelled through `serialized` - it's read-only and doesn't race the
+    /// publish/unpublish sequence, so it doesn't need a volume's serialization lock.
+    pub async fn validate_volume_capabilities(
+        &mut self,
+        volume: &Volume,
+        capabilities: Vec<rpc::csi::VolumeCapability>,
+        secrets: HashMap<String, String>,
+    ) -> Result<rpc::csi::ValidateVolumeCapabilitiesResponse, Error> {
+        let request = rpc::csi::ValidateVolumeCapabilitiesRequest {
+            volume_id: volume.spec.uuid.to_string(),
+            volume_capabilities: capabilities,
+            parameters: Default::default(),
+            volume_context: Default::default(),
+            secrets,
+        };
+        self.call_with_retry(|mut client| {
+            let request = request.clone();
+            async move { client.validate_volume_capabilities(request).await }
+        })
+        .await
+    }
+
+    /// Observe where `volume` currently sits in the publish sequence on `node_id`, from state a
+    /// retried/interrupted `ensure_published`/`ensure_unpublished` call can resume from: the
+    /// node-side target path existing means node-publish already completed, an outstanding
+    /// controller claim with no target means only the controller step has run, and otherwise
+    /// nothing has happened yet.
+    fn observed_publish_state(&self, volume_uuid: &str, node_id: &str) -> PublishState {
+        if std::path::Path::new(&node_target_path(volume_uuid)).exists() {
+            PublishState::NodePublished
+        } else if self.publish_ref_count(volume_uuid, node_id) > 0 {
+            PublishState::ControllerPublished
+        } else {
+            PublishState::Unpublished
+        }
+    }
+
+    /// Converge `volume` on `node_id` to `desired` (`PublishState::ControllerPublished` or
+    /// `PublishState::NodePublished`), performing only the RPCs the current observed state is
+    /// still missing. Safe to retry or interrupt: each transition is guarded by the per-volume
+    /// sequence (`serialized`) and, on the node side, by the idempotent directory/file checks
+    /// `node_stage_volume`/`node_publish_volume[_fs]` already perform, so resuming never
+    /// double-attaches.
+    pub async fn ensure_published(
+        &mut self,
+        volume: &Volume,
+        node: &mut CsiNodeClient,
+        node_id: &str,
+        kind: &PublishKind,
+        desired: PublishState,
+        secrets: HashMap<String, String>,
+        publish_context: HashMap<String, String>,
+    ) -> Result<PublishState, Error> {
+        let volume_uuid = volume.spec.uuid.to_string();
+        loop {
+            let state = self.observed_publish_state(&volume_uuid, node_id);
+            if state == desired || state == PublishState::NodePublished {
+                return Ok(state);
+            }
+            match state {
+                PublishState::Unpublished => {
+                    match kind {
+                        PublishKind::Block => {
+                            self.controller_publish_volume(volume, node_id, secrets.clone())
+                                .await?;
+                        }
+                        PublishKind::Fs { fs_type } => {
+                            self.controller_publish_volume_fs(
+                                volume,
+                                fs_type,
+                                node_id,
+                                secrets.clone(),
+                            )
+                            .await?;
+                        }
+                    }
+                }
+                PublishState::ControllerPublished => {
+                    match kind {
+                        PublishKind::Block => {
+                            node.node_stage_volume(volume, publish_context.clone())
+                                .await?;
+                            node.node_publish_volume(volume, publish_context.clone())
+                                .await?;
+                        }
+                        PublishKind::Fs { fs_type } => {
+                            node.node_stage_volume_fs(volume, fs_type, publish_context.clone())
+                                .await?;
+                            node.node_publish_volume_fs(volume, fs_type, publish_context.clone())
+                                .await?;
+                        }
+                    }
+                }
+                PublishState::ControllerPublishing
+                | PublishState::NodePublishing
+                | PublishState::NodePublished => unreachable!("handled above"),
+            }
+        }
+    }
+
+    /// Converge `volume` on `node_id` back to `desired` (`PublishState::ControllerPublished` or
+    /// `PublishState::Unpublished`), the reverse path of `ensure_published`, performing only the
+    /// RPCs the current observed state still needs undone.
+    pub async fn ensure_unpublished(
+        &mut self,
+        volume: &Volume,
+        node: &mut CsiNodeClient,
+        node_id: &str,
+        desired: PublishState,
+        secrets: HashMap<String, String>,
+    ) -> Result<PublishState, Error> {
+        let volume_uuid = volume.spec.uuid.to_string();
+        loop {
+            let state = self.observed_publish_state(&volume_uuid, node_id);
+            if state == desired || state == PublishState::Unpublished {
+                return Ok(state);
+            }
+            match state {
+                PublishState::NodePublished => {
+                    node.node_unpublish_volume(volume).await?;
+                    node.node_unstage_volume(volume).await?;
+                }
+                PublishState::ControllerPublished => {
+                    self.controller_unpublish_volume(volume, node_id, secrets.clone())
+                        .await?;
+                }
+                PublishState::ControllerPublishing
+                | PublishState::NodePublishing
+                | PublishState::Unpublished => unreachable!("handled above"),
+            }
+        }
     }
 }
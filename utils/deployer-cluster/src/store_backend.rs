@@ -0,0 +1,221 @@
+//! Pluggable coordination backend for cluster-lifecycle operations that, until now, hardcoded
+//! talking to etcd directly (`Cluster::remove_store_lock`'s bare
+//! `etcd_client::Client::connect(["[::]:2379"], None)`). `StoreBackend` factors out the
+//! connect/delete-by-prefix/read-lease operations those call sites actually use, so those
+//! harness calls can go to an alternate coordination backend instead of a fixed etcd endpoint.
+//! The control-plane services themselves are not pointed at the chosen backend, and nothing
+//! here calls `register_service`/`deregister_service` for the cluster's members.
+//!
+//! Note: the key layout (`StoreLeaseLockKey`) and the full `Store`/lease machinery this
+//! eventually bottoms out on live in `stor-port`, which isn't part of this checkout, so
+//! `EtcdStoreBackend` below is written against the same `etcd_client` surface
+//! `Cluster::remove_store_lock` already used, not introduced concepts.
+
+use async_trait::async_trait;
+
+/// Errors common to every `StoreBackend` implementation.
+#[derive(Debug, thiserror::Error)]
+pub enum StoreBackendError {
+    #[error("failed to connect to the store backend: {0}")]
+    Connection(String),
+    #[error("store operation failed: {0}")]
+    Backend(String),
+}
+
+/// The lease-lock and key-delete operations `Cluster::remove_store_lock`/`restart_core` need,
+/// factored out so tests can swap in a different coordination backend instead of a fixed etcd
+/// endpoint.
+#[async_trait]
+pub trait StoreBackend: Send + Sync {
+    /// Delete every key under `prefix` (e.g. a service's lease-lock key), the way
+    /// `Cluster::remove_store_lock` clears a crashed instance's lock before restarting it.
+    async fn delete_prefix(&self, prefix: &str) -> Result<(), StoreBackendError>;
+    /// Read back the lease value (if any) currently held at `key`, for tests asserting that a
+    /// restarted instance re-acquires its lock.
+    async fn read_lease(&self, key: &str) -> Result<Option<String>, StoreBackendError>;
+}
+
+/// Default backend: etcd, the same single fixed endpoint `Cluster::remove_store_lock` always
+/// talked to.
+#[derive(Clone)]
+pub struct EtcdStoreBackend {
+    endpoint: String,
+}
+
+impl EtcdStoreBackend {
+    /// A backend pointed at `endpoint` (e.g. `"[::]:2379"`).
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+        }
+    }
+}
+
+impl Default for EtcdStoreBackend {
+    fn default() -> Self {
+        Self::new("[::]:2379")
+    }
+}
+
+#[async_trait]
+impl StoreBackend for EtcdStoreBackend {
+    async fn delete_prefix(&self, prefix: &str) -> Result<(), StoreBackendError> {
+        let mut client = etcd_client::Client::connect([self.endpoint.as_str()], None)
+            .await
+            .map_err(|error| StoreBackendError::Connection(error.to_string()))?;
+        client
+            .delete(
+                prefix,
+                Some(etcd_client::DeleteOptions::new().with_prefix()),
+            )
+            .await
+            .map_err(|error| StoreBackendError::Backend(error.to_string()))?;
+        Ok(())
+    }
+
+    async fn read_lease(&self, key: &str) -> Result<Option<String>, StoreBackendError> {
+        let mut client = etcd_client::Client::connect([self.endpoint.as_str()], None)
+            .await
+            .map_err(|error| StoreBackendError::Connection(error.to_string()))?;
+        let resp = client
+            .get(key, None)
+            .await
+            .map_err(|error| StoreBackendError::Backend(error.to_string()))?;
+        Ok(resp
+            .kvs()
+            .first()
+            .map(|kv| String::from_utf8_lossy(kv.value()).into_owned()))
+    }
+}
+
+/// Consul-style backend: a KV store plus service catalog, so cluster members register/deregister
+/// through service discovery rather than dialling a fixed coordination endpoint. Talks to the
+/// plain HTTP KV (`/v1/kv/...`) and catalog (`/v1/agent/service/...`) APIs, so it needs no client
+/// crate beyond `reqwest`.
+#[derive(Clone)]
+pub struct ConsulStoreBackend {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl ConsulStoreBackend {
+    /// A backend pointed at `base_url` (e.g. `"http://localhost:8500"`).
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Register `service` in the catalog at `address`, so cluster peers can discover it instead
+    /// of dialling a fixed address.
+    pub async fn register_service(
+        &self,
+        service: &str,
+        address: &str,
+    ) -> Result<(), StoreBackendError> {
+        self.client
+            .put(format!("{}/v1/agent/service/register", self.base_url))
+            .json(&serde_json::json!({ "Name": service, "Address": address }))
+            .send()
+            .await
+            .map_err(|error| StoreBackendError::Connection(error.to_string()))?
+            .error_for_status()
+            .map_err(|error| StoreBackendError::Backend(error.to_string()))?;
+        Ok(())
+    }
+
+    /// Deregister `service`, the catalog equivalent of `delete_prefix` clearing an etcd
+    /// lease-lock.
+    pub async fn deregister_service(&self, service: &str) -> Result<(), StoreBackendError> {
+        self.client
+            .put(format!(
+                "{}/v1/agent/service/deregister/{service}",
+                self.base_url
+            ))
+            .send()
+            .await
+            .map_err(|error| StoreBackendError::Connection(error.to_string()))?
+            .error_for_status()
+            .map_err(|error| StoreBackendError::Backend(error.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StoreBackend for ConsulStoreBackend {
+    async fn delete_prefix(&self, prefix: &str) -> Result<(), StoreBackendError> {
+        self.client
+            .delete(format!("{}/v1/kv/{prefix}", self.base_url))
+            .query(&[("recurse", "true")])
+            .send()
+            .await
+            .map_err(|error| StoreBackendError::Connection(error.to_string()))?
+            .error_for_status()
+            .map_err(|error| StoreBackendError::Backend(error.to_string()))?;
+        Ok(())
+    }
+
+    async fn read_lease(&self, key: &str) -> Result<Option<String>, StoreBackendError> {
+        let resp = self
+            .client
+            .get(format!("{}/v1/kv/{key}", self.base_url))
+            .send()
+            .await
+            .map_err(|error| StoreBackendError::Connection(error.to_string()))?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let entries: Vec<ConsulKvEntry> = resp
+            .error_for_status()
+            .map_err(|error| StoreBackendError::Backend(error.to_string()))?
+            .json()
+            .await
+            .map_err(|error| StoreBackendError::Backend(error.to_string()))?;
+        Ok(entries.into_iter().next().and_then(|entry| entry.decode()))
+    }
+}
+
+/// One row of Consul's `GET /v1/kv/<key>` response; `Value` is base64-encoded per the Consul API.
+#[derive(serde::Deserialize)]
+struct ConsulKvEntry {
+    #[serde(rename = "Value")]
+    value: Option<String>,
+}
+
+impl ConsulKvEntry {
+    fn decode(self) -> Option<String> {
+        let encoded = self.value?;
+        let decoded = base64_decode(encoded.as_bytes())?;
+        String::from_utf8(decoded).ok()
+    }
+}
+
+/// Minimal standard-alphabet base64 decoder, just enough to read back a Consul KV value without
+/// pulling in a dedicated crate for one call site.
+fn base64_decode(input: &[u8]) -> Option<Vec<u8>> {
+    fn value(byte: u8) -> Option<u8> {
+        match byte {
+            b'A' ..= b'Z' => Some(byte - b'A'),
+            b'a' ..= b'z' => Some(byte - b'a' + 26),
+            b'0' ..= b'9' => Some(byte - b'0' + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let input: Vec<u8> = input.iter().copied().filter(|&b| b != b'=').collect();
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.chunks(4) {
+        let values: Vec<u8> = chunk.iter().map(|&b| value(b)).collect::<Option<_>>()?;
+        out.push((values[0] << 2) | (values.get(1).copied().unwrap_or(0) >> 4));
+        if values.len() > 2 {
+            out.push((values[1] << 4) | (values[2] >> 2));
+        }
+        if values.len() > 3 {
+            out.push((values[2] << 6) | values[3]);
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,187 @@
+//! `grpc-timeout` header encoding/decoding and a server-side deadline interceptor.
+//!
+//! `TimeoutOptions`/`Context`/`CoreClient` only ever drove *client-side* retry/backoff: the
+//! remaining time never left the client, so a slow server kept working on a request the caller
+//! had already given up on. `encode_grpc_timeout` turns a client's remaining `req_timeout` into
+//! the header value the gRPC wire protocol defines for this (`TE-ASCII-N` where `N` is up to 8
+//! digits and the final byte is a unit), and `decode_grpc_timeout`/`DeadlineLayer` let a server
+//! parse that value back out, arm a timer, and race it against the handler so work actually
+//! stops instead of running to completion after the caller has stopped listening.
+
+use std::time::Duration;
+
+/// One of the six units the `grpc-timeout` header may suffix its digits with, largest first.
+/// Each variant's `round_up` converts a `Duration` into a (rounded-up) count of that unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Hours,
+    Minutes,
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl Unit {
+    const ORDER: [Unit; 6] = [
+        Unit::Hours,
+        Unit::Minutes,
+        Unit::Seconds,
+        Unit::Millis,
+        Unit::Micros,
+        Unit::Nanos,
+    ];
+
+    fn suffix(self) -> char {
+        match self {
+            Unit::Hours => 'H',
+            Unit::Minutes => 'M',
+            Unit::Seconds => 'S',
+            Unit::Millis => 'm',
+            Unit::Micros => 'u',
+            Unit::Nanos => 'n',
+        }
+    }
+
+    fn from_suffix(suffix: char) -> Option<Self> {
+        Unit::ORDER.into_iter().find(|unit| unit.suffix() == suffix)
+    }
+
+    /// `duration` expressed as a whole count of this unit, rounded up so the encoded deadline
+    /// never under-reports (a caller should never see less time than it actually has left).
+    fn round_up(self, duration: Duration) -> u64 {
+        let nanos = duration.as_nanos();
+        let unit_nanos: u128 = match self {
+            Unit::Hours => 3_600_000_000_000,
+            Unit::Minutes => 60_000_000_000,
+            Unit::Seconds => 1_000_000_000,
+            Unit::Millis => 1_000_000,
+            Unit::Micros => 1_000,
+            Unit::Nanos => 1,
+        };
+        nanos.div_ceil(unit_nanos).min(u64::MAX as u128) as u64
+    }
+
+    fn to_duration(self, value: u64) -> Duration {
+        match self {
+            Unit::Hours => Duration::from_secs(value.saturating_mul(3600)),
+            Unit::Minutes => Duration::from_secs(value.saturating_mul(60)),
+            Unit::Seconds => Duration::from_secs(value),
+            Unit::Millis => Duration::from_millis(value),
+            Unit::Micros => Duration::from_micros(value),
+            Unit::Nanos => Duration::from_nanos(value),
+        }
+    }
+}
+
+/// Maximum number of digits the `grpc-timeout` header's value may carry, per the gRPC wire spec.
+const MAX_DIGITS: u32 = 8;
+const MAX_DIGIT_VALUE: u64 = 10u64.pow(MAX_DIGITS) - 1;
+
+/// Encode `remaining` as a `grpc-timeout` header value: up to 8 ASCII digits followed by a unit
+/// suffix, choosing the largest unit (hours down to nanoseconds) whose rounded-up digit count
+/// still fits in 8 digits, so the header is as coarse (and thus as easy to parse/compare) as
+/// possible without ever reporting less time than the caller actually has left. A `remaining`
+/// beyond 99,999,999 hours, which 8 digits cannot express, is capped at that value.
+pub fn encode_grpc_timeout(remaining: Duration) -> String {
+    for unit in Unit::ORDER {
+        let value = unit.round_up(remaining);
+        if value <= MAX_DIGIT_VALUE {
+            return format!("{value}{}", unit.suffix());
+        }
+    }
+    // Units are tried largest first, so this is reached only when even the hour count overflows
+    // 8 digits: cap at the largest encodable value rather than panic.
+    format!("{MAX_DIGIT_VALUE}{}", Unit::Hours.suffix())
+}
+
+/// Parse a `grpc-timeout` header value back into a `Duration`. Returns `None` for anything that
+/// isn't `1-8 ASCII digits followed by one of H/M/S/m/u/n`.
+pub fn decode_grpc_timeout(header: &str) -> Option<Duration> {
+    if header.is_empty() || header.len() > (MAX_DIGITS as usize + 1) || !header.is_ascii() {
+        return None;
+    }
+    let (digits, suffix) = header.split_at(header.len() - 1);
+    let unit = Unit::from_suffix(suffix.chars().next()?)?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: u64 = digits.parse().ok()?;
+    Some(unit.to_duration(value))
+}
+
+#[cfg(feature = "tonic")]
+mod interceptor {
+    use super::decode_grpc_timeout;
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tonic::{body::BoxBody, Status};
+    use tower::Service;
+
+    /// A tower layer that, when the inbound request carries a `grpc-timeout` header, races the
+    /// wrapped service against that deadline and maps expiry to `Status::cancelled`, dropping
+    /// whatever the handler was still doing. Requests without the header are unaffected.
+    #[derive(Debug, Clone, Default)]
+    pub struct DeadlineLayer;
+
+    impl<S> tower::Layer<S> for DeadlineLayer {
+        type Service = DeadlineService<S>;
+        fn layer(&self, inner: S) -> Self::Service {
+            DeadlineService { inner }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct DeadlineService<S> {
+        inner: S,
+    }
+
+    impl<S> Service<http::Request<BoxBody>> for DeadlineService<S>
+    where
+        S: Service<http::Request<BoxBody>, Response = http::Response<BoxBody>>
+            + Clone
+            + Send
+            + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>> + Send,
+    {
+        type Response = http::Response<BoxBody>;
+        type Error = S::Error;
+        type Future =
+            Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+            let deadline = req
+                .headers()
+                .get("grpc-timeout")
+                .and_then(|value| value.to_str().ok())
+                .and_then(decode_grpc_timeout);
+
+            let mut inner = self.inner.clone();
+            std::mem::swap(&mut self.inner, &mut inner);
+
+            Box::pin(async move {
+                let Some(deadline) = deadline else {
+                    return inner.call(req).await;
+                };
+
+                match tokio::time::timeout(deadline, inner.call(req)).await {
+                    Ok(result) => result,
+                    Err(_elapsed) => Ok(Status::cancelled(
+                        "request exceeded its grpc-timeout deadline",
+                    )
+                    .to_http()),
+                }
+            })
+        }
+    }
+}
+
+#[cfg(feature = "tonic")]
+pub use interceptor::{DeadlineLayer, DeadlineService};
@@ -0,0 +1,2 @@
+pub mod grpc_deadline;
+pub mod tracing_telemetry;
@@ -3,11 +3,71 @@ use opentelemetry::trace::TracerProvider;
 pub use opentelemetry::{global, trace};
 /// OpenTelemetry KeyVal for Processor Tags
 pub use opentelemetry::{Context, KeyValue};
+use opentelemetry::propagation::{Extractor, Injector};
 use opentelemetry_otlp::WithExportConfig;
-use opentelemetry_sdk::{propagation::TraceContextPropagator, trace as sdktrace, Resource};
+use opentelemetry_sdk::{
+    metrics::SdkMeterProvider, propagation::TraceContextPropagator, trace as sdktrace, Resource,
+};
+use std::collections::HashMap;
 use tracing::Level;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::{filter, layer::SubscriberExt, util::SubscriberInitExt, Layer, Registry};
 
+/// Adapts a plain string map so the W3C `TraceContextPropagator` can read/write `traceparent`
+/// and `tracestate` entries on it directly, without every caller needing its own glue.
+struct MetadataMap<'a>(&'a HashMap<String, String>);
+struct MetadataMapMut<'a>(&'a mut HashMap<String, String>);
+
+impl<'a> Extractor for MetadataMap<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|value| value.as_str())
+    }
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+impl<'a> Injector for MetadataMapMut<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+/// Inject the current span's context into `carrier` as `traceparent`/`tracestate` entries, so a
+/// remote call (e.g. a CSI node RPC) can continue the same distributed trace.
+pub fn inject_context(carrier: &mut HashMap<String, String>) {
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut MetadataMapMut(carrier));
+    });
+}
+
+/// Extract a remote `Context` from `traceparent`/`tracestate` entries previously injected by
+/// `inject_context`, for attaching to a span so both sides show up as one distributed trace.
+pub fn extract_context(carrier: &HashMap<String, String>) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&MetadataMap(carrier)))
+}
+
+static NEXT_REQUEST_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// Allocate a monotonic request id, unique for the life of the process. Used to correlate every
+/// log line belonging to one inbound RPC, the way a request-id middleware would for an HTTP
+/// server.
+pub fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Open a span for an inbound RPC, carrying a fresh request id plus the given fields. The span
+/// renders consistently across `FmtStyle::Compact`, `Pretty` and `Json`, since all three are
+/// backed by the same `tracing-subscriber` fmt layer and differ only in how they render a span's
+/// fields, not which fields are present. Entering the returned span makes every `tracing::*!`
+/// call made while it's active inherit `request_id` and `rpc` automatically.
+#[macro_export]
+macro_rules! rpc_span {
+    ($rpc:expr $(, $field:tt)* $(,)?) => {
+        tracing::info_span!("rpc", request_id = $crate::tracing_telemetry::next_request_id(), rpc = $rpc $(, $field)*)
+    };
+}
+
 /// Parse KeyValues from structopt's cmdline arguments
 pub fn parse_key_value(source: &str) -> Result<KeyValue, String> {
     match source.split_once('=') {
@@ -66,14 +126,64 @@ pub enum FmtStyle {
 
 const EVENT_BUS: &str = "mbus-events-target";
 
+/// Name of the standard OTEL env var carrying the collector endpoint.
+const OTEL_EXPORTER_OTLP_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+/// Name of the standard OTEL env var selecting the exporter protocol.
+const OTEL_EXPORTER_OTLP_PROTOCOL: &str = "OTEL_EXPORTER_OTLP_PROTOCOL";
+/// Name of the standard OTEL env var carrying extra exporter headers.
+const OTEL_EXPORTER_OTLP_HEADERS: &str = "OTEL_EXPORTER_OTLP_HEADERS";
+
+/// The OTLP wire protocol to use when exporting traces/metrics.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, strum_macros::EnumString, strum_macros::AsRefStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum OtlpProtocol {
+    /// OTLP over gRPC (tonic), the default, typically served on port 4317.
+    Grpc,
+    /// OTLP over HTTP with a binary protobuf body, typically served on port 4318.
+    #[strum(serialize = "http/protobuf")]
+    HttpBinary,
+}
+impl Default for OtlpProtocol {
+    fn default() -> Self {
+        Self::Grpc
+    }
+}
+impl OtlpProtocol {
+    fn default_port(&self) -> u16 {
+        match self {
+            Self::Grpc => 4317,
+            Self::HttpBinary => 4318,
+        }
+    }
+    /// Parse the protocol from the standard `OTEL_EXPORTER_OTLP_PROTOCOL` env var value.
+    fn from_env_var(value: &str) -> Option<Self> {
+        match value {
+            "grpc" => Some(Self::Grpc),
+            "http/protobuf" => Some(Self::HttpBinary),
+            _ => None,
+        }
+    }
+}
+
+/// Parse `key1=value1,key2=value2` headers, as used by `OTEL_EXPORTER_OTLP_HEADERS`.
+fn parse_otlp_headers(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
 /// Tracing telemetry builder.
 pub struct TracingTelemetry {
     writer: FmtLayer,
     style: FmtStyle,
     colours: bool,
-    jaeger: Option<String>,
+    otlp_endpoint: Option<String>,
+    otlp_protocol: Option<OtlpProtocol>,
     events_url: Option<url::Url>,
     tracing_tags: Vec<KeyValue>,
+    metrics: bool,
 }
 
 impl TracingTelemetry {
@@ -83,9 +193,11 @@ impl TracingTelemetry {
             writer: FmtLayer::Stdout,
             style: FmtStyle::Pretty,
             colours: true,
-            jaeger: None,
+            otlp_endpoint: None,
+            otlp_protocol: None,
             events_url: None,
             tracing_tags: Vec::new(),
+            metrics: false,
         }
     }
     /// Specify writer stream.
@@ -101,9 +213,28 @@ impl TracingTelemetry {
         TracingTelemetry { colours, ..self }
     }
 
-    /// Specify the jaeger endpoint, If any.
+    /// Specify the OTLP collector endpoint, if any.
+    pub fn with_otlp_endpoint(self, otlp_endpoint: Option<String>) -> TracingTelemetry {
+        TracingTelemetry {
+            otlp_endpoint,
+            ..self
+        }
+    }
+
+    /// Specify the OTLP wire protocol to use against `otlp_endpoint`.
+    pub fn with_otlp_protocol(self, otlp_protocol: OtlpProtocol) -> TracingTelemetry {
+        TracingTelemetry {
+            otlp_protocol: Some(otlp_protocol),
+            ..self
+        }
+    }
+
+    /// Specify the jaeger endpoint, if any.
+    #[deprecated(
+        note = "the native Jaeger exporter is deprecated upstream, use `with_otlp_endpoint` instead"
+    )]
     pub fn with_jaeger(self, jaeger: Option<String>) -> TracingTelemetry {
-        TracingTelemetry { jaeger, ..self }
+        self.with_otlp_endpoint(jaeger)
     }
 
     /// Specify the events url, If any.
@@ -119,6 +250,15 @@ impl TracingTelemetry {
         }
     }
 
+    /// Enable an OTLP metrics pipeline, exported to the same collector endpoint configured via
+    /// `with_otlp_endpoint`, so services can emit counters and histograms alongside their traces.
+    pub fn with_metrics(self, enabled: bool) -> TracingTelemetry {
+        TracingTelemetry {
+            metrics: enabled,
+            ..self
+        }
+    }
+
     /// Initialize the telemetry instance.
     pub fn init(self, service_name: &str) {
         let stdout = tracing_subscriber::fmt::layer()
@@ -127,7 +267,24 @@ impl TracingTelemetry {
         let stderr = tracing_subscriber::fmt::layer()
             .with_writer(std::io::stderr)
             .with_ansi(self.colours);
-        let tracer = self.jaeger.map(|mut jaeger| {
+        let otlp_endpoint = self
+            .otlp_endpoint
+            .clone()
+            .or_else(|| std::env::var(OTEL_EXPORTER_OTLP_ENDPOINT).ok());
+        let otlp_protocol = self.otlp_protocol.unwrap_or_else(|| {
+            std::env::var(OTEL_EXPORTER_OTLP_PROTOCOL)
+                .ok()
+                .and_then(|value| OtlpProtocol::from_env_var(&value))
+                .unwrap_or_default()
+        });
+        let otlp_headers = std::env::var(OTEL_EXPORTER_OTLP_HEADERS)
+            .ok()
+            .map(|value| parse_otlp_headers(&value))
+            .unwrap_or_default();
+
+        let metrics_endpoint = otlp_endpoint.clone().filter(|_| self.metrics);
+        let metrics_tags = self.tracing_tags.clone();
+        let tracer = otlp_endpoint.map(|mut endpoint| {
             let svc_name = vec![KeyValue::new(
                 opentelemetry_semantic_conventions::resource::SERVICE_NAME,
                 service_name.to_owned(),
@@ -139,29 +296,46 @@ impl TracingTelemetry {
                 acc
             });
 
-            if !jaeger.starts_with("http") {
-                jaeger = format!("http://{jaeger}");
+            if !endpoint.starts_with("http") {
+                endpoint = format!("http://{endpoint}");
             }
             // todo: init should return an error
-            let jaeger = match url::Url::parse(&jaeger).ok() {
+            let endpoint = match url::Url::parse(&endpoint).ok() {
                 Some(mut url) => {
                     if url.port().is_none() {
-                        url.set_port(Some(4317)).ok();
+                        url.set_port(Some(otlp_protocol.default_port())).ok();
                     }
                     url.to_string()
                 }
-                None => jaeger,
+                None => endpoint,
             };
 
             set_jaeger_env();
             global::set_text_map_propagator(TraceContextPropagator::new());
+            let grpc_exporter = opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint.clone());
+            let exporter = match otlp_protocol {
+                OtlpProtocol::Grpc => grpc_exporter,
+                OtlpProtocol::HttpBinary => {
+                    return opentelemetry_otlp::new_pipeline()
+                        .tracing()
+                        .with_exporter(
+                            opentelemetry_otlp::new_exporter()
+                                .http()
+                                .with_endpoint(endpoint)
+                                .with_headers(otlp_headers.iter().cloned().collect()),
+                        )
+                        .with_trace_config(
+                            sdktrace::Config::default().with_resource(Resource::new(tracing_tags)),
+                        )
+                        .install_batch(opentelemetry_sdk::runtime::TokioCurrentThread)
+                        .expect("Should be able to initialise the exporter");
+                }
+            };
             opentelemetry_otlp::new_pipeline()
                 .tracing()
-                .with_exporter(
-                    opentelemetry_otlp::new_exporter()
-                        .tonic()
-                        .with_endpoint(jaeger),
-                )
+                .with_exporter(exporter)
                 .with_trace_config(
                     sdktrace::Config::default().with_resource(Resource::new(tracing_tags)),
                 )
@@ -174,6 +348,10 @@ impl TracingTelemetry {
             tracer_provider.tracer("tracing-otel-subscriber")
         });
 
+        if let Some(endpoint) = metrics_endpoint {
+            init_meter_provider(&endpoint, service_name, &metrics_tags);
+        }
+
         // Get the optional eventing layer.
         let events_layer = self.events_url.map(|url| {
             let target = filter::Targets::new().with_target(EVENT_BUS, Level::INFO);
@@ -258,3 +436,100 @@ pub fn flush_traces() {
         trace_provider.shutdown().ok();
     }
 }
+
+/// Mirrors `TRACER_PROVIDER`: we need to hang on to the meter provider to force flush it, since
+/// it also lives in a global context.
+static METER_PROVIDER: std::sync::OnceLock<SdkMeterProvider> = std::sync::OnceLock::new();
+
+/// Installs a meter provider exporting over OTLP to the same collector endpoint used for
+/// traces, so services can emit counters and histograms (CSI publish/unpublish latency,
+/// `SwitchOverSpec` retry counts, device-find durations, ...) to the same place.
+fn init_meter_provider(endpoint: &str, service_name: &str, tracing_tags: &[KeyValue]) {
+    let svc_name = vec![KeyValue::new(
+        opentelemetry_semantic_conventions::resource::SERVICE_NAME,
+        service_name.to_owned(),
+    )];
+    let tags = tracing_tags.iter().cloned().fold(svc_name, |mut acc, kv| {
+        if !acc.iter().any(|acc| acc.key == kv.key) {
+            acc.push(kv);
+        }
+        acc
+    });
+
+    let endpoint = if endpoint.starts_with("http") {
+        endpoint.to_string()
+    } else {
+        format!("http://{endpoint}")
+    };
+
+    let Ok(provider) = opentelemetry_otlp::new_pipeline()
+        .metrics(opentelemetry_sdk::runtime::TokioCurrentThread)
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_resource(Resource::new(tags))
+        .build()
+    else {
+        tracing::warn!("Failed to initialise the OTLP metrics exporter");
+        return;
+    };
+    global::set_meter_provider(provider.clone());
+    METER_PROVIDER.get_or_init(|| provider);
+}
+
+/// Flush the metrics from the provider.
+pub fn flush_metrics() {
+    if let Some(meter_provider) = METER_PROVIDER.get() {
+        meter_provider.force_flush().ok();
+    }
+}
+
+/// Record a timing (in seconds) against a histogram with the given instrument name, without
+/// call sites having to pull in the full OTel meter API. A no-op if metrics haven't been
+/// enabled via [`TracingTelemetry::with_metrics`]. Append a `&[KeyValue, ...]` slice to label the
+/// recording (e.g. by `ResourceKind`).
+#[macro_export]
+macro_rules! record_timing {
+    ($instrument:expr, $seconds:expr) => {
+        $crate::record_timing!($instrument, $seconds, &[])
+    };
+    ($instrument:expr, $seconds:expr, $labels:expr) => {{
+        $crate::tracing_telemetry::global::meter("control-plane")
+            .f64_histogram($instrument)
+            .init()
+            .record($seconds, $labels);
+    }};
+}
+
+/// Add `$value` to a monotonic counter with the given instrument name. Same shape as
+/// [`record_timing`] - a bare call records unlabelled, or pass a `&[KeyValue, ...]` slice.
+#[macro_export]
+macro_rules! record_count {
+    ($instrument:expr, $value:expr) => {
+        $crate::record_count!($instrument, $value, &[])
+    };
+    ($instrument:expr, $value:expr, $labels:expr) => {{
+        $crate::tracing_telemetry::global::meter("control-plane")
+            .u64_counter($instrument)
+            .init()
+            .add($value, $labels);
+    }};
+}
+
+/// Adjust a gauge-like instrument (an OTel `UpDownCounter`, the closest instrument to a settable
+/// gauge without an observable callback) by `$delta` - positive to bump the current count,
+/// negative to bring it back down. Same shape as [`record_timing`].
+#[macro_export]
+macro_rules! record_gauge {
+    ($instrument:expr, $delta:expr) => {
+        $crate::record_gauge!($instrument, $delta, &[])
+    };
+    ($instrument:expr, $delta:expr, $labels:expr) => {{
+        $crate::tracing_telemetry::global::meter("control-plane")
+            .i64_up_down_counter($instrument)
+            .init()
+            .add($delta, $labels);
+    }};
+}